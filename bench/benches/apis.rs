@@ -2,7 +2,7 @@
 
 use std::time::Instant;
 
-use compact_str::CompactString;
+use compact_str::{sort_compact_strings, CompactString};
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
 static VERY_LONG_STR: &str = include_str!("../data/moby10b.txt");
@@ -178,6 +178,72 @@ fn compact_string_from_string_heap_long(c: &mut Criterion) {
     });
 }
 
+static INTS: &[&str] = &["7", "42", "-1234", "123456789", "18446744073709551615"];
+static FLOATS: &[&str] = &["1.5", "-0.25", "3.14159", "12345.678", "-2.5e-3", "0.1"];
+
+fn compact_string_parse_int(c: &mut Criterion) {
+    let compacts: Vec<CompactString> = INTS.iter().copied().map(CompactString::new).collect();
+    c.bench_function("parse int", |b| {
+        b.iter(|| {
+            for compact in &compacts {
+                let _ = black_box(black_box(compact).parse_int::<i128>());
+            }
+        })
+    });
+}
+
+fn compact_string_parse_float(c: &mut Criterion) {
+    let compacts: Vec<CompactString> = FLOATS.iter().copied().map(CompactString::new).collect();
+    c.bench_function("parse float", |b| {
+        b.iter(|| {
+            for compact in &compacts {
+                let _ = black_box(black_box(compact).parse_float::<f64>());
+            }
+        })
+    });
+}
+
+/// The lines of Moby Dick, a mix of inline and heap allocated strings.
+fn lines() -> impl Iterator<Item = &'static str> {
+    VERY_LONG_STR.lines().take(20_000)
+}
+
+fn compact_string_sort_compact_strings(c: &mut Criterion) {
+    let compacts: Vec<CompactString> = lines().map(CompactString::new).collect();
+    c.bench_function("sort_compact_strings", |b| {
+        b.iter_custom(|iters| {
+            let mut durations = vec![];
+            for _ in 0..iters {
+                let mut compacts = compacts.clone();
+
+                // only time the sort, not the clone
+                let start = Instant::now();
+                sort_compact_strings(black_box(&mut compacts));
+                durations.push(start.elapsed());
+            }
+            durations.into_iter().sum()
+        });
+    });
+}
+
+fn compact_string_slice_sort(c: &mut Criterion) {
+    let compacts: Vec<CompactString> = lines().map(CompactString::new).collect();
+    c.bench_function("slice sort compact strings", |b| {
+        b.iter_custom(|iters| {
+            let mut durations = vec![];
+            for _ in 0..iters {
+                let mut compacts = compacts.clone();
+
+                // only time the sort, not the clone
+                let start = Instant::now();
+                black_box(&mut compacts).sort();
+                durations.push(start.elapsed());
+            }
+            durations.into_iter().sum()
+        });
+    });
+}
+
 fn std_string_short_length(c: &mut Criterion) {
     let word = "i am short";
     let string = String::from(word);
@@ -210,6 +276,46 @@ fn std_string_very_long_length(c: &mut Criterion) {
     });
 }
 
+fn std_str_parse_int(c: &mut Criterion) {
+    let strings: Vec<String> = INTS.iter().copied().map(String::from).collect();
+    c.bench_function("std parse int", |b| {
+        b.iter(|| {
+            for string in &strings {
+                let _ = black_box(black_box(string).parse::<i128>());
+            }
+        })
+    });
+}
+
+fn std_str_parse_float(c: &mut Criterion) {
+    let strings: Vec<String> = FLOATS.iter().copied().map(String::from).collect();
+    c.bench_function("std parse float", |b| {
+        b.iter(|| {
+            for string in &strings {
+                let _ = black_box(black_box(string).parse::<f64>());
+            }
+        })
+    });
+}
+
+fn std_str_slice_sort(c: &mut Criterion) {
+    let strings: Vec<String> = lines().map(String::from).collect();
+    c.bench_function("std slice sort strings", |b| {
+        b.iter_custom(|iters| {
+            let mut durations = vec![];
+            for _ in 0..iters {
+                let mut strings = strings.clone();
+
+                // only time the sort, not the clone
+                let start = Instant::now();
+                black_box(&mut strings).sort();
+                durations.push(start.elapsed());
+            }
+            durations.into_iter().sum()
+        });
+    });
+}
+
 fn std_str_reserve_small(c: &mut Criterion) {
     c.bench_function("std str reserve small", |b| {
         b.iter(|| {
@@ -287,7 +393,11 @@ criterion_group!(
     compact_string_extend_chars_heap_20,
     compact_string_from_string_inline,
     compact_string_from_string_heap,
-    compact_string_from_string_heap_long
+    compact_string_from_string_heap_long,
+    compact_string_parse_int,
+    compact_string_parse_float,
+    compact_string_sort_compact_strings,
+    compact_string_slice_sort
 );
 criterion_group!(
     std_string,
@@ -301,6 +411,9 @@ criterion_group!(
     std_str_extend_chars_empty,
     std_str_extend_chars_short,
     std_str_str_extend_chars_20,
+    std_str_parse_int,
+    std_str_parse_float,
+    std_str_slice_sort,
 );
 
 criterion_main!(compact_str, std_string);
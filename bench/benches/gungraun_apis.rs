@@ -1,9 +1,11 @@
 //! Gungraun (Callgrind) instruction-count benchmarks for various APIs, making sure
 //! `CompactString` is at least no slower than `String`.
 
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::hint::black_box;
 
-use compact_str::CompactString;
+use compact_str::{CompactString, FastHasher};
 use gungraun::{library_benchmark, library_benchmark_group, main};
 
 static VERY_LONG_STR: &str = include_str!("../data/moby10b.txt");
@@ -16,6 +18,14 @@ fn make_string(word: &str) -> String {
     String::from(word)
 }
 
+fn make_compact_pair(a: &str, b: &str) -> (CompactString, CompactString) {
+    (CompactString::new(a), CompactString::new(b))
+}
+
+fn make_string_pair(a: &str, b: &str) -> (String, String) {
+    (String::from(a), String::from(b))
+}
+
 // --- CompactString --------------------------------------------------------------------------
 
 #[library_benchmark(setup = make_compact)]
@@ -70,6 +80,33 @@ fn compact_string_from_string(word: String) -> CompactString {
     black_box(CompactString::from(black_box(word)))
 }
 
+#[library_benchmark(setup = make_compact_pair)]
+#[bench::inline_equal("user_id_1234", "user_id_1234")]
+#[bench::inline_different("user_id_1234", "user_id_1235")]
+#[bench::heap_equal(
+    "I am a very long string that will get allocated on the heap",
+    "I am a very long string that will get allocated on the heap"
+)]
+fn compact_string_eq((a, b): (CompactString, CompactString)) -> bool {
+    black_box(black_box(&a) == black_box(&b))
+}
+
+#[library_benchmark(setup = make_compact)]
+#[bench::inline("user_id_1234")]
+#[bench::heap("I am a very long string that will get allocated on the heap")]
+fn compact_string_hash(compact: CompactString) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    black_box(&compact).hash(&mut hasher);
+    black_box(hasher.finish())
+}
+
+#[library_benchmark(setup = make_compact)]
+#[bench::inline("user_id_1234")]
+#[bench::heap("I am a very long string that will get allocated on the heap")]
+fn compact_string_fast_hash(compact: CompactString) -> u64 {
+    black_box(black_box(&compact).fast_hash())
+}
+
 // --- std::String ----------------------------------------------------------------------------
 
 #[library_benchmark(setup = make_string)]
@@ -115,6 +152,35 @@ fn std_str_extend_chars(base: &str, count: usize) -> String {
     black_box(std_str)
 }
 
+#[library_benchmark(setup = make_string_pair)]
+#[bench::short_equal("user_id_1234", "user_id_1234")]
+#[bench::short_different("user_id_1234", "user_id_1235")]
+#[bench::long_equal(
+    "I am a very long string that will get allocated on the heap",
+    "I am a very long string that will get allocated on the heap"
+)]
+fn std_str_eq((a, b): (String, String)) -> bool {
+    black_box(black_box(&a) == black_box(&b))
+}
+
+#[library_benchmark(setup = make_string)]
+#[bench::short("user_id_1234")]
+#[bench::long("I am a very long string that will get allocated on the heap")]
+fn std_str_hash(std_str: String) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    black_box(&std_str).hash(&mut hasher);
+    black_box(hasher.finish())
+}
+
+#[library_benchmark(setup = make_string)]
+#[bench::short("user_id_1234")]
+#[bench::long("I am a very long string that will get allocated on the heap")]
+fn std_str_fast_hash(std_str: String) -> u64 {
+    let mut hasher = FastHasher::default();
+    black_box(&std_str).hash(&mut hasher);
+    black_box(hasher.finish())
+}
+
 library_benchmark_group!(
     name = compact_str_benches,
     benchmarks = [
@@ -124,6 +190,9 @@ library_benchmark_group!(
         compact_string_clone_and_modify,
         compact_string_extend_chars,
         compact_string_from_string,
+        compact_string_eq,
+        compact_string_hash,
+        compact_string_fast_hash,
     ]
 );
 
@@ -135,6 +204,9 @@ library_benchmark_group!(
         std_str_clone,
         std_str_clone_and_modify,
         std_str_extend_chars,
+        std_str_eq,
+        std_str_hash,
+        std_str_fast_hash,
     ]
 );
 
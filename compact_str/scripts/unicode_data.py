@@ -0,0 +1,245 @@
+#!/usr/bin/env python3
+"""Generates the combining mark, soft dotted and case folding tables in `src/unicode_data.rs`.
+
+Download `UnicodeData.txt`, `SpecialCasing.txt`, `CaseFolding.txt` and `PropList.txt` from
+<https://www.unicode.org/Public/17.0.0/ucd/> into a directory, then run
+
+    python3 scripts/unicode_data.py path/to/ucd > tables.rs
+
+and replace the modules from `combining_mark` onwards in `src/unicode_data.rs` with the output.
+
+The boolean tables use the same skip list encoding as `core`, which `skip_search` decodes. The
+case folding table only contains the characters whose full case folding differs from
+`char::to_lowercase`, so it has to be regenerated for the Unicode version of `core` too.
+"""
+
+import bisect
+import os
+import sys
+
+MAX_CODEPOINT = 0x110000
+
+
+def parse_codepoints(field):
+    """Parses a single codepoint, or a `start..end` range, into a range."""
+    start, _, end = field.strip().partition("..")
+    return range(int(start, 16), int(end or start, 16) + 1)
+
+
+def parse_chars(field):
+    return "".join(chr(int(cp, 16)) for cp in field.split())
+
+
+def data_lines(path):
+    """Yields the fields of every line that isn't empty or a comment."""
+    with open(path, encoding="utf-8") as file:
+        for line in file:
+            line = line.split("#", 1)[0].strip()
+            if line:
+                yield [field.strip() for field in line.split(";")]
+
+
+def read_ucd(ucd):
+    combining_class = {}
+    lowercase = {}
+    for fields in data_lines(os.path.join(ucd, "UnicodeData.txt")):
+        cp = int(fields[0], 16)
+        if fields[3] != "0":
+            combining_class[cp] = int(fields[3])
+        if fields[13]:
+            lowercase[cp] = chr(int(fields[13], 16))
+
+    # `char::to_lowercase` uses the unconditional mappings from `SpecialCasing.txt`
+    for fields in data_lines(os.path.join(ucd, "SpecialCasing.txt")):
+        if len(fields) == 5 or not fields[4]:
+            lowercase[int(fields[0], 16)] = parse_chars(fields[1])
+
+    # the full case folding is the `C` and `F` mappings
+    folding = {}
+    for fields in data_lines(os.path.join(ucd, "CaseFolding.txt")):
+        if fields[1] in ("C", "F"):
+            folding[int(fields[0], 16)] = parse_chars(fields[2])
+
+    soft_dotted = set()
+    for fields in data_lines(os.path.join(ucd, "PropList.txt")):
+        if fields[1] == "Soft_Dotted":
+            soft_dotted.update(parse_codepoints(fields[0]))
+
+    return combining_class, lowercase, folding, soft_dotted
+
+
+def ranges_of(members):
+    """Returns the sorted, half-open ranges of codepoints in `members`."""
+    ranges = []
+    for cp in sorted(members):
+        if ranges and ranges[-1][1] == cp:
+            ranges[-1][1] = cp + 1
+        else:
+            ranges.append([cp, cp + 1])
+    return ranges
+
+
+def encode(ranges):
+    """Encodes `ranges` as the `SHORT_OFFSET_RUNS` and `OFFSETS` of a skip list.
+
+    The offsets alternate between the lengths of the gaps and of the ranges. Any offset that
+    doesn't fit in a byte starts a new run, whose header stores the index of its first offset in
+    the top 11 bits and the codepoint it starts at in the low 21 bits.
+    """
+    offsets = []
+    prev = 0
+    for start, end in ranges:
+        offsets += [start - prev, end - start]
+        prev = end
+    # the last offset always closes the final run, past the largest codepoint
+    offsets.append(MAX_CODEPOINT - prev)
+
+    runs, coded = [], []
+    prefix_sum = 0
+    i = 0
+    while i < len(offsets):
+        start = len(coded)
+        while i < len(offsets):
+            offset = offsets[i]
+            i += 1
+            prefix_sum += offset
+            if offset > 255 or i == len(offsets):
+                runs.append((start << 21) | prefix_sum)
+                coded.append(0)
+                break
+            coded.append(offset)
+    assert len(coded) < 1 << 11, "too many offsets for the run headers"
+    return runs, coded
+
+
+def decode(needle, runs, coded):
+    """A port of `skip_search`, to check the encoding."""
+    keys = [(run << 11) & 0xFFFFFFFF for run in runs]
+    key = (needle << 11) & 0xFFFFFFFF
+    idx = bisect.bisect_left(keys, key)
+    last_idx = idx + 1 if idx < len(keys) and keys[idx] == key else idx
+
+    offset_idx = runs[last_idx] >> 21
+    if last_idx + 1 < len(runs):
+        length = (runs[last_idx + 1] >> 21) - offset_idx
+    else:
+        length = len(coded) - offset_idx
+    prev = runs[last_idx - 1] & ((1 << 21) - 1) if last_idx > 0 else 0
+
+    total = needle - prev
+    prefix_sum = 0
+    for _ in range(length - 1):
+        prefix_sum += coded[offset_idx]
+        if prefix_sum > total:
+            break
+        offset_idx += 1
+    return offset_idx % 2 == 1
+
+
+def wrap(values, indent=8, width=100):
+    lines, line = [], " " * indent
+    for value in values:
+        piece = f"{value},"
+        if len(line) + len(piece) + 1 > width:
+            lines.append(line.rstrip())
+            line = " " * indent
+        line += piece + " "
+    lines.append(line.rstrip())
+    return "\n".join(lines)
+
+
+def skip_list_module(name, members):
+    runs, coded = encode(ranges_of(members))
+    for cp in range(MAX_CODEPOINT):
+        assert decode(cp, runs, coded) == (cp in members), (name, hex(cp))
+    return f"""#[rustfmt::skip]
+pub(crate) mod {name} {{
+    static SHORT_OFFSET_RUNS: [u32; {len(runs)}] = [
+{wrap(runs)}
+    ];
+    static OFFSETS: [u8; {len(coded)}] = [
+{wrap(coded)}
+    ];
+    #[inline(always)]
+    pub(crate) fn lookup(c: char) -> bool {{
+        super::skip_search(
+            c as u32,
+            &SHORT_OFFSET_RUNS,
+            &OFFSETS,
+        )
+    }}
+}}
+"""
+
+
+def escape(text):
+    return "".join(c if c.isascii() and c.isalnum() else f"\\u{{{ord(c):x}}}" for c in text)
+
+
+def case_folding_module(lowercase, folding):
+    cherokee = range(0x13A0, 0x13F6)
+    exceptions = {}
+    for cp in range(MAX_CODEPOINT):
+        fold = folding.get(cp, chr(cp))
+        if fold != lowercase.get(cp, chr(cp)):
+            exceptions[cp] = fold
+
+    # both cases of Cherokee fold to the capital letters, which `lookup` computes instead
+    for cp in cherokee:
+        assert exceptions.pop(cp) == chr(cp), hex(cp)
+    for cp in range(0x13F8, 0x13FE):
+        assert exceptions.pop(cp) == chr(cp - 0x13F8 + 0x13F0), hex(cp)
+    for cp in range(0xAB70, 0xABC0):
+        assert exceptions.pop(cp) == chr(cp - 0xAB70 + 0x13A0), hex(cp)
+    assert min(exceptions) == 0xB5, "`lookup` skips everything below U+00B5"
+
+    letters = "".join(f"\\u{{{cp:x}}}" for cp in cherokee)
+    letters = "\\\n        ".join(letters[i : i + 88] for i in range(0, len(letters), 88))
+    rows = "\n".join(f"        ('\\u{{{cp:x}}}', \"{escape(fold)}\")," for cp, fold in sorted(exceptions.items()))
+    return f"""#[rustfmt::skip]
+pub(crate) mod case_folding {{
+    /// The Cherokee capital letters U+13A0 to U+13F5, which both cases fold to.
+    static CHEROKEE: &str = "\\
+        {letters}";
+    /// The full (`C` and `F`) mappings from `CaseFolding.txt` that differ from
+    /// `char::to_lowercase`, sorted by character. Cherokee is handled separately.
+    static FOLDINGS: [(char, &str); {len(exceptions)}] = [
+{rows}
+    ];
+    /// Returns the full case folding of `c`, if it's different from `c.to_lowercase()`.
+    #[inline]
+    pub(crate) fn lookup(c: char) -> Option<&'static str> {{
+        // every Cherokee capital letter is 3 bytes long in UTF-8
+        let cherokee = |i: u32| Some(&CHEROKEE[i as usize * 3..][..3]);
+        match c as u32 {{
+            0..=0xb4 => None,
+            c @ 0x13a0..=0x13f5 => cherokee(c - 0x13a0),
+            c @ 0x13f8..=0x13fd => cherokee(c - 0x13f8 + 0x50),
+            c @ 0xab70..=0xabbf => cherokee(c - 0xab70),
+            _ => FOLDINGS
+                .binary_search_by_key(&c, |(from, _)| *from)
+                .ok()
+                .map(|i| FOLDINGS[i].1),
+        }}
+    }}
+}}
+"""
+
+
+def main():
+    if len(sys.argv) != 2:
+        sys.exit(f"usage: {sys.argv[0]} <directory with the UCD files>")
+    combining_class, lowercase, folding, soft_dotted = read_ucd(sys.argv[1])
+    modules = [
+        skip_list_module("combining_mark", set(combining_class)),
+        skip_list_module(
+            "combining_above", {cp for cp, ccc in combining_class.items() if ccc == 230}
+        ),
+        skip_list_module("soft_dotted", soft_dotted),
+        case_folding_module(lowercase, folding),
+    ]
+    print("\n".join(modules), end="")
+
+
+if __name__ == "__main__":
+    main()
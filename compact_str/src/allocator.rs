@@ -0,0 +1,101 @@
+use core::alloc::Layout;
+use core::fmt;
+use core::ptr::{self, NonNull};
+
+use crate::ReserveError;
+
+/// An allocator that a [`CompactString`](crate::CompactString) can store its heap buffer in.
+///
+/// This is a stable version of the unstable `core::alloc::Allocator` trait, with the methods a
+/// string needs. With the `allocator-api2` feature enabled it's implemented for every
+/// [`allocator_api2::alloc::Allocator`](https://docs.rs/allocator-api2) that is also [`Send`] and
+/// [`Sync`].
+///
+/// A [`CompactString`](crate::CompactString) holds on to its allocator through an
+/// [`Arc`](alloc::sync::Arc), so an allocator doesn't need to be `'static`, and gets dropped once
+/// the last string it allocated is. See
+/// [`CompactString::new_in`](crate::CompactString::new_in) for how to use it.
+///
+/// # Safety
+/// * Memory returned by [`Allocator::allocate`] must be valid for reads and writes of
+///   `layout.size()` bytes, aligned to `layout.align()`, and stay valid until it's passed to
+///   [`Allocator::deallocate`], or successfully passed to [`Allocator::grow`] or
+///   [`Allocator::shrink`].
+/// * Memory allocated by one [`Allocator`] can be deallocated by the same [`Allocator`], from any
+///   thread.
+pub unsafe trait Allocator: Send + Sync {
+    /// Allocates a block of memory that fits `layout`.
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError>;
+
+    /// Deallocates the memory referenced by `ptr`.
+    ///
+    /// # Safety
+    /// * `ptr` must have been allocated by this allocator, with `layout`
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+
+    /// Grows the memory referenced by `ptr` to fit `new_layout`, returning the new block. The
+    /// contents of the old block are copied into the new one.
+    ///
+    /// # Safety
+    /// * `ptr` must have been allocated by this allocator, with `old_layout`
+    /// * `new_layout.size()` must be greater than or equal to `old_layout.size()`
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        let new_ptr = self.allocate(new_layout)?;
+        ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr(), old_layout.size());
+        self.deallocate(ptr, old_layout);
+        Ok(new_ptr)
+    }
+
+    /// Shrinks the memory referenced by `ptr` to fit `new_layout`, returning the new block. The
+    /// contents of the old block are copied into the new one, up to `new_layout.size()`.
+    ///
+    /// # Safety
+    /// * `ptr` must have been allocated by this allocator, with `old_layout`
+    /// * `new_layout.size()` must be less than or equal to `old_layout.size()`
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        let new_ptr = self.allocate(new_layout)?;
+        ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr(), new_layout.size());
+        self.deallocate(ptr, old_layout);
+        Ok(new_ptr)
+    }
+}
+
+/// The error an [`Allocator`] returns if it failed to allocate memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocError;
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for AllocError {}
+
+impl From<AllocError> for ReserveError {
+    #[inline]
+    fn from(_: AllocError) -> Self {
+        ReserveError(())
+    }
+}
+
+/// A handle to the custom [`Allocator`] of a heap buffer, which is stored in its header.
+#[cfg(target_has_atomic = "ptr")]
+pub(crate) type AllocatorRef = alloc::sync::Arc<dyn Allocator>;
+
+/// Custom allocators are passed in an [`Arc`](alloc::sync::Arc), so without atomic pointers
+/// there is no way to use one and this is never constructed.
+#[cfg(not(target_has_atomic = "ptr"))]
+pub(crate) type AllocatorRef = &'static dyn Allocator;
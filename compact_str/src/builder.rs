@@ -0,0 +1,386 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::format::sealed::{Float, Integer};
+use crate::repr::{int_len, Repr};
+use crate::{CompactString, ReserveError, UnwrapWithMsg};
+
+/// Builds a [`CompactString`] out of pieces, with a single allocation.
+///
+/// Every piece is measured as it's pushed, so when the string is built its final length is
+/// known exactly, and it's written straight into an inline buffer, or a heap buffer of the right
+/// size. [`format_compact!`](crate::format_compact) on the other hand has to grow the string as
+/// it goes, since [`core::fmt`] doesn't say upfront how much it's going to write.
+///
+/// For a fixed number of pieces the [`compact_concat!`](crate::compact_concat) macro does the
+/// same thing without allocating a list of the pieces.
+///
+/// # Examples
+/// ```
+/// use compact_str::CompactStringBuilder;
+///
+/// let mut builder = CompactStringBuilder::new();
+/// builder.push_str("x = ").push_int(-42).push(',').push_str(" y = ").push_float(1.5);
+/// assert_eq!(builder.len(), 16);
+///
+/// let s = builder.build();
+/// assert_eq!(s, "x = -42, y = 1.5");
+/// assert!(!s.is_heap_allocated());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CompactStringBuilder<'a> {
+    pieces: Vec<Piece<'a>>,
+    len: usize,
+}
+
+impl<'a> CompactStringBuilder<'a> {
+    /// Creates an empty [`CompactStringBuilder`].
+    #[inline]
+    pub const fn new() -> Self {
+        CompactStringBuilder {
+            pieces: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Creates an empty [`CompactStringBuilder`] with room for `pieces` pieces.
+    #[inline]
+    pub fn with_capacity(pieces: usize) -> Self {
+        CompactStringBuilder {
+            pieces: Vec::with_capacity(pieces),
+            len: 0,
+        }
+    }
+
+    /// Appends a string slice.
+    #[inline]
+    pub fn push_str(&mut self, s: &'a str) -> &mut Self {
+        self.push_piece(Piece::Str(s))
+    }
+
+    /// Appends a [`char`].
+    #[inline]
+    pub fn push(&mut self, ch: char) -> &mut Self {
+        self.push_piece(Piece::Char(ch))
+    }
+
+    /// Appends an integer, formatted the same as
+    /// [`ToCompactString`](crate::ToCompactString).
+    #[inline]
+    pub fn push_int<T: crate::Integer>(&mut self, n: T) -> &mut Self {
+        self.push_piece(Piece::from_int(n))
+    }
+
+    /// Appends a float, formatted the same as [`ToCompactString`](crate::ToCompactString).
+    #[inline]
+    pub fn push_float<T: crate::Float>(&mut self, f: T) -> &mut Self {
+        self.push_piece(Piece::from_float(f))
+    }
+
+    /// Returns the length in bytes of the [`CompactString`] that would be built.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if nothing, or only empty pieces, have been pushed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Builds the [`CompactString`], the builder can be reused afterwards.
+    ///
+    /// # Panics
+    /// Panics if the system is out-of-memory.
+    /// Use [`CompactStringBuilder::try_build()`] if you want to handle this case.
+    #[inline]
+    #[track_caller]
+    pub fn build(&self) -> CompactString {
+        self.try_build().unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactStringBuilder::build()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return a [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactStringBuilder::build()`].
+    #[inline]
+    pub fn try_build(&self) -> Result<CompactString, ReserveError> {
+        write_pieces(&self.pieces, self.len)
+    }
+
+    #[inline]
+    fn push_piece(&mut self, piece: Piece<'a>) -> &mut Self {
+        self.len += piece.len();
+        self.pieces.push(piece);
+        self
+    }
+}
+
+/// A piece of a [`CompactString`], that knows how long it is.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy)]
+pub enum Piece<'a> {
+    Str(&'a str),
+    Char(char),
+    Int {
+        magnitude: u128,
+        negative: bool,
+    },
+    /// A float formatted by `zmij`, which is at most 24 bytes long.
+    Float {
+        buf: [u8; 24],
+        len: u8,
+    },
+}
+
+impl Piece<'_> {
+    #[inline]
+    fn from_int<T: Integer>(n: T) -> Self {
+        let (magnitude, negative) = n.into_parts();
+        Piece::Int {
+            magnitude,
+            negative,
+        }
+    }
+
+    #[inline]
+    fn from_float<T: Float>(f: T) -> Self {
+        let mut zmij_buf = zmij::Buffer::new();
+        let text = zmij_buf.format(f).as_bytes();
+        let mut buf = [0; 24];
+        buf[..text.len()].copy_from_slice(text);
+        Piece::Float {
+            buf,
+            len: text.len() as u8,
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        match self {
+            Piece::Str(s) => s.len(),
+            Piece::Char(ch) => ch.len_utf8(),
+            Piece::Int {
+                magnitude,
+                negative,
+            } => int_len(*magnitude, *negative),
+            Piece::Float { len, .. } => *len as usize,
+        }
+    }
+}
+
+/// Writes `pieces`, which are `len` bytes long in total, into a new [`CompactString`].
+pub(crate) fn write_pieces(
+    pieces: &[Piece<'_>],
+    len: usize,
+) -> Result<CompactString, ReserveError> {
+    let mut repr = Repr::with_capacity(len)?;
+    for piece in pieces {
+        match piece {
+            Piece::Str(s) => repr.push_str(s),
+            Piece::Char(ch) => repr.push_str(ch.encode_utf8(&mut [0; 4])),
+            Piece::Int {
+                magnitude,
+                negative,
+            } => repr.push_int(*magnitude, *negative)?,
+            Piece::Float { buf, len } => {
+                // SAFETY: `buf` was copied from the `&str` that `zmij` formatted
+                repr.push_str(unsafe { core::str::from_utf8_unchecked(&buf[..*len as usize]) })
+            }
+        }
+    }
+    debug_assert_eq!(repr.len(), len);
+    Ok(CompactString(repr))
+}
+
+/// Writes `pieces` into a new [`CompactString`], for [`compact_concat!`](crate::compact_concat).
+#[doc(hidden)]
+#[inline]
+#[track_caller]
+pub fn concat_pieces(pieces: &[Piece<'_>]) -> CompactString {
+    try_concat_pieces(pieces).unwrap_with_msg()
+}
+
+/// Fallible version of [`concat_pieces`], for [`try_compact_concat!`](crate::try_compact_concat).
+#[doc(hidden)]
+#[inline]
+pub fn try_concat_pieces(pieces: &[Piece<'_>]) -> Result<CompactString, ReserveError> {
+    let len = pieces.iter().map(Piece::len).sum();
+    write_pieces(pieces, len)
+}
+
+/// Converts a value into a [`Piece`], for [`compact_concat!`](crate::compact_concat).
+#[doc(hidden)]
+pub trait ToPiece {
+    fn to_piece(&self) -> Piece<'_>;
+}
+
+impl<T: ToPiece + ?Sized> ToPiece for &T {
+    #[inline]
+    fn to_piece(&self) -> Piece<'_> {
+        (**self).to_piece()
+    }
+}
+
+macro_rules! impl_to_piece_str {
+    ($($t:ty),*) => {$(
+        impl ToPiece for $t {
+            #[inline]
+            fn to_piece(&self) -> Piece<'_> {
+                Piece::Str(self)
+            }
+        }
+    )*};
+}
+
+impl_to_piece_str!(str, String, CompactString, Box<str>, Cow<'_, str>);
+
+impl ToPiece for char {
+    #[inline]
+    fn to_piece(&self) -> Piece<'_> {
+        Piece::Char(*self)
+    }
+}
+
+impl ToPiece for bool {
+    #[inline]
+    fn to_piece(&self) -> Piece<'_> {
+        Piece::Str(if *self { "true" } else { "false" })
+    }
+}
+
+macro_rules! impl_to_piece_int {
+    ($($t:ty),*) => {$(
+        impl ToPiece for $t {
+            #[inline]
+            fn to_piece(&self) -> Piece<'_> {
+                Piece::from_int(*self)
+            }
+        }
+    )*};
+}
+
+impl_to_piece_int!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl ToPiece for f32 {
+    #[inline]
+    fn to_piece(&self) -> Piece<'_> {
+        Piece::from_float(*self)
+    }
+}
+
+impl ToPiece for f64 {
+    #[inline]
+    fn to_piece(&self) -> Piece<'_> {
+        Piece::from_float(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use crate::tests::rand_unicode;
+    use crate::{
+        compact_concat, try_compact_concat, CompactString, CompactStringBuilder, ToCompactString,
+    };
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_builder(
+        #[strategy(rand_unicode())] s: String,
+        c: char,
+        int: i128,
+        small: u8,
+        float: f64,
+    ) {
+        let mut builder = CompactStringBuilder::new();
+        builder
+            .push_str(&s)
+            .push(c)
+            .push_int(int)
+            .push_int(small)
+            .push_float(float);
+
+        let expected = [
+            s.clone(),
+            c.to_string(),
+            int.to_string(),
+            small.to_string(),
+            float.to_compact_string().into_string(),
+        ]
+        .concat();
+        prop_assert_eq!(builder.len(), expected.len());
+
+        let built = builder.build();
+        prop_assert_eq!(&built, &expected);
+        prop_assert_eq!(
+            built.is_heap_allocated(),
+            expected.len() > core::mem::size_of::<String>()
+        );
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_compact_concat(int: i64, float: f32, b: bool) {
+        let name = String::from("name");
+        let compact = compact_concat!(name, ": ", int, '/', float, " ", b, &name);
+        let expected = format!("{name}: {int}/{} {b}{name}", float.to_compact_string());
+        prop_assert_eq!(compact, expected);
+    }
+
+    #[test]
+    fn test_builder_int_edges() {
+        let ints: Vec<i128> = [0, 1, 9, 10, 99, 100, -1, -10, i128::MIN, i128::MAX]
+            .into_iter()
+            .chain([u64::MAX as i128, i64::MIN as i128])
+            .collect();
+        for int in ints {
+            let mut builder = CompactStringBuilder::with_capacity(1);
+            builder.push_int(int);
+            assert_eq!(builder.build(), int.to_string());
+        }
+
+        let mut builder = CompactStringBuilder::new();
+        builder.push_int(u128::MAX);
+        assert_eq!(builder.build(), u128::MAX.to_string());
+    }
+
+    #[test]
+    fn test_builder_floats() {
+        for float in [
+            0.0,
+            -0.0,
+            f64::NAN,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::MIN,
+            1e-300,
+        ] {
+            let mut builder = CompactStringBuilder::new();
+            builder.push_float(float);
+            assert_eq!(builder.build(), float.to_compact_string());
+        }
+    }
+
+    #[test]
+    fn test_compact_concat() {
+        let long = "a string that is too long to be inlined";
+        assert_eq!(compact_concat!(), "");
+        assert_eq!(compact_concat!("a", 1, 'b', 2.5,), "a1b2.5");
+        assert!(!compact_concat!("a", 1).is_heap_allocated());
+
+        let heap = compact_concat!(long, CompactString::new("!"), 42u8);
+        assert_eq!(heap, format!("{long}!42"));
+        assert_eq!(heap.capacity(), heap.len());
+
+        assert_eq!(try_compact_concat!(long, 1).unwrap(), format!("{long}1"));
+    }
+}
@@ -0,0 +1,333 @@
+//! Case folding and language specific case mappings, see [`CompactString::to_casefold`] and
+//! [`CompactString::to_lowercase_in`].
+//!
+//! The contextual conditions (`After_I`, `More_Above`, ...) are defined in chapter 3.13 of the
+//! Unicode Standard, and the mappings themselves come from `SpecialCasing.txt`.
+
+use core::char::ToLowercase;
+use core::str::Chars;
+
+use crate::unicode_data::{
+    case_folding, case_ignorable, cased, combining_above, combining_mark, soft_dotted,
+};
+use crate::{convert_while_ascii, CompactString, ReserveError};
+
+/// A language with its own case mappings, see [`CompactString::to_lowercase_in`] and
+/// [`CompactString::to_uppercase_in`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CaseLocale {
+    /// Turkish, where `I` lowercases to a dotless `ı` and `i` uppercases to a dotted `İ`.
+    Turkish,
+    /// Azerbaijani, which has the same case mappings as [`CaseLocale::Turkish`].
+    Azerbaijani,
+    /// Lithuanian, which keeps the dot of `i` and `j` when they're lowercased with an accent
+    /// above them, and removes it when they're uppercased.
+    Lithuanian,
+}
+
+/// The full case folding of a single character.
+pub(crate) enum CaseFold {
+    Lower(ToLowercase),
+    Folded(Chars<'static>),
+}
+
+impl Iterator for CaseFold {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        match self {
+            CaseFold::Lower(chars) => chars.next(),
+            CaseFold::Folded(chars) => chars.next(),
+        }
+    }
+}
+
+#[inline]
+pub(crate) fn fold_char(c: char) -> CaseFold {
+    match case_folding::lookup(c) {
+        Some(folded) => CaseFold::Folded(folded.chars()),
+        None => CaseFold::Lower(c.to_lowercase()),
+    }
+}
+
+pub(crate) fn try_casefold(input: &str) -> Result<CompactString, ReserveError> {
+    let mut out = convert_while_ascii(input.as_bytes(), u8::to_ascii_lowercase)?;
+
+    // Safety: we know this is a valid char boundary since
+    // out.len() is only progressed if ascii bytes are found
+    let rest = unsafe { input.get_unchecked(out.len()..) };
+
+    for c in rest.chars() {
+        out.try_extend_chars(fold_char(c))?;
+    }
+    Ok(out)
+}
+
+pub(crate) fn eq_ignore_case(a: &str, b: &str) -> bool {
+    if a.is_ascii() && b.is_ascii() {
+        return a.eq_ignore_ascii_case(b);
+    }
+    a.chars()
+        .flat_map(fold_char)
+        .eq(b.chars().flat_map(fold_char))
+}
+
+pub(crate) fn try_lowercase_in(
+    input: &str,
+    locale: CaseLocale,
+) -> Result<CompactString, ReserveError> {
+    let mut out = CompactString::try_with_capacity(input.len())?;
+
+    for (i, c) in input.char_indices() {
+        let (before, after) = (&input[..i], &input[i + c.len_utf8()..]);
+        let mapped = match locale {
+            CaseLocale::Turkish | CaseLocale::Azerbaijani => match c {
+                'İ' => Some("i"),
+                // the dot above is removed below, so together they become a plain `i`
+                'I' if before_dot(after) => Some("i"),
+                'I' => Some("ı"),
+                '\u{307}' if after_i(before) => Some(""),
+                _ => None,
+            },
+            CaseLocale::Lithuanian => match c {
+                'I' if more_above(after) => Some("i\u{307}"),
+                'J' if more_above(after) => Some("j\u{307}"),
+                'Į' if more_above(after) => Some("į\u{307}"),
+                'Ì' => Some("i\u{307}\u{300}"),
+                'Í' => Some("i\u{307}\u{301}"),
+                'Ĩ' => Some("i\u{307}\u{303}"),
+                _ => None,
+            },
+        };
+
+        match mapped {
+            Some(mapped) => out.try_push_str(mapped)?,
+            None if c == 'Σ' => map_uppercase_sigma(input, i, &mut out)?,
+            None => out.try_extend_chars(c.to_lowercase())?,
+        }
+    }
+    Ok(out)
+}
+
+pub(crate) fn try_uppercase_in(
+    input: &str,
+    locale: CaseLocale,
+) -> Result<CompactString, ReserveError> {
+    let mut out = CompactString::try_with_capacity(input.len())?;
+
+    for (i, c) in input.char_indices() {
+        match (locale, c) {
+            (CaseLocale::Turkish | CaseLocale::Azerbaijani, 'i') => out.try_push_str("İ")?,
+            (CaseLocale::Lithuanian, '\u{307}') if after_soft_dotted(&input[..i]) => {}
+            _ => out.try_extend_chars(c.to_uppercase())?,
+        }
+    }
+    Ok(out)
+}
+
+/// Maps `Σ` at byte `i` of `from`, to `ς` at the end of a word and `σ` everywhere else.
+pub(crate) fn map_uppercase_sigma(
+    from: &str,
+    i: usize,
+    to: &mut CompactString,
+) -> Result<(), ReserveError> {
+    // See https://www.unicode.org/versions/Unicode7.0.0/ch03.pdf#G33992
+    // for the definition of `Final_Sigma`.
+    debug_assert!('Σ'.len_utf8() == 2);
+    let is_word_final = case_ignorable_then_cased(from[..i].chars().rev())
+        && !case_ignorable_then_cased(from[i + 2..].chars());
+    to.try_push_str(if is_word_final { "ς" } else { "σ" })
+}
+
+fn case_ignorable_then_cased<I: Iterator<Item = char>>(mut iter: I) -> bool {
+    use case_ignorable::lookup as Case_Ignorable;
+    use cased::lookup as Cased;
+    match iter.find(|&c| !Case_Ignorable(c)) {
+        Some(c) => Cased(c),
+        None => false,
+    }
+}
+
+/// Returns the first character in `chars` that isn't a combining mark, other than one that
+/// combines above the base character.
+fn find_above_or_base<I: Iterator<Item = char>>(mut chars: I) -> Option<char> {
+    chars.find(|&c| !combining_mark::lookup(c) || combining_above::lookup(c))
+}
+
+/// `After_I`, there is an `I` before, without another base character or accent above between.
+fn after_i(before: &str) -> bool {
+    find_above_or_base(before.chars().rev()) == Some('I')
+}
+
+/// `Before_Dot`, a `U+0307 COMBINING DOT ABOVE` follows, without another base character or
+/// accent above between.
+fn before_dot(after: &str) -> bool {
+    find_above_or_base(after.chars()) == Some('\u{307}')
+}
+
+/// `After_Soft_Dotted`, there is a character with a dot, like `i` or `j`, before, without
+/// another base character or accent above between.
+fn after_soft_dotted(before: &str) -> bool {
+    find_above_or_base(before.chars().rev()).is_some_and(soft_dotted::lookup)
+}
+
+/// `More_Above`, an accent above follows, with only other combining marks between.
+fn more_above(after: &str) -> bool {
+    after
+        .chars()
+        .take_while(|&c| combining_mark::lookup(c))
+        .any(combining_above::lookup)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use super::*;
+    use crate::tests::rand_unicode;
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_casefold_is_idempotent(#[strategy(rand_unicode())] s: String) {
+        let folded = CompactString::new(&s).to_casefold();
+        prop_assert_eq!(folded.to_casefold(), &folded);
+        prop_assert!(folded.eq_ignore_case(&s));
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_casefold_ascii(#[strategy("[ -~]{0,40}")] s: String) {
+        let compact = CompactString::new(&s);
+        prop_assert_eq!(compact.to_casefold(), s.to_ascii_lowercase());
+        prop_assert!(compact.eq_ignore_case(&s.to_ascii_uppercase()));
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_eq_ignore_case(
+        #[strategy("[aAsSßẞσΣςﬁäÄ ]{0,10}")] a: String,
+        #[strategy("[aAsSßẞσΣςﬁäÄ ]{0,10}")] b: String,
+    ) {
+        let (a, b) = (CompactString::new(a), CompactString::new(b));
+        prop_assert_eq!(a.eq_ignore_case(&b), a.to_casefold() == b.to_casefold());
+        prop_assert!(a.eq_ignore_case(&a.to_uppercase()));
+        prop_assert!(a.eq_ignore_case(&a.to_lowercase()));
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_locale_matches_default(
+        #[strategy("[a-hA-H\u{300}-\u{36f}ΣσäÄ ]{0,20}")] s: String,
+    ) {
+        // without any `i`s or `j`s, the locales don't change anything
+        let compact = CompactString::new(&s);
+        for locale in [CaseLocale::Turkish, CaseLocale::Lithuanian] {
+            prop_assert_eq!(compact.to_lowercase_in(locale), compact.to_lowercase());
+            prop_assert_eq!(compact.to_uppercase_in(locale), compact.to_uppercase());
+        }
+    }
+
+    #[test]
+    fn test_casefold() {
+        let cases = [
+            ("Straße", "strasse"),
+            ("ẞ", "ss"),
+            ("ﬁﬂ", "fifl"),
+            ("ΣΊΣΥΦΟΣ", "σίσυφοσ"),
+            ("ᾼ", "αι"),
+            ("µ", "μ"),
+            ("İ", "i\u{307}"),
+            // Cherokee folds to the capital letters
+            ("ꭰᏸᎠ", "ᎠᏰᎠ"),
+            ("hello world", "hello world"),
+            ("", ""),
+        ];
+        for (s, folded) in cases {
+            assert_eq!(CompactString::new(s).to_casefold(), folded, "{s}");
+        }
+    }
+
+    #[test]
+    fn test_case_folding_table() {
+        // every entry only exists because it differs from `char::to_lowercase`
+        let mut previous = None;
+        for c in (0..=char::MAX as u32).filter_map(char::from_u32) {
+            if let Some(folded) = case_folding::lookup(c) {
+                assert!(c.to_lowercase().ne(folded.chars()), "{c:?}");
+                assert!(previous < Some(c));
+                previous = Some(c);
+            }
+        }
+    }
+
+    #[test]
+    fn test_turkish() {
+        let lower = |s: &str| CompactString::new(s).to_lowercase_in(CaseLocale::Turkish);
+        let upper = |s: &str| CompactString::new(s).to_uppercase_in(CaseLocale::Turkish);
+
+        assert_eq!(lower("IİıiI"), "ıiıiı");
+        // a combining dot above makes `I` an `i`, even with other marks between
+        assert_eq!(lower("I\u{307}"), "i");
+        assert_eq!(lower("I\u{323}\u{307}"), "i\u{323}");
+        // but not with an accent above between
+        assert_eq!(lower("I\u{301}\u{307}"), "ı\u{301}\u{307}");
+        assert_eq!(lower("ΟΔΥΣΣΕΥΣ"), "οδυσσευς");
+
+        assert_eq!(upper("iİıI"), "İİII");
+        assert_eq!(
+            CompactString::new("istanbul").to_uppercase_in(CaseLocale::Azerbaijani),
+            "İSTANBUL"
+        );
+    }
+
+    #[test]
+    fn test_lithuanian() {
+        let lower = |s: &str| CompactString::new(s).to_lowercase_in(CaseLocale::Lithuanian);
+        let upper = |s: &str| CompactString::new(s).to_uppercase_in(CaseLocale::Lithuanian);
+
+        assert_eq!(lower("I"), "i");
+        assert_eq!(lower("I\u{301}"), "i\u{307}\u{301}");
+        assert_eq!(lower("J\u{323}\u{303}"), "j\u{307}\u{323}\u{303}");
+        assert_eq!(lower("Į\u{301}"), "į\u{307}\u{301}");
+        assert_eq!(
+            lower("ÌÍĨ"),
+            "i\u{307}\u{300}i\u{307}\u{301}i\u{307}\u{303}"
+        );
+        // an accent below isn't "more above"
+        assert_eq!(lower("I\u{323}"), "i\u{323}");
+
+        assert_eq!(upper("i\u{307}"), "I");
+        assert_eq!(upper("j\u{323}\u{307}"), "J\u{323}");
+        assert_eq!(upper("i\u{301}\u{307}"), "I\u{301}\u{307}");
+        assert_eq!(upper("a\u{307}"), "A\u{307}");
+    }
+
+    #[test]
+    fn test_combining_tables() {
+        assert!(combining_mark::lookup('\u{301}') && combining_above::lookup('\u{301}'));
+        assert!(combining_mark::lookup('\u{323}') && !combining_above::lookup('\u{323}'));
+        assert!(!combining_mark::lookup('a') && !combining_above::lookup('a'));
+        assert!(!combining_mark::lookup(char::MAX));
+        assert!(soft_dotted::lookup('i') && soft_dotted::lookup('ј'));
+        assert!(!soft_dotted::lookup('I') && !soft_dotted::lookup('ı'));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_combining_tables_exhaustive() {
+        use unicode_normalization::char::canonical_combining_class;
+
+        // if this fails, regenerate the tables with `scripts/unicode_data.py`
+        assert_eq!(unicode_normalization::UNICODE_VERSION, (17, 0, 0));
+        for c in (0..=char::MAX as u32).filter_map(char::from_u32) {
+            let class = canonical_combining_class(c);
+            assert_eq!(combining_mark::lookup(c), class != 0, "{c:?}");
+            assert_eq!(combining_above::lookup(c), class == 230, "{c:?}");
+        }
+    }
+}
@@ -0,0 +1,815 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::borrow::{Borrow, BorrowMut};
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::ops::{Deref, DerefMut};
+use core::str::Utf8Error;
+use core::{fmt, slice};
+
+use castaway::match_type;
+
+use crate::repr::{Repr, MAX_INLINE_BYTES};
+use crate::{CompactString, ReserveError, UnwrapWithMsg};
+
+/// A [`CompactBytes`] is a compact byte buffer that can be used almost anywhere a [`Vec<u8>`] or
+/// `[u8]` can be used.
+///
+/// It shares its memory layout with [`CompactString`], so it's the same size as a [`Vec<u8>`]
+/// and up to `std::mem::size_of::<Vec<u8>>() - 1` bytes get inlined on the stack. One byte less
+/// than a [`CompactString`] can inline, because arbitrary bytes could otherwise be mistaken for the
+/// discriminant stored in the final byte.
+///
+/// ```
+/// use compact_str::CompactBytes;
+///
+/// let mut bytes = CompactBytes::new(b"hello");
+/// bytes.push(b' ');
+/// bytes.extend_from_slice(&[0xF0, 0x9F, 0xA6, 0x80]);
+///
+/// assert_eq!(bytes, b"hello \xF0\x9F\xA6\x80");
+/// assert!(!bytes.is_heap_allocated());
+///
+/// // CompactBytes derefs into a [u8]
+/// assert!(bytes.starts_with(b"hello"));
+/// ```
+///
+/// # Converting to and from `CompactString`
+/// A [`CompactString`] can always be converted into a [`CompactBytes`], and a [`CompactBytes`] can
+/// be converted back via [`CompactString::from_utf8`] (which copies), or [`TryFrom`] which re-uses
+/// the underlying buffer if the bytes are valid UTF-8.
+///
+/// ```
+/// use compact_str::{CompactBytes, CompactString};
+///
+/// let s = CompactString::new("a string that is long enough to be heap allocated");
+/// let bytes = CompactBytes::from(s);
+///
+/// let copied = CompactString::from_utf8(&bytes).unwrap();
+/// let moved = CompactString::try_from(bytes).unwrap();
+/// assert_eq!(copied, moved);
+///
+/// let invalid = CompactBytes::new(&[0xFF, 0xFE]);
+/// assert!(CompactString::try_from(invalid).is_err());
+/// ```
+#[repr(transparent)]
+pub struct CompactBytes(Repr);
+
+impl CompactBytes {
+    /// The maximum number of bytes a [`CompactBytes`] can store inline.
+    pub const MAX_INLINE: usize = MAX_INLINE_BYTES;
+
+    /// Creates a new [`CompactBytes`] from any type that implements `AsRef<[u8]>`. If the
+    /// buffer is short enough, then it will be inlined on the stack! When `bytes` is an owned
+    /// [`Vec<u8>`] that does not fit inline, its existing allocation is reused.
+    ///
+    /// In a `static` or `const` context you can use the method [`CompactBytes::const_new()`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactBytes;
+    /// let inline = CompactBytes::new([1, 2, 3]);
+    /// assert!(!inline.is_heap_allocated());
+    ///
+    /// let heap = CompactBytes::new(vec![0u8; 128]);
+    /// assert!(heap.is_heap_allocated());
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn new<T: AsRef<[u8]>>(bytes: T) -> Self {
+        Self::try_new(bytes).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactBytes::new()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactBytes::new()`].
+    #[inline]
+    pub fn try_new<T: AsRef<[u8]>>(bytes: T) -> Result<Self, ReserveError> {
+        let repr = match_type!(bytes, {
+            Vec<u8> as bytes => Repr::from_vec(bytes)?,
+            bytes => Repr::from_bytes(bytes.as_ref())?,
+        });
+
+        Ok(CompactBytes(repr))
+    }
+
+    /// Creates a new [`CompactBytes`] from `&'static [u8]` at compile time.
+    /// Complexity: O(1). As an optimization, short buffers get inlined.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactBytes;
+    ///
+    /// const MAGIC: CompactBytes = CompactBytes::const_new(b"\x7FELF");
+    /// ```
+    #[inline]
+    pub const fn const_new(bytes: &'static [u8]) -> Self {
+        CompactBytes(Repr::const_from_bytes(bytes))
+    }
+
+    /// Get back the `&'static [u8]` constructed by [`CompactBytes::const_new`].
+    ///
+    /// If the buffer was short enough that it could be inlined, then it was inline, and this
+    /// method will return `None`.
+    #[inline]
+    pub const fn as_static_bytes(&self) -> Option<&'static [u8]> {
+        self.0.as_static_bytes()
+    }
+
+    /// Creates a new empty [`CompactBytes`] with the capacity to fit at least `capacity` bytes.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the system is out-of-memory.
+    /// Use [`CompactBytes::try_with_capacity()`] if you want to handle such a problem manually.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactBytes;
+    /// let empty = CompactBytes::with_capacity(0);
+    /// assert_eq!(empty.capacity(), CompactBytes::MAX_INLINE);
+    /// assert!(!empty.is_heap_allocated());
+    ///
+    /// let heap = CompactBytes::with_capacity(CompactBytes::MAX_INLINE + 1);
+    /// assert!(heap.is_heap_allocated());
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::try_with_capacity(capacity).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactBytes::with_capacity()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactBytes::with_capacity()`].
+    #[inline]
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, ReserveError> {
+        Repr::bytes_with_capacity(capacity).map(CompactBytes)
+    }
+
+    /// Returns the length of the [`CompactBytes`] in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the [`CompactBytes`] has a length of 0, `false` otherwise
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the capacity of the [`CompactBytes`], in bytes.
+    ///
+    /// Note: A [`CompactBytes`] always has a capacity of at least [`CompactBytes::MAX_INLINE`],
+    /// unless it's backed by a `&'static [u8]`.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.0.bytes_capacity()
+    }
+
+    /// Ensures that this [`CompactBytes`]'s capacity is at least `additional` bytes longer than
+    /// its length. The capacity may be increased by more than `additional` bytes if it chooses,
+    /// to prevent frequent reallocations.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the new capacity overflows `usize` or if the system is out-of-memory.
+    /// Use [`CompactBytes::try_reserve()`] if you want to handle such a problem manually.
+    #[inline]
+    #[track_caller]
+    pub fn reserve(&mut self, additional: usize) {
+        self.try_reserve(additional).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactBytes::reserve()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`]
+    /// Otherwise it behaves the same as [`CompactBytes::reserve()`].
+    #[inline]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), ReserveError> {
+        self.0.reserve_bytes(additional)
+    }
+
+    /// Returns a slice containing the entire [`CompactBytes`].
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+
+    /// Returns a mutable slice containing the entire [`CompactBytes`].
+    ///
+    /// Note: If the [`CompactBytes`] is backed by a `&'static [u8]`, the bytes are copied into an
+    /// owned buffer first.
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        let len = self.len();
+        // SAFETY: `as_mut_bytes_ptr` returns a pointer that is valid for `len` initialized bytes,
+        // and the returned slice mutably borrows `self`
+        unsafe { slice::from_raw_parts_mut(self.0.as_mut_bytes_ptr(), len) }
+    }
+
+    /// Returns a raw pointer to the [`CompactBytes`]'s buffer.
+    #[inline]
+    pub fn as_ptr(&self) -> *const u8 {
+        self.as_slice().as_ptr()
+    }
+
+    /// Returns a mutable raw pointer to the [`CompactBytes`]'s buffer.
+    ///
+    /// The pointer is valid for writes up to [`CompactBytes::capacity`].
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.0.as_mut_bytes_ptr()
+    }
+
+    /// Forces the length of the [`CompactBytes`] to `new_len`.
+    ///
+    /// # Safety
+    /// - `new_len` must be less than or equal to [`CompactBytes::capacity()`]
+    /// - The elements at `old_len..new_len` must be initialized
+    #[inline]
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        self.0.set_bytes_len(new_len)
+    }
+
+    /// Appends a byte to the end of the [`CompactBytes`].
+    #[inline]
+    pub fn push(&mut self, byte: u8) {
+        self.0.push_bytes(&[byte]);
+    }
+
+    /// Removes the last byte from the [`CompactBytes`] and returns it, or `None` if it's empty.
+    #[inline]
+    pub fn pop(&mut self) -> Option<u8> {
+        let (&last, rest) = self.as_slice().split_last()?;
+        let new_len = rest.len();
+        // SAFETY: We're shrinking the buffer by one byte
+        unsafe { self.set_len(new_len) };
+        Some(last)
+    }
+
+    /// Appends all of the bytes in `other` to the [`CompactBytes`].
+    #[inline]
+    pub fn extend_from_slice(&mut self, other: &[u8]) {
+        self.0.push_bytes(other);
+    }
+
+    /// Inserts a byte at position `idx`, shifting all bytes after it to the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is larger than the [`CompactBytes`]'s length.
+    pub fn insert(&mut self, idx: usize, byte: u8) {
+        self.insert_from_slice(idx, &[byte]);
+    }
+
+    /// Inserts a slice of bytes at position `idx`, shifting all bytes after it to the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is larger than the [`CompactBytes`]'s length.
+    pub fn insert_from_slice(&mut self, idx: usize, bytes: &[u8]) {
+        let len = self.len();
+        assert!(
+            idx <= len,
+            "insertion index (is {idx}) should be <= len (is {len})"
+        );
+
+        let count = bytes.len();
+        self.reserve(count);
+
+        let ptr = self.as_mut_ptr();
+        // SAFETY: We reserved `count` additional bytes, `idx <= len`, and `bytes` can't overlap
+        // with our buffer because we hold a mutable reference to it
+        unsafe {
+            ptr.add(idx).copy_to(ptr.add(idx + count), len - idx);
+            ptr.add(idx).copy_from_nonoverlapping(bytes.as_ptr(), count);
+            self.set_len(len + count);
+        }
+    }
+
+    /// Removes and returns the byte at position `idx`, shifting all bytes after it to the left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    pub fn remove(&mut self, idx: usize) -> u8 {
+        let len = self.len();
+        assert!(
+            idx < len,
+            "removal index (is {idx}) should be < len (is {len})"
+        );
+
+        let ptr = self.as_mut_ptr();
+        // SAFETY: `idx` is in bounds, and we're shrinking the buffer by one byte
+        unsafe {
+            let byte = ptr.add(idx).read();
+            ptr.add(idx + 1).copy_to(ptr.add(idx), len - idx - 1);
+            self.set_len(len - 1);
+            byte
+        }
+    }
+
+    /// Shortens the [`CompactBytes`], keeping the first `new_len` bytes and dropping the rest.
+    ///
+    /// If `new_len` is greater than the current length, this has no effect.
+    #[inline]
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len < self.len() {
+            // SAFETY: We're shrinking the buffer
+            unsafe { self.set_len(new_len) };
+        }
+    }
+
+    /// Resizes the [`CompactBytes`] in-place so that its length is equal to `new_len`, filling
+    /// any new space with `value`.
+    pub fn resize(&mut self, new_len: usize, value: u8) {
+        let len = self.len();
+        if new_len <= len {
+            self.truncate(new_len);
+            return;
+        }
+
+        let additional = new_len - len;
+        self.reserve(additional);
+        // SAFETY: We reserved `additional` bytes, and we initialize them before setting the length
+        unsafe {
+            self.as_mut_ptr().add(len).write_bytes(value, additional);
+            self.set_len(new_len);
+        }
+    }
+
+    /// Truncates the [`CompactBytes`] to zero length, without changing its capacity.
+    #[inline]
+    pub fn clear(&mut self) {
+        // SAFETY: An empty buffer is always valid
+        unsafe { self.set_len(0) };
+    }
+
+    /// Splits the [`CompactBytes`] into two at the given index, returning the bytes `[at, len)`
+    /// and leaving `[0, at)` in `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is greater than the length.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let other = CompactBytes::new(&self.as_slice()[at..]);
+        self.truncate(at);
+        other
+    }
+
+    /// Retains only the bytes specified by the predicate.
+    pub fn retain(&mut self, mut predicate: impl FnMut(u8) -> bool) {
+        let mut new_len = 0;
+        let bytes = self.as_mut_slice();
+        for idx in 0..bytes.len() {
+            let byte = bytes[idx];
+            if predicate(byte) {
+                bytes[new_len] = byte;
+                new_len += 1;
+            }
+        }
+        self.truncate(new_len);
+    }
+
+    /// Shrinks the capacity of this [`CompactBytes`] with a lower bound.
+    ///
+    /// The resulting capacity is never less than the size of 3×[`usize`] minus one, i.e. the
+    /// capacity that can be inlined.
+    #[inline]
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.0.shrink_bytes_to(min_capacity);
+    }
+
+    /// Shrinks the capacity of this [`CompactBytes`] to match its length, inlining the bytes if
+    /// possible.
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.0.shrink_bytes_to(0);
+    }
+
+    /// Returns whether or not the [`CompactBytes`] is heap allocated.
+    #[inline]
+    pub fn is_heap_allocated(&self) -> bool {
+        self.0.is_heap_allocated()
+    }
+
+    /// Converts a [`CompactBytes`] into a [`Vec<u8>`], in `O(1)` time if it's heap allocated.
+    #[inline]
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0.into_vec()
+    }
+}
+
+impl Clone for CompactBytes {
+    #[inline]
+    fn clone(&self) -> Self {
+        CompactBytes(self.0.clone_bytes())
+    }
+}
+
+impl Default for CompactBytes {
+    #[inline]
+    fn default() -> Self {
+        CompactBytes::const_new(b"")
+    }
+}
+
+impl Deref for CompactBytes {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl DerefMut for CompactBytes {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+impl AsRef<[u8]> for CompactBytes {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsMut<[u8]> for CompactBytes {
+    #[inline]
+    fn as_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+impl Borrow<[u8]> for CompactBytes {
+    #[inline]
+    fn borrow(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl BorrowMut<[u8]> for CompactBytes {
+    #[inline]
+    fn borrow_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+impl Eq for CompactBytes {}
+
+impl<T: AsRef<[u8]> + ?Sized> PartialEq<T> for CompactBytes {
+    fn eq(&self, other: &T) -> bool {
+        self.as_slice() == other.as_ref()
+    }
+}
+
+impl PartialEq<CompactBytes> for [u8] {
+    fn eq(&self, other: &CompactBytes) -> bool {
+        self == other.as_slice()
+    }
+}
+
+impl PartialEq<CompactBytes> for &[u8] {
+    fn eq(&self, other: &CompactBytes) -> bool {
+        *self == other.as_slice()
+    }
+}
+
+impl PartialEq<CompactBytes> for Vec<u8> {
+    fn eq(&self, other: &CompactBytes) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Ord for CompactBytes {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+}
+
+impl PartialOrd for CompactBytes {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for CompactBytes {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state)
+    }
+}
+
+impl fmt::Debug for CompactBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_slice(), f)
+    }
+}
+
+impl<'a> From<&'a [u8]> for CompactBytes {
+    #[inline]
+    #[track_caller]
+    fn from(bytes: &'a [u8]) -> Self {
+        CompactBytes::new(bytes)
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for CompactBytes {
+    #[inline]
+    #[track_caller]
+    fn from(bytes: [u8; N]) -> Self {
+        CompactBytes::new(bytes)
+    }
+}
+
+impl From<Vec<u8>> for CompactBytes {
+    #[inline]
+    #[track_caller]
+    fn from(bytes: Vec<u8>) -> Self {
+        CompactBytes(Repr::from_vec(bytes).unwrap_with_msg())
+    }
+}
+
+impl From<Box<[u8]>> for CompactBytes {
+    #[inline]
+    #[track_caller]
+    fn from(bytes: Box<[u8]>) -> Self {
+        CompactBytes::from(bytes.into_vec())
+    }
+}
+
+impl<'a> From<Cow<'a, [u8]>> for CompactBytes {
+    fn from(cow: Cow<'a, [u8]>) -> Self {
+        match cow {
+            Cow::Borrowed(b) => b.into(),
+            // we separate these two so we can re-use the underlying buffer in the owned case
+            Cow::Owned(b) => b.into(),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for CompactBytes {
+    #[inline]
+    #[track_caller]
+    fn from(s: &'a str) -> Self {
+        CompactBytes::new(s)
+    }
+}
+
+impl From<CompactString> for CompactBytes {
+    /// Converts a [`CompactString`] into a [`CompactBytes`], re-using the underlying buffer.
+    ///
+    /// Note: A [`CompactString`] that is inlined and exactly 3×[`usize`] bytes long doesn't fit
+    /// inline as [`CompactBytes`], so it gets moved to the heap.
+    #[inline]
+    #[track_caller]
+    fn from(s: CompactString) -> Self {
+        let mut repr = s.0;
+        // Copies a full inline or a shared string to the heap, every other string is already valid
+        // as bytes
+        repr.reserve_bytes(0).unwrap_with_msg();
+        CompactBytes(repr)
+    }
+}
+
+impl TryFrom<CompactBytes> for CompactString {
+    type Error = Utf8Error;
+
+    /// Converts a [`CompactBytes`] into a [`CompactString`], re-using the underlying buffer,
+    /// failing if the bytes are not valid UTF-8.
+    #[inline]
+    fn try_from(bytes: CompactBytes) -> Result<Self, Self::Error> {
+        core::str::from_utf8(bytes.as_slice())?;
+        // We just checked that the bytes are valid UTF-8, and every variant of a bytes `Repr` is
+        // also a valid string `Repr`
+        Ok(CompactString(bytes.0))
+    }
+}
+
+impl From<CompactBytes> for Vec<u8> {
+    #[inline]
+    fn from(bytes: CompactBytes) -> Self {
+        bytes.into_vec()
+    }
+}
+
+impl From<CompactBytes> for Box<[u8]> {
+    #[inline]
+    fn from(bytes: CompactBytes) -> Self {
+        bytes.into_vec().into_boxed_slice()
+    }
+}
+
+impl FromIterator<u8> for CompactBytes {
+    fn from_iter<T: IntoIterator<Item = u8>>(iter: T) -> Self {
+        let mut bytes = CompactBytes::default();
+        bytes.extend(iter);
+        bytes
+    }
+}
+
+impl<'a> FromIterator<&'a u8> for CompactBytes {
+    fn from_iter<T: IntoIterator<Item = &'a u8>>(iter: T) -> Self {
+        iter.into_iter().copied().collect()
+    }
+}
+
+impl Extend<u8> for CompactBytes {
+    fn extend<T: IntoIterator<Item = u8>>(&mut self, iter: T) {
+        let iter = iter.into_iter();
+        let (lower_bound, _) = iter.size_hint();
+        self.reserve(lower_bound);
+        iter.for_each(|byte| self.push(byte));
+    }
+}
+
+impl<'a> Extend<&'a u8> for CompactBytes {
+    fn extend<T: IntoIterator<Item = &'a u8>>(&mut self, iter: T) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+impl<'a> Extend<&'a [u8]> for CompactBytes {
+    fn extend<T: IntoIterator<Item = &'a [u8]>>(&mut self, iter: T) {
+        iter.into_iter()
+            .for_each(|bytes| self.extend_from_slice(bytes));
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::io::Write for CompactBytes {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+static_assertions::assert_eq_size!(CompactBytes, Vec<u8>);
+static_assertions::assert_eq_size!(CompactBytes, Option<CompactBytes>);
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use crate::{CompactBytes, CompactString};
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_roundtrip_vec(
+        #[strategy(proptest::collection::vec(any::<u8>(), 0..100))] v: Vec<u8>,
+    ) {
+        let bytes = CompactBytes::new(&v);
+        prop_assert_eq!(&bytes, &v);
+        prop_assert_eq!(
+            bytes.is_heap_allocated(),
+            v.len() > CompactBytes::MAX_INLINE
+        );
+        prop_assert_eq!(bytes.clone(), bytes.as_slice());
+        prop_assert_eq!(bytes.into_vec(), v.clone());
+
+        let bytes = CompactBytes::from(v.clone());
+        prop_assert_eq!(bytes, v);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_push_matches_vec(
+        #[strategy(proptest::collection::vec(any::<u8>(), 0..100))] v: Vec<u8>,
+    ) {
+        let mut bytes = CompactBytes::default();
+        let mut control = Vec::new();
+        for byte in v {
+            bytes.push(byte);
+            control.push(byte);
+            prop_assert_eq!(&bytes, &control);
+            prop_assert!(bytes.capacity() >= bytes.len());
+        }
+
+        while let Some(byte) = control.pop() {
+            prop_assert_eq!(bytes.pop(), Some(byte));
+            prop_assert_eq!(&bytes, &control);
+        }
+        prop_assert_eq!(bytes.pop(), None);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_compact_string_roundtrip(#[strategy("\\PC{0,60}")] s: String) {
+        let compact = CompactString::new(&s);
+        let bytes = CompactBytes::from(compact);
+        prop_assert_eq!(&bytes, s.as_bytes());
+        prop_assert_eq!(
+            bytes.is_heap_allocated(),
+            s.len() > CompactBytes::MAX_INLINE
+        );
+
+        let compact = CompactString::try_from(bytes).unwrap();
+        prop_assert_eq!(compact, s);
+    }
+
+    #[test]
+    fn test_full_inline_byte_is_not_a_discriminant() {
+        // Every possible value for the final byte of a buffer that's exactly the size of a
+        // `CompactString`'s inline capacity
+        for last in 0..=u8::MAX {
+            let mut v = Vec::from([b'a'; CompactBytes::MAX_INLINE]);
+            v.push(last);
+
+            let mut bytes = CompactBytes::new(&v[..CompactBytes::MAX_INLINE]);
+            bytes.push(last);
+            assert!(bytes.is_heap_allocated());
+            assert_eq!(bytes, v);
+
+            let mut bytes = CompactBytes::new(&v);
+            assert_eq!(bytes.pop(), Some(last));
+            bytes.shrink_to_fit();
+            assert!(!bytes.is_heap_allocated());
+            assert_eq!(bytes, &v[..CompactBytes::MAX_INLINE]);
+        }
+    }
+
+    #[test]
+    fn test_const_new() {
+        const SHORT: CompactBytes = CompactBytes::const_new(&[0xFF; 3]);
+        const LONG: CompactBytes = CompactBytes::const_new(&[0xFF; 64]);
+
+        assert_eq!(SHORT, [0xFF; 3]);
+        assert!(SHORT.as_static_bytes().is_none());
+        assert_eq!(LONG.as_static_bytes(), Some(&[0xFF; 64][..]));
+
+        let mut long = LONG;
+        long.truncate(CompactBytes::MAX_INLINE + 1);
+        assert_eq!(
+            long.as_static_bytes().map(<[u8]>::len),
+            Some(CompactBytes::MAX_INLINE + 1)
+        );
+
+        long[0] = 0;
+        assert!(long.as_static_bytes().is_none());
+        assert_eq!(long[..2], [0, 0xFF]);
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut bytes = CompactBytes::new(b"hello world");
+        bytes.insert(5, b',');
+        assert_eq!(bytes, b"hello, world");
+        bytes.insert_from_slice(0, &[0xFF; 20]);
+        assert!(bytes.is_heap_allocated());
+        assert_eq!(bytes.remove(0), 0xFF);
+        assert_eq!(bytes.len(), 31);
+
+        bytes.retain(|b| b != 0xFF);
+        assert_eq!(bytes, b"hello, world");
+
+        let world = bytes.split_off(7);
+        assert_eq!(bytes, b"hello, ");
+        assert_eq!(world, b"world");
+
+        bytes.resize(10, b'!');
+        assert_eq!(bytes, b"hello, !!!");
+        bytes.clear();
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn test_from_compact_string_full_inline() {
+        let s = CompactString::new("a".repeat(core::mem::size_of::<String>()));
+        assert!(!s.is_heap_allocated());
+
+        let bytes = CompactBytes::from(s.clone());
+        assert!(bytes.is_heap_allocated());
+        assert_eq!(bytes, s.as_bytes());
+    }
+
+    #[test]
+    fn test_try_from_invalid_utf8() {
+        let bytes = CompactBytes::new([b'a', 0xFF]);
+        assert!(CompactString::try_from(bytes.clone()).is_err());
+        assert!(CompactString::from_utf8(&bytes).is_err());
+    }
+}
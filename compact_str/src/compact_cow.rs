@@ -0,0 +1,375 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+use crate::repr::Repr;
+use crate::CompactString;
+
+/// A [`CompactCow`] is a clone-on-write string, that either borrows a `&'a str` or owns a
+/// [`CompactString`], all in 24 bytes.
+///
+/// It's the borrowing counterpart of [`CompactString::const_new`]: a long borrowed string is
+/// referenced instead of copied, while a short one is inlined, same as a [`CompactString`] would.
+/// This makes it a good fit for deserializing, where the input can often be borrowed, but the
+/// result sometimes has to be owned.
+///
+/// ```
+/// use compact_str::{CompactCow, CompactString};
+///
+/// let text = String::from("a string that is long enough to be heap allocated");
+///
+/// let borrowed = CompactCow::borrowed(&text);
+/// assert!(borrowed.is_borrowed());
+/// assert_eq!(borrowed.as_ptr(), text.as_ptr());
+///
+/// let owned = CompactCow::from(CompactString::new(&text));
+/// assert!(!owned.is_borrowed());
+/// assert_eq!(borrowed, owned);
+///
+/// // short strings get inlined
+/// assert!(!CompactCow::borrowed("short").is_borrowed());
+///
+/// // `into_owned` only copies the string if it's borrowed
+/// let compact: CompactString = borrowed.into_owned();
+/// assert_eq!(compact, text);
+/// ```
+///
+/// With the `serde` feature enabled, a [`CompactCow`] borrows from the input when it's
+/// deserialized. Note that, like with [`Cow`], serde's derive macro needs a `#[serde(borrow)]`
+/// attribute on the field for this to work.
+#[repr(transparent)]
+pub struct CompactCow<'a> {
+    repr: Repr,
+    marker: PhantomData<&'a str>,
+}
+
+impl<'a> CompactCow<'a> {
+    /// Creates a [`CompactCow`] that borrows `text`. If `text` is short enough it gets inlined
+    /// instead, so no pointer into `text` is kept.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactCow;
+    /// const HELLO: CompactCow<'static> = CompactCow::borrowed("hello");
+    /// assert_eq!(HELLO, "hello");
+    /// ```
+    #[inline]
+    pub const fn borrowed(text: &'a str) -> Self {
+        CompactCow {
+            // SAFETY: The lifetime of `self` is bound to `text`, and we only ever hand out `text`
+            // as a `&'a str`
+            repr: unsafe { Repr::from_borrowed(text) },
+            marker: PhantomData,
+        }
+    }
+
+    /// Creates a [`CompactCow`] that owns `compact`.
+    #[inline]
+    pub fn owned(compact: CompactString) -> Self {
+        CompactCow {
+            repr: compact.0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the borrowed `&'a str`, or `None` if the string is owned or inlined.
+    #[inline]
+    pub const fn as_borrowed(&self) -> Option<&'a str> {
+        self.repr.as_static_str()
+    }
+
+    /// Returns `true` if this [`CompactCow`] references a borrowed string.
+    #[inline]
+    pub const fn is_borrowed(&self) -> bool {
+        self.as_borrowed().is_some()
+    }
+
+    /// Returns the length of the [`CompactCow`] in `bytes`, not [`char`]s or graphemes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.repr.len()
+    }
+
+    /// Returns `true` if the [`CompactCow`] has a length of 0, `false` otherwise.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a string slice containing the entire [`CompactCow`].
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.repr.as_str()
+    }
+
+    /// Returns a mutable reference to the owned [`CompactString`], copying the borrowed string
+    /// first if needed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactCow;
+    /// let text = "a string that is long enough to be heap allocated";
+    /// let mut cow = CompactCow::borrowed(text);
+    ///
+    /// cow.to_mut().push('!');
+    /// assert!(!cow.is_borrowed());
+    /// assert_eq!(cow, "a string that is long enough to be heap allocated!");
+    /// ```
+    pub fn to_mut(&mut self) -> &mut CompactString {
+        if let Some(text) = self.as_borrowed() {
+            *self = CompactCow::owned(CompactString::new(text));
+        }
+        // SAFETY: `CompactString` is a `repr(transparent)` wrapper around `Repr`, and the `Repr`
+        // no longer borrows anything
+        unsafe { &mut *(&mut self.repr as *mut Repr as *mut CompactString) }
+    }
+
+    /// Converts this [`CompactCow`] into an owned [`CompactString`], copying the string if it's
+    /// borrowed.
+    #[inline]
+    pub fn into_owned(self) -> CompactString {
+        match self.as_borrowed() {
+            Some(text) => CompactString::new(text),
+            None => CompactString(self.repr),
+        }
+    }
+
+    /// Converts this [`CompactCow`] into a [`CompactCow`] that doesn't borrow anything, copying
+    /// the string if it's borrowed.
+    #[inline]
+    pub fn into_static(self) -> CompactCow<'static> {
+        CompactCow::owned(self.into_owned())
+    }
+}
+
+impl Clone for CompactCow<'_> {
+    #[inline]
+    fn clone(&self) -> Self {
+        CompactCow {
+            repr: self.repr.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl Default for CompactCow<'_> {
+    #[inline]
+    fn default() -> Self {
+        CompactCow::borrowed("")
+    }
+}
+
+impl Deref for CompactCow<'_> {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for CompactCow<'_> {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<[u8]> for CompactCow<'_> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl Borrow<str> for CompactCow<'_> {
+    #[inline]
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Eq for CompactCow<'_> {}
+
+impl<T: AsRef<str> + ?Sized> PartialEq<T> for CompactCow<'_> {
+    fn eq(&self, other: &T) -> bool {
+        self.as_str() == other.as_ref()
+    }
+}
+
+impl PartialEq<CompactCow<'_>> for str {
+    fn eq(&self, other: &CompactCow<'_>) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<CompactCow<'_>> for &str {
+    fn eq(&self, other: &CompactCow<'_>) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl PartialEq<CompactCow<'_>> for String {
+    fn eq(&self, other: &CompactCow<'_>) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Ord for CompactCow<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl PartialOrd for CompactCow<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for CompactCow<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl fmt::Debug for CompactCow<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for CompactCow<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl<'a> From<&'a str> for CompactCow<'a> {
+    #[inline]
+    fn from(text: &'a str) -> Self {
+        CompactCow::borrowed(text)
+    }
+}
+
+impl From<String> for CompactCow<'_> {
+    #[inline]
+    fn from(text: String) -> Self {
+        CompactCow::owned(CompactString::from(text))
+    }
+}
+
+impl From<CompactString> for CompactCow<'_> {
+    #[inline]
+    fn from(compact: CompactString) -> Self {
+        CompactCow::owned(compact)
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for CompactCow<'a> {
+    #[inline]
+    fn from(cow: Cow<'a, str>) -> Self {
+        match cow {
+            Cow::Borrowed(text) => CompactCow::borrowed(text),
+            Cow::Owned(text) => CompactCow::from(text),
+        }
+    }
+}
+
+impl From<CompactCow<'_>> for CompactString {
+    #[inline]
+    fn from(cow: CompactCow<'_>) -> Self {
+        cow.into_owned()
+    }
+}
+
+impl<'a> From<CompactCow<'a>> for Cow<'a, str> {
+    #[inline]
+    fn from(cow: CompactCow<'a>) -> Self {
+        match cow.as_borrowed() {
+            Some(text) => Cow::Borrowed(text),
+            None => Cow::Owned(cow.into_owned().into_string()),
+        }
+    }
+}
+
+static_assertions::assert_eq_size!(CompactCow<'_>, String);
+static_assertions::assert_eq_size!(Option<CompactCow<'_>>, CompactCow<'_>);
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::Cow;
+    use alloc::string::String;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use super::CompactCow;
+    use crate::repr::MAX_SIZE;
+    use crate::tests::rand_unicode;
+    use crate::CompactString;
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_borrowed(#[strategy(rand_unicode())] s: String) {
+        let cow = CompactCow::borrowed(&s);
+        prop_assert_eq!(&cow, &s);
+        prop_assert_eq!(cow.is_borrowed(), s.len() > MAX_SIZE);
+        prop_assert_eq!(cow.as_ptr() == s.as_ptr(), s.len() > MAX_SIZE);
+
+        let clone = cow.clone();
+        prop_assert_eq!(clone.as_borrowed(), cow.as_borrowed());
+
+        let owned = cow.into_owned();
+        prop_assert_eq!(&owned, &s);
+        prop_assert!(owned.as_static_str().is_none());
+        prop_assert_eq!(owned.is_heap_allocated(), s.len() > MAX_SIZE);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_owned(#[strategy(rand_unicode())] s: String) {
+        let compact = CompactString::new(&s);
+        let ptr = compact.as_ptr();
+
+        let cow = CompactCow::from(compact);
+        prop_assert!(!cow.is_borrowed());
+        prop_assert_eq!(&cow, &s);
+
+        // the buffer gets moved, not copied
+        let owned = cow.into_owned();
+        prop_assert_eq!(owned.as_ptr() == ptr, s.len() > MAX_SIZE);
+    }
+
+    #[test]
+    fn test_to_mut() {
+        let text = String::from("a string that is long enough to be heap allocated");
+        let mut cow = CompactCow::borrowed(&text);
+
+        cow.to_mut().make_ascii_uppercase();
+        assert!(!cow.is_borrowed());
+        assert_eq!(cow, text.to_ascii_uppercase());
+        assert_eq!(text, "a string that is long enough to be heap allocated");
+
+        let cow: CompactCow<'static> = CompactCow::borrowed(&text).into_static();
+        assert_eq!(cow, text);
+    }
+
+    #[test]
+    fn test_cow_conversions() {
+        let text = "a string that is long enough to be heap allocated";
+
+        let cow = CompactCow::from(Cow::Borrowed(text));
+        assert!(cow.is_borrowed());
+        assert!(matches!(Cow::from(cow), Cow::Borrowed(s) if s == text));
+
+        let cow = CompactCow::from(Cow::<str>::Owned(String::from(text)));
+        assert!(!cow.is_borrowed());
+        assert!(matches!(Cow::from(cow), Cow::Owned(s) if s == text));
+    }
+}
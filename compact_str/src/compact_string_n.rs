@@ -0,0 +1,1883 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::borrow::{Borrow, BorrowMut};
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::mem::MaybeUninit;
+use core::ops::{Add, AddAssign, Deref, DerefMut, RangeBounds};
+use core::str::{FromStr, Utf8Error};
+use core::{fmt, ptr, slice};
+
+use crate::repr::{ReprN, MAX_SIZE};
+use crate::{
+    ensure_range, CompactString, ReserveError, ToCompactStringError, UnwrapWithMsg, Utf16Error,
+};
+
+/// The inline capacity of a [`CompactStringN`].
+///
+/// It's only used to tell which inline capacities are supported, see [`SupportedInlineSize`].
+pub struct InlineSize<const N: usize>;
+
+/// An inline capacity that [`CompactStringN`] supports: every multiple of 8 from 16 up to 64.
+///
+/// This trait is sealed, it's implemented for [`InlineSize<N>`] of all of the supported `N`.
+pub trait SupportedInlineSize: sealed::SupportedInlineSize {}
+
+pub(crate) mod sealed {
+    pub trait SupportedInlineSize {
+        /// The first `N - 1` bytes of a [`ReprN<N>`](crate::repr::ReprN).
+        type Buffer: Copy;
+        /// Pads a `StaticStr` out to the end of a [`ReprN<N>`](crate::repr::ReprN).
+        type Padding: Copy;
+        const PADDING: Self::Padding;
+    }
+}
+
+macro_rules! supported_inline_sizes {
+    ($($n:literal),*) => {$(
+        impl SupportedInlineSize for InlineSize<$n> {}
+
+        impl sealed::SupportedInlineSize for InlineSize<$n> {
+            type Buffer = [MaybeUninit<u8>; $n - 1];
+            type Padding = [MaybeUninit<u8>; ($n as usize).saturating_sub(MAX_SIZE)];
+            const PADDING: Self::Padding =
+                [MaybeUninit::uninit(); ($n as usize).saturating_sub(MAX_SIZE)];
+        }
+
+        static_assertions::assert_eq_size!(CompactStringN<$n>, [u8; $n]);
+        static_assertions::assert_eq_size!(Option<CompactStringN<$n>>, [u8; $n]);
+    )*};
+}
+
+supported_inline_sizes!(16, 24, 32, 40, 48, 56, 64);
+
+/// A [`CompactStringN`] is a [`CompactString`] that can inline up to `N` bytes, instead of
+/// `3 * size_of::<usize>()`.
+///
+/// This lets you pick the inline capacity that matches your data, e.g. `16` for short tags, or
+/// `48` for keys that are usually 30 to 40 bytes long. `N` can be any multiple of 8 from 16 up to
+/// 64, see [`SupportedInlineSize`]. A [`CompactStringN<N>`] is exactly `N` bytes large, and just
+/// like a [`CompactString`] the compiler can use its last byte as a niche, so an
+/// `Option<CompactStringN<N>>` is `N` bytes large too.
+///
+/// Strings that don't fit inline are heap allocated, or reference a `&'static str`, just like a
+/// [`CompactString`]. A [`CompactStringN<16>`] on a 64-bit arch doesn't have room for a capacity,
+/// so its heap buffers store their capacity on the heap, and it can't re-use the buffer of a
+/// [`String`].
+///
+/// ```
+/// use compact_str::CompactStringN;
+///
+/// let mut key = CompactStringN::<48>::new("tenant-42/users/3f2a8c91/preferences");
+/// assert!(!key.is_heap_allocated());
+/// assert_eq!(std::mem::size_of_val(&key), 48);
+/// assert_eq!(std::mem::size_of::<Option<CompactStringN<48>>>(), 48);
+///
+/// key.push_str("/notifications/email");
+/// assert!(key.is_heap_allocated());
+///
+/// // CompactStringN derefs into a str
+/// assert!(key.starts_with("tenant-42/"));
+/// ```
+///
+/// Any other `N` fails to compile:
+///
+/// ```compile_fail
+/// use compact_str::CompactStringN;
+///
+/// let s = CompactStringN::<20>::new("hello");
+/// ```
+///
+/// # Converting to and from `CompactString`
+/// A [`CompactStringN`] has the same API as a [`String`], plus the constructors and case
+/// conversions of a [`CompactString`]. The other methods that are specific to [`CompactString`],
+/// like the in-place trimming, case folding, or hex encoding, are available by converting into a
+/// [`CompactString`] with [`From`], and back again. Heap allocated strings and `&'static str`s
+/// are converted without copying the text.
+///
+/// ```
+/// use compact_str::{CompactString, CompactStringN};
+///
+/// let tag = CompactStringN::<16>::new("  Release  ");
+/// let mut compact = CompactString::from(tag);
+/// compact.trim_in_place();
+/// assert_eq!(CompactStringN::<16>::from(compact).to_lowercase(), "release");
+/// ```
+pub struct CompactStringN<const N: usize>(ReprN<N>)
+where
+    InlineSize<N>: SupportedInlineSize;
+
+impl<const N: usize> CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    /// The maximum number of bytes a [`CompactStringN`] can store inline.
+    pub const MAX_INLINE: usize = N;
+
+    /// Creates a new, empty, [`CompactStringN`]. This can be used in a `static` or `const`
+    /// context.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// const EMPTY: CompactStringN<32> = CompactStringN::empty();
+    /// assert_eq!(EMPTY, "");
+    /// ```
+    #[inline]
+    pub const fn empty() -> Self {
+        CompactStringN(ReprN::empty())
+    }
+
+    /// Creates a new [`CompactStringN`] from any type that implements `AsRef<str>`. If the string
+    /// is at most `N` bytes long, then it will be inlined.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// let tag = CompactStringN::<16>::new("sixteen bytes!!!");
+    /// assert!(!tag.is_heap_allocated());
+    ///
+    /// let tag = CompactStringN::<16>::new("seventeen bytes!!");
+    /// assert!(tag.is_heap_allocated());
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn new<T: AsRef<str>>(text: T) -> Self {
+        Self::try_new(text).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactStringN::new()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactStringN::new()`].
+    #[inline]
+    pub fn try_new<T: AsRef<str>>(text: T) -> Result<Self, ReserveError> {
+        ReprN::new(text.as_ref()).map(CompactStringN)
+    }
+
+    /// Creates a new inline [`CompactStringN`] from `&'static str` at compile time. Strings that
+    /// don't fit inline reference the `&'static str`, see [`CompactString::const_new`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// const DEFAULT_NAME: CompactStringN<16> = CompactStringN::const_new("untitled");
+    /// const LONG: CompactStringN<16> = CompactStringN::const_new("a name that doesn't fit");
+    ///
+    /// assert_eq!(DEFAULT_NAME.as_static_str(), None);
+    /// assert_eq!(LONG.as_static_str(), Some("a name that doesn't fit"));
+    /// ```
+    #[inline]
+    pub const fn const_new(text: &'static str) -> Self {
+        CompactStringN(ReprN::const_new(text))
+    }
+
+    /// Get back the `&'static str` constructed by [`CompactStringN::const_new`].
+    ///
+    /// If the string was short enough that it could be inlined, then it was inline, and this
+    /// method will return `None`.
+    #[inline]
+    pub const fn as_static_str(&self) -> Option<&'static str> {
+        self.0.as_static_str()
+    }
+
+    /// Creates a new, empty, [`CompactStringN`] with the capacity to fit at least `capacity`
+    /// bytes. If `capacity <= N` nothing gets allocated.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// let s = CompactStringN::<32>::with_capacity(32);
+    /// assert_eq!(s.capacity(), 32);
+    /// assert!(!s.is_heap_allocated());
+    ///
+    /// let s = CompactStringN::<32>::with_capacity(100);
+    /// assert!(s.capacity() >= 100);
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::try_with_capacity(capacity).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactStringN::with_capacity()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactStringN::with_capacity()`].
+    #[inline]
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, ReserveError> {
+        ReprN::with_capacity(capacity).map(CompactStringN)
+    }
+
+    /// Converts a buffer of bytes to a [`CompactStringN`], see [`CompactString::from_utf8`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// let s = CompactStringN::<16>::from_utf8(b"hello").unwrap();
+    /// assert_eq!(s, "hello");
+    ///
+    /// assert!(CompactStringN::<16>::from_utf8([0xFF]).is_err());
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn from_utf8<B: AsRef<[u8]>>(buf: B) -> Result<Self, Utf8Error> {
+        core::str::from_utf8(buf.as_ref()).map(Self::new)
+    }
+
+    /// Fallible version of [`CompactStringN::from_utf8()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return a
+    /// [`ToCompactStringError::Reserve`]. Invalid UTF-8 is reported as a
+    /// [`ToCompactStringError::Utf8`].
+    #[inline]
+    pub fn try_from_utf8<B: AsRef<[u8]>>(buf: B) -> Result<Self, ToCompactStringError> {
+        let s = core::str::from_utf8(buf.as_ref())?;
+        Ok(Self::try_new(s)?)
+    }
+
+    /// Converts a buffer of bytes to a [`CompactStringN`] without checking that it contains
+    /// valid UTF-8, see [`CompactString::from_utf8_unchecked`].
+    ///
+    /// # Safety
+    /// * The contents passed to this method must be valid UTF-8.
+    #[inline]
+    #[must_use]
+    #[track_caller]
+    pub unsafe fn from_utf8_unchecked<B: AsRef<[u8]>>(buf: B) -> Self {
+        Self::new(core::str::from_utf8_unchecked(buf.as_ref()))
+    }
+
+    /// Decodes a bytes slice as UTF-8, replacing any invalid sequences with the replacement
+    /// character (`U+FFFD`), see [`CompactString::from_utf8_lossy`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// assert_eq!(CompactStringN::<16>::from_utf8_lossy(b"\xf0\x9f\xc8\x84"), "�Ȅ");
+    /// ```
+    pub fn from_utf8_lossy(v: &[u8]) -> Self {
+        let mut error = match core::str::from_utf8(v) {
+            Ok(valid) => return Self::new(valid),
+            Err(error) => error,
+        };
+
+        let mut result = Self::with_capacity(v.len());
+        let mut remaining = v;
+        loop {
+            let valid_up_to = error.valid_up_to();
+            // SAFETY: `remaining[..valid_up_to]` is valid UTF-8, by definition of `valid_up_to`.
+            let valid = unsafe { core::str::from_utf8_unchecked(&remaining[..valid_up_to]) };
+            result.push_str(valid);
+            result.push(char::REPLACEMENT_CHARACTER);
+
+            let invalid_len = match error.error_len() {
+                Some(len) => len,
+                None => return result,
+            };
+            remaining = &remaining[valid_up_to + invalid_len..];
+            match core::str::from_utf8(remaining) {
+                Ok(valid) => {
+                    result.push_str(valid);
+                    return result;
+                }
+                Err(next) => error = next,
+            }
+        }
+    }
+
+    /// Decode a UTF-16 slice into a [`CompactStringN`], returning an [`Err`] if the slice
+    /// contains any invalid data.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// let buf: &[u16] = &[0xD834, 0xDD1E, 0x006d, 0x0075, 0x0073, 0x0069, 0x0063];
+    /// assert_eq!(CompactStringN::<16>::from_utf16(buf).unwrap(), "𝄞music");
+    ///
+    /// let buf: &[u16] = &[0xD834, 0xDD1E, 0x006d, 0x0075, 0xD800, 0x0069, 0x0063];
+    /// assert!(CompactStringN::<16>::from_utf16(buf).is_err());
+    /// ```
+    pub fn from_utf16<B: AsRef<[u16]>>(buf: B) -> Result<Self, Utf16Error> {
+        let buf = buf.as_ref();
+        let mut ret = Self::with_capacity(buf.len());
+        for c in core::char::decode_utf16(buf.iter().copied()) {
+            ret.push(c.map_err(|_| Utf16Error(()))?);
+        }
+        Ok(ret)
+    }
+
+    /// Decode a UTF-16 slice into a [`CompactStringN`], replacing invalid data with the
+    /// replacement character (`U+FFFD`).
+    pub fn from_utf16_lossy<B: AsRef<[u16]>>(buf: B) -> Self {
+        let buf = buf.as_ref();
+        let mut ret = Self::with_capacity(buf.len());
+        for c in core::char::decode_utf16(buf.iter().copied()) {
+            ret.push(c.unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+        ret
+    }
+
+    /// Decode a slice of bytes as UTF-16 encoded string, in little endian, see
+    /// [`CompactString::from_utf16le`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// const DANCING_MEN: &[u8] = b"\x3d\xd8\x6f\xdc\x0d\x20\x42\x26\x0f\xfe";
+    /// let dancing_men = CompactStringN::<16>::from_utf16le(DANCING_MEN).unwrap();
+    /// assert_eq!(dancing_men, "👯‍♂️");
+    /// ```
+    #[inline]
+    pub fn from_utf16le(v: impl AsRef<[u8]>) -> Result<Self, Utf16Error> {
+        CompactString::from_utf16le(v).map(Self::from)
+    }
+
+    /// Decode a slice of bytes as UTF-16 encoded string, in big endian, see
+    /// [`CompactString::from_utf16be`].
+    #[inline]
+    pub fn from_utf16be(v: impl AsRef<[u8]>) -> Result<Self, Utf16Error> {
+        CompactString::from_utf16be(v).map(Self::from)
+    }
+
+    /// Lossy decode a slice of bytes as UTF-16 encoded string, in little endian, see
+    /// [`CompactString::from_utf16le_lossy`].
+    #[inline]
+    pub fn from_utf16le_lossy(v: impl AsRef<[u8]>) -> Self {
+        Self::from(CompactString::from_utf16le_lossy(v))
+    }
+
+    /// Lossy decode a slice of bytes as UTF-16 encoded string, in big endian, see
+    /// [`CompactString::from_utf16be_lossy`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// // A "random" bit was flipped in the 9th byte:
+    /// const DANCING_WOMEN: &[u8] = b"\xd8\x3d\xdc\x6f\x20\x0d\x26\x40\xde\x0f";
+    /// let dancing_women = CompactStringN::<16>::from_utf16be_lossy(DANCING_WOMEN);
+    /// assert_eq!(dancing_women, "👯\u{200d}♀�");
+    /// ```
+    #[inline]
+    pub fn from_utf16be_lossy(v: impl AsRef<[u8]>) -> Self {
+        Self::from(CompactString::from_utf16be_lossy(v))
+    }
+
+    /// Returns the length of the [`CompactStringN`] in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the [`CompactStringN`] has a length of 0.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the capacity of the [`CompactStringN`], which is at least `N`, unless it
+    /// references a `&'static str`.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+
+    /// Returns whether or not the [`CompactStringN`] is heap allocated.
+    #[inline]
+    pub fn is_heap_allocated(&self) -> bool {
+        self.0.is_heap_allocated()
+    }
+
+    /// Ensures that this [`CompactStringN`]'s capacity is at least `additional` bytes longer than
+    /// its length. Strings that no longer fit inline are moved onto the heap.
+    ///
+    /// # Panics
+    /// Panics if the new capacity overflows `usize`, or if the allocation fails.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// let mut s = CompactStringN::<16>::new("hello");
+    /// s.reserve(100);
+    /// assert!(s.capacity() >= 105);
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn reserve(&mut self, additional: usize) {
+        self.try_reserve(additional).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactStringN::reserve()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactStringN::reserve()`].
+    #[inline]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), ReserveError> {
+        self.0.reserve(additional)
+    }
+
+    /// Returns a string slice containing the entire [`CompactStringN`].
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    /// Returns a mutable string slice containing the entire [`CompactStringN`].
+    #[inline]
+    pub fn as_mut_str(&mut self) -> &mut str {
+        // SAFETY: We only expose the buffer as a `&mut str`, which can only hold valid UTF-8
+        unsafe { core::str::from_utf8_unchecked_mut(self.as_mut_bytes()) }
+    }
+
+    /// Returns a byte slice of the [`CompactStringN`]'s contents.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+
+    /// Provides a mutable reference to the initialized bytes in this [`CompactStringN`].
+    ///
+    /// # Safety
+    /// * The caller must guarantee that any modifications made to the underlying buffer are
+    ///   valid UTF-8.
+    #[inline]
+    pub unsafe fn as_mut_bytes(&mut self) -> &mut [u8] {
+        let len = self.len();
+        // SAFETY: The first `len` bytes of a `CompactStringN` are initialized
+        slice::from_raw_parts_mut(self.0.as_mut_ptr(), len)
+    }
+
+    /// Returns the remaining spare capacity of this [`CompactStringN`] as a slice of
+    /// [`MaybeUninit`] bytes, see [`CompactString::spare_capacity_mut`].
+    ///
+    /// # Safety
+    /// * Before increasing the length, the caller must initialize every newly included byte and
+    ///   ensure the resulting string is valid UTF-8.
+    /// * For an inline string, the last bytes of the spare capacity also store its length. They
+    ///   may only be overwritten as part of a string that's then set with
+    ///   [`CompactStringN::set_len`].
+    #[inline]
+    pub unsafe fn spare_capacity_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        let len = self.len();
+        let ptr = self.0.as_mut_ptr();
+        let cap = self.capacity();
+        // SAFETY: The buffer is valid for `cap` bytes, and `len <= cap`
+        slice::from_raw_parts_mut(ptr.add(len).cast::<MaybeUninit<u8>>(), cap - len)
+    }
+
+    /// Converts a [`CompactStringN`] to a raw pointer.
+    #[inline]
+    pub fn as_ptr(&self) -> *const u8 {
+        self.as_bytes().as_ptr()
+    }
+
+    /// Converts a mutable [`CompactStringN`] to a raw pointer.
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.0.as_mut_ptr()
+    }
+
+    /// Forces the length of the [`CompactStringN`] to `new_len`.
+    ///
+    /// # Safety
+    /// * `new_len` must be less than or equal to `capacity()`
+    /// * The elements at `old_len..new_len` must be initialized, and the first `new_len` bytes
+    ///   must be valid UTF-8
+    #[inline]
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        self.0.set_len(new_len)
+    }
+
+    /// Appends the given [`char`] to the end of this [`CompactStringN`].
+    #[inline]
+    pub fn push(&mut self, ch: char) {
+        self.push_str(ch.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Fallible version of [`CompactStringN::push()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactStringN::push()`].
+    #[inline]
+    pub fn try_push(&mut self, ch: char) -> Result<(), ReserveError> {
+        self.try_push_str(ch.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Removes the last character from the [`CompactStringN`] and returns it.
+    /// Returns `None` if this [`CompactStringN`] is empty.
+    #[inline]
+    pub fn pop(&mut self) -> Option<char> {
+        self.0.pop()
+    }
+
+    /// Appends a string slice onto the end of the [`CompactStringN`].
+    #[inline]
+    pub fn push_str(&mut self, s: &str) {
+        self.0.push_str(s)
+    }
+
+    /// Fallible version of [`CompactStringN::push_str()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactStringN::push_str()`].
+    #[inline]
+    pub fn try_push_str(&mut self, s: &str) -> Result<(), ReserveError> {
+        self.0.try_push_str(s)
+    }
+
+    /// Inserts a string slice into the [`CompactStringN`] at byte position `idx`.
+    ///
+    /// # Panics
+    /// Panics if `idx` is larger than the string's length, or does not lie on a [`char`]
+    /// boundary.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// let mut s = CompactStringN::<16>::new("hello world");
+    /// s.insert_str(5, ",");
+    /// assert_eq!(s, "hello, world");
+    /// ```
+    #[track_caller]
+    pub fn insert_str(&mut self, idx: usize, string: &str) {
+        self.try_insert_str(idx, string).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactStringN::insert_str()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactStringN::insert_str()`].
+    ///
+    /// # Panics
+    /// Panics if `idx` does not lie on a [`char`] boundary.
+    pub fn try_insert_str(&mut self, idx: usize, string: &str) -> Result<(), ReserveError> {
+        self.try_replace_range(idx..idx, string)
+    }
+
+    /// Inserts a [`char`] into the [`CompactStringN`] at byte position `idx`.
+    ///
+    /// # Panics
+    /// Panics if `idx` is larger than the string's length, or does not lie on a [`char`]
+    /// boundary.
+    #[inline]
+    pub fn insert(&mut self, idx: usize, ch: char) {
+        self.insert_str(idx, ch.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Fallible version of [`CompactStringN::insert()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactStringN::insert()`].
+    #[inline]
+    pub fn try_insert(&mut self, idx: usize, ch: char) -> Result<(), ReserveError> {
+        self.try_insert_str(idx, ch.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Removes a [`char`] from the [`CompactStringN`] at byte position `idx` and returns it.
+    ///
+    /// # Panics
+    /// Panics if `idx` is larger than or equal to the string's length, or does not lie on a
+    /// [`char`] boundary.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// let mut s = CompactStringN::<16>::new("Hello, world!");
+    /// assert_eq!(s.remove(5), ',');
+    /// assert_eq!(s, "Hello world!");
+    /// ```
+    pub fn remove(&mut self, idx: usize) -> char {
+        let ch = self[idx..]
+            .chars()
+            .next()
+            .expect("cannot remove a char from the end of a string");
+        self.replace_range(idx..idx + ch.len_utf8(), "");
+        ch
+    }
+
+    /// Removes the specified range in the [`CompactStringN`], and replaces it with the given
+    /// string, which doesn't need to be the same length as the range.
+    ///
+    /// # Panics
+    /// Panics if the starting point or end point do not lie on a [`char`] boundary, or if
+    /// they're out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// let mut s = CompactStringN::<16>::new("Hello, world!");
+    ///
+    /// s.replace_range(7..12, "WORLD");
+    /// assert_eq!(s, "Hello, WORLD!");
+    ///
+    /// s.replace_range(5.., "! Is it me you're looking for?");
+    /// assert_eq!(s, "Hello! Is it me you're looking for?");
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn replace_range(&mut self, range: impl RangeBounds<usize>, replace_with: &str) {
+        self.try_replace_range(range, replace_with)
+            .unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactStringN::replace_range()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactStringN::replace_range()`].
+    ///
+    /// # Panics
+    /// Panics if the starting point or end point do not lie on a [`char`] boundary, or if
+    /// they're out of bounds.
+    pub fn try_replace_range(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        replace_with: &str,
+    ) -> Result<(), ReserveError> {
+        let (start, end) = ensure_range(self, range);
+        let len = self.len();
+        if replace_with.len() > end - start {
+            self.try_reserve(replace_with.len() - (end - start))?;
+        } else {
+            self.0.try_make_mut()?;
+        }
+
+        let new_end = start + replace_with.len();
+        // SAFETY: We have capacity for the new length, `start` and `end` are char boundaries,
+        // and we replace `start..end` with valid UTF-8 before setting the new length
+        unsafe {
+            let data = self.as_mut_ptr();
+            // first move the tail of the string to its new place, then insert the replacement
+            ptr::copy(data.add(end), data.add(new_end), len - end);
+            ptr::copy_nonoverlapping(replace_with.as_ptr(), data.add(start), replace_with.len());
+            self.set_len(new_end + len - end);
+        }
+        Ok(())
+    }
+
+    /// Creates a new [`CompactStringN`] by repeating a string `n` times.
+    ///
+    /// # Panics
+    /// Panics if the capacity would overflow.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// assert_eq!(CompactStringN::<16>::new("abc").repeat(4), "abcabcabcabc");
+    /// ```
+    #[must_use]
+    #[track_caller]
+    pub fn repeat(&self, n: usize) -> Self {
+        self.try_repeat(n).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactStringN::repeat()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, or if the capacity would overflow,
+    /// but return an [`ReserveError`]. Otherwise it behaves the same as
+    /// [`CompactStringN::repeat()`].
+    pub fn try_repeat(&self, n: usize) -> Result<Self, ReserveError> {
+        let cap = self.len().checked_mul(n).ok_or(ReserveError(()))?;
+        let mut out = Self::try_with_capacity(cap)?;
+        (0..n).for_each(|_| out.push_str(self));
+        Ok(out)
+    }
+
+    /// Fallible version of [`Clone::clone()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Only heap allocated strings need to allocate when they get cloned.
+    #[inline]
+    pub fn try_clone(&self) -> Result<Self, ReserveError> {
+        if self.is_heap_allocated() {
+            Self::try_new(self)
+        } else {
+            Ok(self.clone())
+        }
+    }
+
+    /// Appends every item of `iter` to the end of this [`CompactStringN`].
+    ///
+    /// This is a fallible version of [`Extend::extend()`]: it won't panic if the system is
+    /// out-of-memory, but return an [`ReserveError`]. The items that were appended before the
+    /// allocation failed are kept.
+    pub fn try_extend<I, S>(&mut self, iter: I) -> Result<(), ReserveError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        iter.into_iter()
+            .try_for_each(|s| self.try_push_str(s.as_ref()))
+    }
+
+    /// Truncates the [`CompactStringN`] to a shorter length. If the length is already less or
+    /// equal to `new_len`, this is a no-op.
+    ///
+    /// # Panics
+    /// Panics if the new end of the string does not lie on a [`char`] boundary.
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len >= self.len() {
+            return;
+        }
+        assert!(
+            self.is_char_boundary(new_len),
+            "new_len must lie on char boundary",
+        );
+        // SAFETY: `new_len` is less than our length, and a char boundary
+        unsafe { self.set_len(new_len) };
+    }
+
+    /// Truncates the [`CompactStringN`] to zero length. This does not change its capacity,
+    /// unless it references a `&'static str`.
+    #[inline]
+    pub fn clear(&mut self) {
+        // SAFETY: An empty string is always valid
+        unsafe { self.set_len(0) };
+    }
+
+    /// Splits the [`CompactStringN`] in two at the given byte index, and returns the second
+    /// half.
+    ///
+    /// # Panics
+    /// Panics if `at` does not lie on a [`char`] boundary.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// let mut s = CompactStringN::<16>::const_new("Hello, world!");
+    /// let w = s.split_off(5);
+    ///
+    /// assert_eq!(w, ", world!");
+    /// assert_eq!(s, "Hello");
+    /// ```
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let result = match self.as_static_str() {
+            Some(s) => Self::const_new(&s[at..]),
+            None => Self::new(&self[at..]),
+        };
+        // SAFETY: `self[at..]` would have panicked if `at` was invalid
+        unsafe { self.set_len(at) };
+        result
+    }
+
+    /// Remove a range from the [`CompactStringN`], and return it as an iterator.
+    ///
+    /// Calling this function does not change the capacity of the [`CompactStringN`].
+    ///
+    /// # Panics
+    /// Panics if the start or end of the range does not lie on a [`char`] boundary.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// let mut s = CompactStringN::<16>::new("Hello, world!");
+    ///
+    /// let mut d = s.drain(5..12);
+    /// assert_eq!(d.next(), Some(','));
+    /// assert_eq!(d.as_str(), " world");
+    ///
+    /// drop(d);
+    /// assert_eq!(s, "Hello!");
+    /// ```
+    pub fn drain(&mut self, range: impl RangeBounds<usize>) -> DrainN<'_, N> {
+        let (start, end) = ensure_range(self, range);
+        DrainN {
+            compact_string: self as *mut Self,
+            start,
+            end,
+            chars: self[start..end].chars(),
+        }
+    }
+
+    /// Retains only the characters specified by the predicate.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// let mut s = CompactStringN::<16>::from("äb𝄞d€");
+    /// s.retain(|ch| ch != 'd' && ch != 'ä');
+    /// assert_eq!(s, "b𝄞€");
+    /// ```
+    pub fn retain(&mut self, mut predicate: impl FnMut(char) -> bool) {
+        struct SetLenOnDrop<'a, const N: usize>
+        where
+            InlineSize<N>: SupportedInlineSize,
+        {
+            self_: &'a mut CompactStringN<N>,
+            dst_idx: usize,
+        }
+
+        impl<const N: usize> Drop for SetLenOnDrop<'_, N>
+        where
+            InlineSize<N>: SupportedInlineSize,
+        {
+            fn drop(&mut self) {
+                // SAFETY: We only ever keep whole characters, so this is a char boundary
+                unsafe { self.self_.set_len(self.dst_idx) };
+            }
+        }
+
+        let original_len = self.len();
+        let ptr = self.0.as_mut_ptr();
+        let mut g = SetLenOnDrop {
+            self_: self,
+            dst_idx: 0,
+        };
+        let mut src_idx = 0;
+        while src_idx < original_len {
+            // SAFETY: Everything at and after `src_idx` is an untouched suffix of the original
+            // string, so it's initialized, valid UTF-8, and non-empty.
+            let ch = unsafe {
+                let suffix = slice::from_raw_parts(ptr.add(src_idx), original_len - src_idx);
+                core::str::from_utf8_unchecked(suffix)
+                    .chars()
+                    .next()
+                    .expect("source suffix is non-empty")
+            };
+            let ch_len = ch.len_utf8();
+            if predicate(ch) {
+                // SAFETY: Both ranges are in our buffer, and the destination ends no later than
+                // the current character, so the unprocessed suffix remains unchanged
+                unsafe { ptr::copy(ptr.add(src_idx), ptr.add(g.dst_idx), ch_len) };
+                g.dst_idx += ch_len;
+            }
+            src_idx += ch_len;
+        }
+    }
+
+    /// Shrinks the capacity of this [`CompactStringN`] with a lower bound. A heap allocated
+    /// string that fits in `N` bytes is moved back inline.
+    #[inline]
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.0.shrink_to(min_capacity);
+    }
+
+    /// Shrinks the capacity of the [`CompactStringN`] to match its length. A heap allocated
+    /// string that fits in `N` bytes is moved back inline.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// let mut s = CompactStringN::<16>::with_capacity(100);
+    /// s.push_str("short");
+    /// assert!(s.is_heap_allocated());
+    ///
+    /// s.shrink_to_fit();
+    /// assert!(!s.is_heap_allocated());
+    /// assert_eq!(s.capacity(), 16);
+    /// ```
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.0.shrink_to(0);
+    }
+
+    /// Converts the [`CompactStringN`] into a [`String`], re-using the existing allocation of
+    /// heap allocated strings when possible.
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.0.into_string()
+    }
+
+    /// Convert a [`String`] into a [`CompactStringN`] _without inlining_, see
+    /// [`CompactString::from_string_buffer`].
+    ///
+    /// A [`CompactStringN<16>`] on a 64-bit arch can't re-use the buffer of a [`String`], so it
+    /// behaves like `From<String>`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// let og = "hello world".to_string();
+    /// let og_addr = og.as_ptr();
+    ///
+    /// let c = CompactStringN::<24>::from_string_buffer(og);
+    /// assert!(c.is_heap_allocated());
+    /// assert_eq!(c.as_ptr(), og_addr);
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn from_string_buffer(s: String) -> Self {
+        CompactStringN(ReprN::from_string(s, false).unwrap_with_msg())
+    }
+
+    /// Returns a copy of this string where each character is mapped to its ASCII lower case
+    /// equivalent, see [`CompactString::to_ascii_lowercase`].
+    #[must_use = "to lowercase the value in-place, use `make_ascii_lowercase()`"]
+    #[inline]
+    pub fn to_ascii_lowercase(&self) -> Self {
+        let mut s = self.clone();
+        s.make_ascii_lowercase();
+        s
+    }
+
+    /// Returns a copy of this string where each character is mapped to its ASCII upper case
+    /// equivalent, see [`CompactString::to_ascii_uppercase`].
+    #[must_use = "to uppercase the value in-place, use `make_ascii_uppercase()`"]
+    #[inline]
+    pub fn to_ascii_uppercase(&self) -> Self {
+        let mut s = self.clone();
+        s.make_ascii_uppercase();
+        s
+    }
+
+    /// Returns the lowercase equivalent of this string slice, as a new [`CompactStringN`], see
+    /// [`CompactString::to_lowercase`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// let odysseus = CompactStringN::<16>::new("ὈΔΥΣΣΕΎΣ");
+    /// assert_eq!("ὀδυσσεύς", odysseus.to_lowercase());
+    /// ```
+    #[must_use = "this returns the lowercase string as a new CompactStringN, \
+                  without modifying the original"]
+    pub fn to_lowercase(&self) -> Self {
+        Self::from_str_to_lowercase(self.as_str())
+    }
+
+    /// Fallible version of [`CompactStringN::to_lowercase()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactStringN::to_lowercase()`].
+    #[inline]
+    pub fn try_to_lowercase(&self) -> Result<Self, ReserveError> {
+        Self::try_from_str_to_lowercase(self.as_str())
+    }
+
+    /// Returns the lowercase equivalent of a string slice, as a new [`CompactStringN`], see
+    /// [`CompactString::from_str_to_lowercase`].
+    #[must_use = "this returns the lowercase string as a new CompactStringN, \
+                  without modifying the original"]
+    #[track_caller]
+    pub fn from_str_to_lowercase(input: &str) -> Self {
+        Self::try_from_str_to_lowercase(input).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactStringN::from_str_to_lowercase()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactStringN::from_str_to_lowercase()`].
+    pub fn try_from_str_to_lowercase(input: &str) -> Result<Self, ReserveError> {
+        CompactString::try_from_str_to_lowercase(input).map(Self::from)
+    }
+
+    /// Returns the uppercase equivalent of this string slice, as a new [`CompactStringN`], see
+    /// [`CompactString::to_uppercase`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStringN;
+    /// let s = CompactStringN::<16>::new("tschüß");
+    /// assert_eq!("TSCHÜSS", s.to_uppercase());
+    /// ```
+    #[must_use = "this returns the uppercase string as a new CompactStringN, \
+                  without modifying the original"]
+    pub fn to_uppercase(&self) -> Self {
+        Self::from_str_to_uppercase(self.as_str())
+    }
+
+    /// Fallible version of [`CompactStringN::to_uppercase()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactStringN::to_uppercase()`].
+    #[inline]
+    pub fn try_to_uppercase(&self) -> Result<Self, ReserveError> {
+        Self::try_from_str_to_uppercase(self.as_str())
+    }
+
+    /// Returns the uppercase equivalent of a string slice, as a new [`CompactStringN`], see
+    /// [`CompactString::from_str_to_uppercase`].
+    #[must_use = "this returns the uppercase string as a new CompactStringN, \
+                  without modifying the original"]
+    #[track_caller]
+    pub fn from_str_to_uppercase(input: &str) -> Self {
+        Self::try_from_str_to_uppercase(input).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactStringN::from_str_to_uppercase()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactStringN::from_str_to_uppercase()`].
+    pub fn try_from_str_to_uppercase(input: &str) -> Result<Self, ReserveError> {
+        CompactString::try_from_str_to_uppercase(input).map(Self::from)
+    }
+}
+
+/// An iterator over the exacted data by [`CompactStringN::drain()`].
+///
+/// Like [`Drain`](crate::Drain), dropping a `DrainN` removes the selected range from the source
+/// [`CompactStringN`], even if the iterator wasn't consumed.
+pub struct DrainN<'a, const N: usize>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    compact_string: *mut CompactStringN<N>,
+    start: usize,
+    end: usize,
+    chars: core::str::Chars<'a>,
+}
+
+// SAFETY: DrainN keeps the lifetime of the CompactStringN it belongs to.
+unsafe impl<const N: usize> Send for DrainN<'_, N> where InlineSize<N>: SupportedInlineSize {}
+unsafe impl<const N: usize> Sync for DrainN<'_, N> where InlineSize<N>: SupportedInlineSize {}
+
+impl<const N: usize> fmt::Debug for DrainN<'_, N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DrainN").field(&self.as_str()).finish()
+    }
+}
+
+impl<const N: usize> fmt::Display for DrainN<'_, N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<const N: usize> Drop for DrainN<'_, N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn drop(&mut self) {
+        // SAFETY: DrainN keeps a mutable reference to compact_string, so no one else can access
+        //         the CompactStringN, but this function right now. CompactStringN::drain()
+        //         ensured that the extracted range does not split a UTF-8 character.
+        unsafe { (*self.compact_string).replace_range(self.start..self.end, "") };
+    }
+}
+
+impl<const N: usize> DrainN<'_, N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    /// The remaining, unconsumed characters of the extracted substring.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.chars.as_str()
+    }
+}
+
+impl<const N: usize> Deref for DrainN<'_, N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> Iterator for DrainN<'_, N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        self.chars.next()
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.chars.clone().count()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chars.size_hint()
+    }
+
+    #[inline]
+    fn last(mut self) -> Option<char> {
+        self.chars.next_back()
+    }
+}
+
+impl<const N: usize> DoubleEndedIterator for DrainN<'_, N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<char> {
+        self.chars.next_back()
+    }
+}
+
+impl<const N: usize> core::iter::FusedIterator for DrainN<'_, N> where
+    InlineSize<N>: SupportedInlineSize
+{
+}
+
+impl<const N: usize> Clone for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        CompactStringN(self.0.clone())
+    }
+}
+
+impl<const N: usize> Default for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<const N: usize> Deref for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> DerefMut for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn deref_mut(&mut self) -> &mut str {
+        self.as_mut_str()
+    }
+}
+
+impl<const N: usize> AsRef<str> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> AsRef<std::ffi::OsStr> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn as_ref(&self) -> &std::ffi::OsStr {
+        std::ffi::OsStr::new(self.as_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> AsRef<std::path::Path> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn as_ref(&self) -> &std::path::Path {
+        std::path::Path::new(self.as_str())
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<const N: usize> Borrow<str> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> BorrowMut<str> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn borrow_mut(&mut self) -> &mut str {
+        self.as_mut_str()
+    }
+}
+
+impl<const N: usize> Eq for CompactStringN<N> where InlineSize<N>: SupportedInlineSize {}
+
+impl<const N: usize, T: AsRef<str> + ?Sized> PartialEq<T> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn eq(&self, other: &T) -> bool {
+        self.as_str() == other.as_ref()
+    }
+}
+
+macro_rules! partial_eq_reverse {
+    ($($ty:ty),*) => {$(
+        impl<const N: usize> PartialEq<CompactStringN<N>> for $ty
+        where
+            InlineSize<N>: SupportedInlineSize,
+        {
+            #[inline]
+            fn eq(&self, other: &CompactStringN<N>) -> bool {
+                AsRef::<str>::as_ref(self) == other.as_str()
+            }
+        }
+    )*};
+}
+
+partial_eq_reverse!(str, &str, String, &String, Cow<'_, str>);
+
+impl<const N: usize> Ord for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl<const N: usize> PartialOrd for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Hash for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl<const N: usize> fmt::Debug for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> fmt::Display for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> fmt::Write for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+impl<const N: usize> FromStr for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    type Err = core::convert::Infallible;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(s))
+    }
+}
+
+impl<'a, const N: usize> From<&'a str> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    #[track_caller]
+    fn from(s: &'a str) -> Self {
+        Self::new(s)
+    }
+}
+
+impl<'a, const N: usize> From<&'a String> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    #[track_caller]
+    fn from(s: &'a String) -> Self {
+        Self::new(s)
+    }
+}
+
+impl<const N: usize> From<String> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    /// Strings that don't fit inline re-use the allocation of the [`String`], except for a
+    /// [`CompactStringN<16>`] on a 64-bit arch.
+    #[inline]
+    #[track_caller]
+    fn from(s: String) -> Self {
+        CompactStringN(ReprN::from_string(s, true).unwrap_with_msg())
+    }
+}
+
+impl<const N: usize> From<Box<str>> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    #[track_caller]
+    fn from(b: Box<str>) -> Self {
+        Self::from(b.into_string())
+    }
+}
+
+impl<'a, const N: usize> From<Cow<'a, str>> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    #[track_caller]
+    fn from(s: Cow<'a, str>) -> Self {
+        match s {
+            Cow::Borrowed(s) => Self::new(s),
+            Cow::Owned(s) => Self::from(s),
+        }
+    }
+}
+
+impl<const N: usize> From<CompactString> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    #[track_caller]
+    fn from(s: CompactString) -> Self {
+        if let Some(s) = s.as_static_str() {
+            Self::const_new(s)
+        } else if s.len() > N {
+            Self::from(s.into_string())
+        } else {
+            Self::new(s)
+        }
+    }
+}
+
+impl<const N: usize> From<CompactStringN<N>> for CompactString
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn from(s: CompactStringN<N>) -> Self {
+        if let Some(s) = s.as_static_str() {
+            CompactString::const_new(s)
+        } else if s.is_heap_allocated() {
+            CompactString::from(s.into_string())
+        } else {
+            CompactString::new(s)
+        }
+    }
+}
+
+impl<const N: usize> From<CompactStringN<N>> for String
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn from(s: CompactStringN<N>) -> Self {
+        s.into_string()
+    }
+}
+
+impl<const N: usize> From<CompactStringN<N>> for Cow<'_, str>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn from(s: CompactStringN<N>) -> Self {
+        match s.as_static_str() {
+            Some(s) => Cow::Borrowed(s),
+            None => Cow::Owned(s.into_string()),
+        }
+    }
+}
+
+impl<'a, const N: usize> From<&'a CompactStringN<N>> for Cow<'a, str>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn from(s: &'a CompactStringN<N>) -> Self {
+        Cow::Borrowed(s)
+    }
+}
+
+impl<const N: usize> From<CompactStringN<N>> for Box<str>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn from(value: CompactStringN<N>) -> Self {
+        if value.is_heap_allocated() {
+            value.into_string().into_boxed_str()
+        } else {
+            Box::from(value.as_str())
+        }
+    }
+}
+
+impl<const N: usize> From<CompactStringN<N>> for alloc::vec::Vec<u8>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn from(value: CompactStringN<N>) -> Self {
+        if value.is_heap_allocated() {
+            value.into_string().into_bytes()
+        } else {
+            value.as_bytes().to_vec()
+        }
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<const N: usize> From<CompactStringN<N>> for alloc::sync::Arc<str>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn from(value: CompactStringN<N>) -> Self {
+        Self::from(value.as_str())
+    }
+}
+
+impl<const N: usize> From<CompactStringN<N>> for alloc::rc::Rc<str>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn from(value: CompactStringN<N>) -> Self {
+        Self::from(value.as_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> From<CompactStringN<N>> for Box<dyn std::error::Error + Send + Sync>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn from(value: CompactStringN<N>) -> Self {
+        From::from(CompactString::from(value))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> From<CompactStringN<N>> for Box<dyn std::error::Error>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn from(value: CompactStringN<N>) -> Self {
+        From::from(CompactString::from(value))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> From<CompactStringN<N>> for std::ffi::OsString
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn from(value: CompactStringN<N>) -> Self {
+        Self::from(value.into_string())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> From<CompactStringN<N>> for std::path::PathBuf
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn from(value: CompactStringN<N>) -> Self {
+        Self::from(value.into_string())
+    }
+}
+
+impl<const N: usize> FromIterator<char> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
+        let mut s = Self::empty();
+        s.extend(iter);
+        s
+    }
+}
+
+impl<'a, const N: usize> FromIterator<&'a char> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn from_iter<T: IntoIterator<Item = &'a char>>(iter: T) -> Self {
+        let mut s = Self::empty();
+        s.extend(iter);
+        s
+    }
+}
+
+macro_rules! from_iter_and_extend_strs {
+    ($($ty:ty),*) => {$(
+        impl<'a, const N: usize> FromIterator<$ty> for CompactStringN<N>
+        where
+            InlineSize<N>: SupportedInlineSize,
+        {
+            fn from_iter<T: IntoIterator<Item = $ty>>(iter: T) -> Self {
+                let mut s = Self::empty();
+                s.extend(iter);
+                s
+            }
+        }
+
+        impl<'a, const N: usize> Extend<$ty> for CompactStringN<N>
+        where
+            InlineSize<N>: SupportedInlineSize,
+        {
+            fn extend<T: IntoIterator<Item = $ty>>(&mut self, iter: T) {
+                iter.into_iter().for_each(|s| self.push_str(&s));
+            }
+        }
+    )*};
+}
+
+from_iter_and_extend_strs!(&'a str, Box<str>, Cow<'a, str>, String, CompactString);
+
+impl<const N: usize> FromIterator<CompactStringN<N>> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn from_iter<T: IntoIterator<Item = CompactStringN<N>>>(iter: T) -> Self {
+        let mut s = Self::empty();
+        s.extend(iter);
+        s
+    }
+}
+
+impl<const N: usize> Extend<CompactStringN<N>> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn extend<T: IntoIterator<Item = CompactStringN<N>>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|s| self.push_str(&s));
+    }
+}
+
+impl<const N: usize> Extend<CompactStringN<N>> for String
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn extend<T: IntoIterator<Item = CompactStringN<N>>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|s| self.push_str(&s));
+    }
+}
+
+impl<const N: usize> Extend<char> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn extend<T: IntoIterator<Item = char>>(&mut self, iter: T) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        iter.for_each(|ch| self.push(ch));
+    }
+}
+
+impl<'a, const N: usize> Extend<&'a char> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn extend<T: IntoIterator<Item = &'a char>>(&mut self, iter: T) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<const N: usize> Add<&str> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    type Output = Self;
+
+    #[inline]
+    fn add(mut self, rhs: &str) -> Self::Output {
+        self.push_str(rhs);
+        self
+    }
+}
+
+impl<const N: usize> AddAssign<&str> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn add_assign(&mut self, rhs: &str) {
+        self.push_str(rhs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+    use core::fmt::Write;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use super::{CompactStringN, InlineSize, SupportedInlineSize};
+    use crate::tests::{rand_bytes, rand_unicode};
+    use crate::CompactString;
+
+    fn check_roundtrip<const N: usize>(s: &str) -> Result<(), TestCaseError>
+    where
+        InlineSize<N>: SupportedInlineSize,
+    {
+        let compact = CompactStringN::<N>::new(s);
+        prop_assert_eq!(&compact, s);
+        prop_assert_eq!(compact.len(), s.len());
+        prop_assert_eq!(compact.is_heap_allocated(), s.len() > N);
+        prop_assert_eq!(compact.clone(), s);
+        prop_assert!(compact.capacity() >= N.max(s.len()));
+
+        let from_string = CompactStringN::<N>::from(String::from(s));
+        prop_assert_eq!(&from_string, s);
+        prop_assert_eq!(from_string.into_string(), s);
+
+        let from_compact = CompactStringN::<N>::from(CompactString::new(s));
+        prop_assert_eq!(CompactString::from(from_compact), s);
+
+        let mut shrunk = CompactStringN::<N>::with_capacity(s.len() + 100);
+        shrunk.push_str(s);
+        shrunk.shrink_to_fit();
+        prop_assert_eq!(&shrunk, s);
+        prop_assert_eq!(shrunk.is_heap_allocated(), s.len() > N);
+        prop_assert!(shrunk.capacity() >= N.max(s.len()));
+        Ok(())
+    }
+
+    fn check_mutations<const N: usize>(s: &str) -> Result<(), TestCaseError>
+    where
+        InlineSize<N>: SupportedInlineSize,
+    {
+        let mut compact = CompactStringN::<N>::empty();
+        let mut control = String::new();
+
+        for ch in s.chars() {
+            compact.push(ch);
+            control.push(ch);
+            prop_assert_eq!(&compact, &control);
+        }
+        compact.insert_str(0, "ab");
+        control.insert_str(0, "ab");
+        prop_assert_eq!(&compact, &control);
+        prop_assert_eq!(compact.remove(1), control.remove(1));
+        prop_assert_eq!(&compact, &control);
+
+        let mid = control.len() / 2;
+        let mid = (mid..).find(|&i| control.is_char_boundary(i)).unwrap();
+        compact.replace_range(..mid, "¡hola!");
+        control.replace_range(..mid, "¡hola!");
+        prop_assert_eq!(&compact, &control);
+
+        let mid = control.len() / 2;
+        let mid = (mid..).find(|&i| control.is_char_boundary(i)).unwrap();
+        prop_assert_eq!(compact.split_off(mid), control.split_off(mid));
+        prop_assert_eq!(&compact, &control);
+
+        compact.retain(|ch| ch.is_ascii());
+        control.retain(|ch| ch.is_ascii());
+        prop_assert_eq!(&compact, &control);
+
+        let end = control.len() / 2;
+        let end = (end..).find(|&i| control.is_char_boundary(i)).unwrap();
+        prop_assert_eq!(
+            compact.drain(..end).collect::<String>(),
+            control.drain(..end).collect::<String>()
+        );
+        prop_assert_eq!(&compact, &control);
+
+        while let Some(ch) = control.pop() {
+            prop_assert_eq!(compact.pop(), Some(ch));
+            prop_assert_eq!(&compact, &control);
+        }
+        prop_assert_eq!(compact.pop(), None);
+        prop_assert!(compact.is_empty());
+        Ok(())
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_roundtrip(#[strategy(rand_unicode())] s: String) {
+        check_roundtrip::<16>(&s)?;
+        check_roundtrip::<24>(&s)?;
+        check_roundtrip::<40>(&s)?;
+        check_roundtrip::<64>(&s)?;
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_mutations(#[strategy(rand_unicode())] s: String) {
+        check_mutations::<16>(&s)?;
+        check_mutations::<32>(&s)?;
+        check_mutations::<56>(&s)?;
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_from_utf8_lossy(#[strategy(rand_bytes())] bytes: alloc::vec::Vec<u8>) {
+        let compact = CompactStringN::<24>::from_utf8_lossy(&bytes);
+        prop_assert_eq!(compact, String::from_utf8_lossy(&bytes));
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_case_conversions(#[strategy(rand_unicode())] s: String) {
+        let compact = CompactStringN::<24>::new(&s);
+        prop_assert_eq!(compact.to_lowercase(), s.to_lowercase());
+        prop_assert_eq!(compact.to_uppercase(), s.to_uppercase());
+        prop_assert_eq!(compact.to_ascii_lowercase(), s.to_ascii_lowercase());
+        prop_assert_eq!(compact.to_ascii_uppercase(), s.to_ascii_uppercase());
+        prop_assert_eq!(
+            CompactStringN::<16>::from_str_to_lowercase(&s),
+            s.to_lowercase()
+        );
+        prop_assert_eq!(
+            CompactStringN::<16>::from_str_to_uppercase(&s),
+            s.to_uppercase()
+        );
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_from_utf16(#[strategy(rand_unicode())] s: String) {
+        let le: alloc::vec::Vec<u8> = s.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let be: alloc::vec::Vec<u8> = s.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        prop_assert_eq!(CompactStringN::<32>::from_utf16le(&le).unwrap(), &s);
+        prop_assert_eq!(CompactStringN::<32>::from_utf16be(&be).unwrap(), &s);
+        prop_assert_eq!(CompactStringN::<32>::from_utf16le_lossy(&le), &s);
+        prop_assert_eq!(CompactStringN::<32>::from_utf16be_lossy(&be), &s);
+    }
+
+    #[test]
+    fn test_from_string_buffer() {
+        let og = String::from("short");
+        let og_addr = og.as_ptr();
+        let s = CompactStringN::<24>::from_string_buffer(og);
+        assert!(s.is_heap_allocated());
+        assert_eq!(s.as_ptr(), og_addr);
+        let og = s.into_string();
+        assert_eq!(og.as_ptr(), og_addr);
+
+        // there's no room to re-use the buffer, so short strings are inlined
+        let s = CompactStringN::<16>::from_string_buffer(String::from("short"));
+        assert_eq!(s, "short");
+        assert_eq!(s.is_heap_allocated(), core::mem::size_of::<usize>() == 4);
+
+        let s = CompactStringN::<24>::from_string_buffer(String::new());
+        assert!(!s.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut s = CompactStringN::<16>::const_new("a &'static str that's long");
+        let mut d = s.drain(2..11);
+        assert_eq!(d.next_back(), Some(' '));
+        assert_eq!(d.as_str(), "&'static");
+        drop(d);
+        assert_eq!(s, "a str that's long");
+        assert!(s.is_heap_allocated());
+
+        s.drain(..);
+        assert_eq!(s, "");
+    }
+
+    #[test]
+    fn test_static_str() {
+        const NAME: CompactStringN<16> = CompactStringN::const_new("a &'static str that's long");
+        let mut name = NAME;
+        assert_eq!(name.as_static_str(), Some("a &'static str that's long"));
+        assert!(!name.is_heap_allocated());
+
+        let compact = CompactString::from(name.clone());
+        assert_eq!(compact.as_static_str(), Some("a &'static str that's long"));
+        assert_eq!(
+            CompactStringN::<16>::from(compact).as_static_str(),
+            name.as_static_str()
+        );
+
+        name.push('!');
+        assert_eq!(name.as_static_str(), None);
+        assert_eq!(name, "a &'static str that's long!");
+    }
+
+    #[test]
+    fn test_truncate_and_shrink() {
+        let mut s = CompactStringN::<16>::new("a string that is heap allocated");
+        s.truncate(8);
+        assert_eq!(s, "a string");
+        assert!(s.is_heap_allocated());
+
+        s.shrink_to_fit();
+        assert_eq!(s, "a string");
+        assert!(!s.is_heap_allocated());
+
+        s.clear();
+        assert_eq!(s, "");
+        assert_eq!(s.capacity(), 16);
+    }
+
+    #[test]
+    fn test_write() {
+        let mut s = CompactStringN::<24>::default();
+        write!(s, "{}-the answer", 42).unwrap();
+        assert_eq!(s, "42-the answer");
+        assert_eq!(s.to_string(), "42-the answer");
+    }
+
+    #[test]
+    fn test_option_niche() {
+        assert_eq!(
+            core::mem::size_of::<Option<CompactStringN<24>>>(),
+            core::mem::size_of::<Option<CompactString>>(),
+        );
+        let s = Some(CompactStringN::<16>::new("sixteen bytes!!!"));
+        assert_eq!(s.as_deref(), Some("sixteen bytes!!!"));
+    }
+}
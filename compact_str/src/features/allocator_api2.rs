@@ -0,0 +1,63 @@
+use core::alloc::Layout;
+use core::ptr::NonNull;
+
+use crate::{AllocError, Allocator};
+
+#[cfg_attr(docsrs, doc(cfg(feature = "allocator-api2")))]
+unsafe impl<A: allocator_api2::alloc::Allocator + Send + Sync> Allocator for A {
+    #[inline]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        allocator_api2::alloc::Allocator::allocate(self, layout)
+            .map(NonNull::cast)
+            .map_err(|_| AllocError)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        allocator_api2::alloc::Allocator::deallocate(self, ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        allocator_api2::alloc::Allocator::grow(self, ptr, old_layout, new_layout)
+            .map(NonNull::cast)
+            .map_err(|_| AllocError)
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        allocator_api2::alloc::Allocator::shrink(self, ptr, old_layout, new_layout)
+            .map(NonNull::cast)
+            .map_err(|_| AllocError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+
+    use allocator_api2::alloc::Global;
+
+    use crate::CompactString;
+
+    #[test]
+    fn test_allocator_api2_global() {
+        let mut s =
+            CompactString::new_in("a string that is allocated on the heap", Arc::new(Global));
+        assert!(s.allocator().is_some());
+
+        s.push_str(" and then grows");
+        assert_eq!(s, "a string that is allocated on the heap and then grows");
+        assert!(s.allocator().is_some());
+    }
+}
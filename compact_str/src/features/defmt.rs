@@ -24,6 +24,7 @@ impl Format for ToCompactStringError {
             ToCompactStringError::Fmt(core::fmt::Error) => {
                 defmt::write!(fmt, "Display::fmt() returned an error")
             }
+            ToCompactStringError::Utf8(_) => defmt::write!(fmt, "invalid utf-8"),
         }
     }
 }
@@ -1,5 +1,7 @@
 //! A module that contains the implementations for optional features. For example `serde` support
 
+#[cfg(feature = "allocator-api2")]
+mod allocator_api2;
 #[cfg(feature = "arbitrary")]
 mod arbitrary;
 #[cfg(feature = "bevy-reflect")]
@@ -32,9 +34,20 @@ mod serde;
 mod smallvec;
 #[cfg(feature = "sqlx")]
 mod sqlx;
+#[cfg(feature = "unicode-normalization")]
+mod unicode_normalization;
+#[cfg(feature = "unicode-segmentation")]
+mod unicode_segmentation;
+#[cfg(feature = "unicode-width")]
+mod unicode_width;
 #[cfg(feature = "utoipa")]
 mod utoipa;
+#[cfg(feature = "uuid")]
+mod uuid;
 #[cfg(feature = "valuable")]
 mod valuable;
 #[cfg(feature = "zeroize")]
 mod zeroize;
+
+#[cfg(feature = "unicode-width")]
+pub use unicode_width::Align;
@@ -3,7 +3,7 @@ use alloc::vec::Vec;
 
 use serde::de::{Deserializer, Error, Unexpected, Visitor};
 
-use crate::CompactString;
+use crate::{CompactCow, CompactString, CompactStringN, InlineSize, SupportedInlineSize};
 
 fn compact_string<'de: 'a, 'a, D: Deserializer<'de>>(
     deserializer: D,
@@ -71,6 +71,86 @@ impl<'de> serde::Deserialize<'de> for CompactString {
     }
 }
 
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<const N: usize> serde::Serialize for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_str().serialize(serializer)
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de, const N: usize> serde::Deserialize<'de> for CompactStringN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        compact_string(deserializer).map(CompactStringN::from)
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl serde::Serialize for CompactCow<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_str().serialize(serializer)
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de: 'a, 'a> serde::Deserialize<'de> for CompactCow<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CompactCowVisitor;
+
+        impl<'a> Visitor<'a> for CompactCowVisitor {
+            type Value = CompactCow<'a>;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                formatter.write_str("a string")
+            }
+
+            fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(CompactCow::owned(CompactString::from(v)))
+            }
+
+            fn visit_borrowed_str<E: Error>(self, v: &'a str) -> Result<Self::Value, E> {
+                Ok(CompactCow::borrowed(v))
+            }
+
+            fn visit_string<E: Error>(self, v: String) -> Result<Self::Value, E> {
+                Ok(CompactCow::from(v))
+            }
+
+            fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                match core::str::from_utf8(v) {
+                    Ok(s) => Ok(CompactCow::owned(CompactString::from(s))),
+                    Err(_) => Err(Error::invalid_value(Unexpected::Bytes(v), &self)),
+                }
+            }
+
+            fn visit_borrowed_bytes<E: Error>(self, v: &'a [u8]) -> Result<Self::Value, E> {
+                match core::str::from_utf8(v) {
+                    Ok(s) => Ok(CompactCow::borrowed(s)),
+                    Err(_) => Err(Error::invalid_value(Unexpected::Bytes(v), &self)),
+                }
+            }
+
+            fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                match String::from_utf8(v) {
+                    Ok(s) => Ok(CompactCow::from(s)),
+                    Err(e) => Err(Error::invalid_value(
+                        Unexpected::Bytes(&e.into_bytes()),
+                        &self,
+                    )),
+                }
+            }
+        }
+
+        deserializer.deserialize_str(CompactCowVisitor)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use alloc::string::{String, ToString};
@@ -79,7 +159,7 @@ mod tests {
     use serde::{Deserialize, Serialize};
     use test_strategy::proptest;
 
-    use crate::CompactString;
+    use crate::{CompactCow, CompactString, CompactStringN};
 
     #[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
     struct PersonString {
@@ -153,4 +233,42 @@ mod tests {
         assert_eq!(std_de_compact, std);
         assert_eq!(compact_de_std, compact);
     }
+
+    #[test]
+    fn test_compact_string_n_roundtrip() {
+        let tags: Vec<CompactStringN<16>> =
+            vec!["short".into(), "a tag that is heap allocated".into()];
+
+        let json = serde_json::to_string(&tags).unwrap();
+        assert_eq!(json, r#"["short","a tag that is heap allocated"]"#);
+
+        let de: Vec<CompactStringN<16>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(de, tags);
+    }
+
+    #[test]
+    fn test_compact_cow_borrows() {
+        #[derive(Deserialize, Serialize)]
+        struct Entry<'a> {
+            #[serde(borrow)]
+            key: CompactCow<'a>,
+            #[serde(borrow)]
+            value: CompactCow<'a>,
+        }
+
+        let json =
+            r#"{"key":"a key that is long enough to be borrowed","value":"an \"escaped\" value"}"#;
+        let entry: Entry = serde_json::from_str(json).unwrap();
+
+        // strings without escapes get borrowed from the input
+        assert!(entry.key.is_borrowed());
+        assert_eq!(entry.key, "a key that is long enough to be borrowed");
+        assert!(json.as_bytes().as_ptr_range().contains(&entry.key.as_ptr()));
+
+        // escaped strings have to be copied
+        assert!(!entry.value.is_borrowed());
+        assert_eq!(entry.value, r#"an "escaped" value"#);
+
+        assert_eq!(serde_json::to_string(&entry).unwrap(), json);
+    }
 }
@@ -0,0 +1,209 @@
+//! Unicode normalization for [`CompactString`], using the [`unicode_normalization`] crate.
+
+use unicode_normalization::{is_nfc_quick, is_nfd_quick, is_nfkc_quick, is_nfkd_quick};
+use unicode_normalization::{IsNormalized, UnicodeNormalization};
+
+use crate::CompactString;
+
+impl CompactString {
+    /// Returns the [Normalization Form C] (canonical composition) of the [`CompactString`].
+    ///
+    /// If the string is already normalized this is as cheap as a clone, otherwise the result is
+    /// built inline whenever it fits.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// // "e" followed by a combining acute accent
+    /// let decomposed = CompactString::new("caf\u{65}\u{301}");
+    /// assert_eq!(decomposed.to_nfc(), "caf\u{e9}");
+    /// assert!(!decomposed.to_nfc().is_heap_allocated());
+    /// ```
+    ///
+    /// [Normalization Form C]: https://www.unicode.org/reports/tr15/#Norm_Forms
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+    #[must_use = "this returns the normalized string as a new CompactString, \
+                  without modifying the original"]
+    pub fn to_nfc(&self) -> CompactString {
+        self.normalize(is_nfc_quick(self.chars()), |s| s.nfc().collect())
+    }
+
+    /// Returns the [Normalization Form D] (canonical decomposition) of the [`CompactString`].
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// let composed = CompactString::new("caf\u{e9}");
+    /// assert_eq!(composed.to_nfd(), "caf\u{65}\u{301}");
+    /// ```
+    ///
+    /// [Normalization Form D]: https://www.unicode.org/reports/tr15/#Norm_Forms
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+    #[must_use = "this returns the normalized string as a new CompactString, \
+                  without modifying the original"]
+    pub fn to_nfd(&self) -> CompactString {
+        self.normalize(is_nfd_quick(self.chars()), |s| s.nfd().collect())
+    }
+
+    /// Returns the [Normalization Form KC] (compatibility composition) of the [`CompactString`].
+    ///
+    /// This also folds compatibility characters like ligatures and full width letters, which
+    /// makes it a good fit for comparing user identifiers.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// // a full width "Ａ" and the "ﬁ" ligature
+    /// let username = CompactString::new("\u{ff21}\u{fb01}");
+    /// assert_eq!(username.to_nfkc(), "Afi");
+    /// ```
+    ///
+    /// [Normalization Form KC]: https://www.unicode.org/reports/tr15/#Norm_Forms
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+    #[must_use = "this returns the normalized string as a new CompactString, \
+                  without modifying the original"]
+    pub fn to_nfkc(&self) -> CompactString {
+        self.normalize(is_nfkc_quick(self.chars()), |s| s.nfkc().collect())
+    }
+
+    /// Returns the [Normalization Form KD] (compatibility decomposition) of the
+    /// [`CompactString`].
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// let compact = CompactString::new("\u{fb01}anc\u{e9}");
+    /// assert_eq!(compact.to_nfkd(), "fiance\u{301}");
+    /// ```
+    ///
+    /// [Normalization Form KD]: https://www.unicode.org/reports/tr15/#Norm_Forms
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+    #[must_use = "this returns the normalized string as a new CompactString, \
+                  without modifying the original"]
+    pub fn to_nfkd(&self) -> CompactString {
+        self.normalize(is_nfkd_quick(self.chars()), |s| s.nfkd().collect())
+    }
+
+    /// Returns `true` if the [`CompactString`] is in Normalization Form C.
+    ///
+    /// This runs the Unicode quick check first, and only normalizes the string if that's
+    /// inconclusive.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// assert!(CompactString::new("caf\u{e9}").is_nfc());
+    /// assert!(!CompactString::new("caf\u{65}\u{301}").is_nfc());
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+    #[inline]
+    pub fn is_nfc(&self) -> bool {
+        unicode_normalization::is_nfc(self.as_str())
+    }
+
+    /// Returns `true` if the [`CompactString`] is in Normalization Form D, see
+    /// [`CompactString::is_nfc`].
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+    #[inline]
+    pub fn is_nfd(&self) -> bool {
+        unicode_normalization::is_nfd(self.as_str())
+    }
+
+    /// Returns `true` if the [`CompactString`] is in Normalization Form KC, see
+    /// [`CompactString::is_nfc`].
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+    #[inline]
+    pub fn is_nfkc(&self) -> bool {
+        unicode_normalization::is_nfkc(self.as_str())
+    }
+
+    /// Returns `true` if the [`CompactString`] is in Normalization Form KD, see
+    /// [`CompactString::is_nfc`].
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+    #[inline]
+    pub fn is_nfkd(&self) -> bool {
+        unicode_normalization::is_nfkd(self.as_str())
+    }
+
+    /// Skips normalizing the string if the quick check says it's already normalized.
+    #[inline]
+    fn normalize(
+        &self,
+        quick_check: IsNormalized,
+        normalize: impl FnOnce(&str) -> CompactString,
+    ) -> CompactString {
+        match quick_check {
+            IsNormalized::Yes => self.clone(),
+            IsNormalized::No | IsNormalized::Maybe => normalize(self.as_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+    use unicode_normalization::UnicodeNormalization;
+
+    use crate::tests::rand_unicode;
+    use crate::CompactString;
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_matches_unicode_normalization(#[strategy(rand_unicode())] s: String) {
+        let compact = CompactString::new(&s);
+
+        prop_assert_eq!(compact.to_nfc(), s.nfc().collect::<String>());
+        prop_assert_eq!(compact.to_nfd(), s.nfd().collect::<String>());
+        prop_assert_eq!(compact.to_nfkc(), s.nfkc().collect::<String>());
+        prop_assert_eq!(compact.to_nfkd(), s.nfkd().collect::<String>());
+
+        prop_assert_eq!(compact.is_nfc(), unicode_normalization::is_nfc(&s));
+        prop_assert_eq!(compact.is_nfd(), unicode_normalization::is_nfd(&s));
+        prop_assert_eq!(compact.is_nfkc(), unicode_normalization::is_nfkc(&s));
+        prop_assert_eq!(compact.is_nfkd(), unicode_normalization::is_nfkd(&s));
+
+        prop_assert!(compact.to_nfc().is_nfc());
+        prop_assert!(compact.to_nfkd().is_nfkd());
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_combining_marks(
+        #[strategy("[aeoAEO\u{300}-\u{36f}\u{1100}\u{1161}]{0,30}")] s: String,
+    ) {
+        // lots of combining marks and Hangul jamo, which the quick check can't decide on
+        let compact = CompactString::new(&s);
+        prop_assert_eq!(compact.to_nfc(), s.nfc().collect::<String>());
+        prop_assert_eq!(compact.to_nfd(), s.nfd().collect::<String>());
+    }
+
+    #[test]
+    fn test_inline_when_it_fits() {
+        // decomposed on the heap, but the composed form fits inline
+        let decomposed: String = "\u{65}\u{301}".repeat(10);
+        let compact = CompactString::new(&decomposed);
+        assert!(compact.is_heap_allocated());
+
+        let nfc = compact.to_nfc();
+        assert_eq!(nfc, "\u{e9}".repeat(10));
+        assert!(!nfc.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_already_normalized() {
+        let compact = CompactString::new("hello world");
+        assert_eq!(compact.to_nfc(), compact);
+        assert_eq!(compact.to_nfd(), compact);
+        assert_eq!(compact.to_nfkc(), compact);
+        assert_eq!(compact.to_nfkd(), compact);
+        assert!(compact.is_nfc() && compact.is_nfd() && compact.is_nfkc() && compact.is_nfkd());
+    }
+}
@@ -0,0 +1,70 @@
+//! Grapheme aware truncation of a [`CompactString`], using the [`unicode_segmentation`] crate.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::CompactString;
+
+impl CompactString {
+    /// Shortens the [`CompactString`] to at most `n` extended grapheme clusters, i.e. what a
+    /// user would consider a character.
+    ///
+    /// Unlike truncating to a number of `char`s, this never separates a letter from its accents,
+    /// or splits up an emoji sequence. Like [`CompactString::truncate`], this works in place and
+    /// doesn't change the capacity, so an inline string stays inline.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// // "e" with a combining acute accent, then a family emoji made of 3 code points
+    /// let mut s = CompactString::new("e\u{301}👨‍👩‍👧!");
+    /// s.truncate_graphemes(2);
+    /// assert_eq!(s, "e\u{301}👨‍👩‍👧");
+    ///
+    /// s.truncate_graphemes(10);
+    /// assert_eq!(s, "e\u{301}👨‍👩‍👧");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-segmentation")))]
+    pub fn truncate_graphemes(&mut self, n: usize) {
+        if let Some((idx, _)) = self.grapheme_indices(true).nth(n) {
+            self.truncate(idx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+    use unicode_segmentation::UnicodeSegmentation;
+
+    use crate::tests::rand_unicode;
+    use crate::CompactString;
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_truncate_graphemes(
+        #[strategy(rand_unicode())] s: String,
+        #[strategy(0..30usize)] n: usize,
+    ) {
+        let mut compact = CompactString::new(&s);
+        compact.truncate_graphemes(n);
+
+        let expected: String = s.graphemes(true).take(n).collect();
+        prop_assert_eq!(&compact, &expected);
+    }
+
+    #[test]
+    fn test_truncate_graphemes_stays_inline() {
+        let mut compact = CompactString::new("🇳🇱🇧🇪🇩🇪");
+        assert!(!compact.is_heap_allocated());
+        compact.truncate_graphemes(2);
+        assert_eq!(compact, "🇳🇱🇧🇪");
+        assert!(!compact.is_heap_allocated());
+
+        compact.truncate_graphemes(0);
+        assert_eq!(compact, "");
+    }
+}
@@ -0,0 +1,243 @@
+//! Display width aware truncation and padding of a [`CompactString`], using the
+//! [`unicode_width`] crate, for rendering text in a terminal.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::repr::MAX_SIZE;
+use crate::CompactString;
+
+/// Where [`CompactString::pad_to_width`] places the text within the padding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(docsrs, doc(cfg(feature = "unicode-width")))]
+pub enum Align {
+    /// Pads on the right.
+    Left,
+    /// Pads on the left.
+    Right,
+    /// Pads on both sides, with the extra space on the right if the padding is uneven.
+    Center,
+}
+
+impl CompactString {
+    /// Shortens the [`CompactString`] so it takes up at most `width` columns when displayed,
+    /// ending it with `ellipsis` if anything was cut off.
+    ///
+    /// The text is cut between grapheme clusters, and the `ellipsis` counts towards the width.
+    /// If the `ellipsis` itself is wider than `width`, only as much of it as fits is kept. Like
+    /// [`CompactString::trim_in_place`], an inline string stays inline and a heap allocated
+    /// string is moved back inline if the result fits. Widths are measured per grapheme cluster
+    /// with [`UnicodeWidthStr::width`], e.g. most CJK characters and emoji take up two columns.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// let mut s = CompactString::new("hello world");
+    /// s.truncate_display_width(8, "…");
+    /// assert_eq!(s, "hello w…");
+    ///
+    /// // each of these characters takes up two columns
+    /// let mut s = CompactString::new("日本語のテキスト");
+    /// s.truncate_display_width(7, "...");
+    /// assert_eq!(s, "日本...");
+    ///
+    /// // strings that already fit are left alone
+    /// let mut s = CompactString::new("short");
+    /// s.truncate_display_width(5, "…");
+    /// assert_eq!(s, "short");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-width")))]
+    pub fn truncate_display_width(&mut self, width: usize, ellipsis: &str) {
+        if display_width(self) <= width {
+            return;
+        }
+
+        let ellipsis_width = display_width(ellipsis);
+        let (len, ellipsis) = if ellipsis_width <= width {
+            (prefix_len(self, width - ellipsis_width), ellipsis)
+        } else {
+            (0, &ellipsis[..prefix_len(ellipsis, width)])
+        };
+        if self.is_heap_allocated() && len + ellipsis.len() <= MAX_SIZE {
+            let mut inline = CompactString::new(&self[..len]);
+            inline.push_str(ellipsis);
+            *self = inline;
+        } else {
+            self.truncate(len);
+            self.push_str(ellipsis);
+        }
+    }
+
+    /// Pads the [`CompactString`] with spaces so it takes up at least `width` columns when
+    /// displayed, placing the text according to `align`.
+    ///
+    /// The string is modified in place, so an inline string stays inline whenever the result
+    /// fits. Widths are measured the same way as in [`CompactString::truncate_display_width`].
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::{Align, CompactString};
+    ///
+    /// let mut s = CompactString::new("日本");
+    /// s.pad_to_width(6, Align::Right);
+    /// assert_eq!(s, "  日本");
+    ///
+    /// let mut s = CompactString::new("abc");
+    /// s.pad_to_width(6, Align::Center);
+    /// assert_eq!(s, " abc  ");
+    ///
+    /// // strings that are already wide enough are left alone
+    /// let mut s = CompactString::new("too long");
+    /// s.pad_to_width(4, Align::Left);
+    /// assert_eq!(s, "too long");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-width")))]
+    pub fn pad_to_width(&mut self, width: usize, align: Align) {
+        let padding = width.saturating_sub(display_width(self));
+        if padding == 0 {
+            return;
+        }
+
+        let (before, after) = match align {
+            Align::Left => (0, padding),
+            Align::Right => (padding, 0),
+            Align::Center => (padding / 2, padding - padding / 2),
+        };
+        self.reserve(padding);
+        for chunk in spaces(before) {
+            self.insert_str(0, chunk);
+        }
+        for chunk in spaces(after) {
+            self.push_str(chunk);
+        }
+    }
+}
+
+/// Returns the number of columns `s` takes up, adding up the widths of its grapheme clusters.
+fn display_width(s: &str) -> usize {
+    s.graphemes(true).map(UnicodeWidthStr::width).sum()
+}
+
+/// Returns the length in bytes of the longest prefix of whole grapheme clusters of `s`, that's
+/// at most `width` columns wide.
+fn prefix_len(s: &str, width: usize) -> usize {
+    let mut total = 0;
+    for (idx, grapheme) in s.grapheme_indices(true) {
+        total += grapheme.width();
+        if total > width {
+            return idx;
+        }
+    }
+    s.len()
+}
+
+/// Returns `n` spaces, in chunks.
+fn spaces(mut n: usize) -> impl Iterator<Item = &'static str> {
+    const SPACES: &str = "                                                                ";
+    core::iter::from_fn(move || {
+        let chunk = n.min(SPACES.len());
+        n -= chunk;
+        (chunk > 0).then(|| &SPACES[..chunk])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use super::display_width;
+    use crate::repr::MAX_SIZE;
+    use crate::tests::rand_unicode;
+    use crate::{Align, CompactString};
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_truncate_display_width(
+        #[strategy(
+            "([a-z日本語🦀\u{301}\u{FE0E}\u{FE0F} ]|👨\u{200d}👩\u{200d}👧|❤\u{FE0F}){0,30}"
+        )]
+        s: String,
+        #[strategy(0..20usize)] width: usize,
+    ) {
+        let mut compact = CompactString::new(&s);
+        compact.truncate_display_width(width, "…");
+
+        if display_width(&s) <= width {
+            prop_assert_eq!(&compact, &s);
+        } else {
+            prop_assert!(display_width(&compact) <= width, "{compact:?} {width}");
+            prop_assert_eq!(compact.is_heap_allocated(), compact.len() > MAX_SIZE);
+            let kept = compact.strip_suffix('…').unwrap_or(&compact);
+            prop_assert!(s.starts_with(kept));
+        }
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_pad_to_width(
+        #[strategy(rand_unicode())] s: String,
+        #[strategy(0..100usize)] width: usize,
+    ) {
+        for align in [Align::Left, Align::Right, Align::Center] {
+            let mut compact = CompactString::new(&s);
+            compact.pad_to_width(width, align);
+
+            prop_assert_eq!(display_width(&compact), display_width(&s).max(width));
+            prop_assert_eq!(compact.trim_matches(' '), s.trim_matches(' '));
+        }
+    }
+
+    #[test]
+    fn test_truncate_keeps_graphemes() {
+        let mut compact = CompactString::new("e\u{301}e\u{301}e\u{301}");
+        compact.truncate_display_width(2, ".");
+        assert_eq!(compact, "e\u{301}.");
+        assert!(!compact.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_truncate_heap_moves_inline() {
+        let mut compact = CompactString::new("a string that is too long to be inlined");
+        assert!(compact.is_heap_allocated());
+        compact.truncate_display_width(10, "…");
+        assert_eq!(compact, "a string …");
+        assert!(!compact.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_truncate_zwj_sequence() {
+        // the family emoji is a single grapheme cluster two columns wide
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let mut compact = CompactString::new(family);
+        compact.truncate_display_width(2, "…");
+        assert_eq!(compact, family);
+
+        let mut compact = CompactString::new(format!("{family}{family}"));
+        compact.truncate_display_width(3, "…");
+        assert_eq!(compact, format!("{family}…"));
+    }
+
+    #[test]
+    fn test_wide_ellipsis() {
+        let mut compact = CompactString::new("hello world");
+        compact.truncate_display_width(2, "...");
+        assert_eq!(compact, "..");
+
+        let mut compact = CompactString::new("hello world");
+        compact.truncate_display_width(0, "…");
+        assert_eq!(compact, "");
+    }
+
+    #[test]
+    fn test_pad_heap() {
+        let mut compact = CompactString::new("abc");
+        compact.pad_to_width(100, Align::Center);
+        assert_eq!(compact.len(), 100);
+        assert_eq!(compact.trim_start().len(), 52);
+        assert!(compact.is_heap_allocated());
+    }
+}
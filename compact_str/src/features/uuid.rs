@@ -0,0 +1,82 @@
+use uuid::Uuid;
+
+use crate::repr::{IntoRepr, Repr};
+use crate::{CompactString, ToCompactStringError, UnwrapWithMsg};
+
+impl IntoRepr for Uuid {
+    #[inline]
+    fn into_repr(self) -> Result<Repr, ToCompactStringError> {
+        let mut buf = Uuid::encode_buffer();
+        Ok(Repr::new(self.hyphenated().encode_lower(&mut buf))?)
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "uuid")))]
+impl From<Uuid> for CompactString {
+    /// Formats a [`Uuid`] in its lower case, hyphenated form, the same as its `Display`
+    /// implementation, with a single allocation.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    /// use uuid::Uuid;
+    ///
+    /// let uuid = Uuid::from_u128(0x67e5_5044_10b1_426f_9247_bb68_0e5f_e0c8);
+    /// let s = CompactString::from(uuid);
+    /// assert_eq!(s, "67e55044-10b1-426f-9247-bb680e5fe0c8");
+    /// ```
+    #[inline]
+    #[track_caller]
+    fn from(uuid: Uuid) -> Self {
+        CompactString(uuid.into_repr().unwrap_with_msg())
+    }
+}
+
+impl CompactString {
+    /// Parses the [`CompactString`] as a [`Uuid`], in any of the formats accepted by
+    /// [`Uuid::try_parse`], e.g. hyphenated, simple, braced or as a URN.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    /// use uuid::Uuid;
+    ///
+    /// let uuid = Uuid::from_u128(0x67e5_5044_10b1_426f_9247_bb68_0e5f_e0c8);
+    /// assert_eq!(CompactString::from(uuid).parse_uuid(), Ok(uuid));
+    ///
+    /// let simple = CompactString::from_hex_bytes(uuid.as_bytes());
+    /// assert_eq!(simple.parse_uuid(), Ok(uuid));
+    ///
+    /// assert!(CompactString::new("not a uuid").parse_uuid().is_err());
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "uuid")))]
+    #[inline]
+    pub fn parse_uuid(&self) -> Result<Uuid, uuid::Error> {
+        Uuid::try_parse(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use test_strategy::proptest;
+    use uuid::Uuid;
+
+    use crate::{CompactString, ToCompactString};
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_uuid_roundtrip(value: u128) {
+        let uuid = Uuid::from_u128(value);
+        let compact = CompactString::from(uuid);
+        assert_eq!(compact, uuid.to_string());
+        assert_eq!(uuid.to_compact_string(), compact);
+        assert_eq!(compact.parse_uuid(), Ok(uuid));
+
+        let simple = CompactString::from_hex_bytes(uuid.as_bytes());
+        assert_eq!(simple, uuid.simple().to_string());
+        assert_eq!(simple.parse_uuid(), Ok(uuid));
+        assert_eq!(simple.decode_hex().unwrap(), uuid.as_bytes());
+    }
+}
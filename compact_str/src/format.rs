@@ -0,0 +1,351 @@
+//! Options for formatting numbers directly into a [`CompactString`](crate::CompactString).
+
+use core::fmt;
+use core::num::IntErrorKind;
+use core::str::FromStr;
+
+/// Options for formatting an integer into a [`CompactString`](crate::CompactString), see
+/// [`CompactString::from_int_with`](crate::CompactString::from_int_with).
+///
+/// By default integers are formatted in base 10, without any padding, the same as
+/// [`ToCompactString`](crate::ToCompactString).
+///
+/// # Examples
+/// ```
+/// use compact_str::{CompactString, IntFormat};
+///
+/// const HEX_ID: IntFormat = IntFormat::new().radix(16).zero_pad(8);
+/// assert_eq!(CompactString::from_int_with(0xbeef_u32, HEX_ID), "0000beef");
+///
+/// let column = IntFormat::new().space_pad(6);
+/// assert_eq!(CompactString::from_int_with(-42, column), "   -42");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntFormat {
+    pub(crate) radix: u32,
+    pub(crate) uppercase: bool,
+    pub(crate) width: usize,
+    pub(crate) fill: u8,
+}
+
+impl IntFormat {
+    /// Creates the default [`IntFormat`], base 10 without any padding.
+    #[inline]
+    pub const fn new() -> Self {
+        IntFormat {
+            radix: 10,
+            uppercase: false,
+            width: 0,
+            fill: b' ',
+        }
+    }
+
+    /// Formats the integer in base `radix`, using the digits `0-9` and then the letters `a-z`.
+    ///
+    /// # Panics
+    /// Panics if `radix` is not in the range `2..=36`.
+    #[inline]
+    pub const fn radix(mut self, radix: u32) -> Self {
+        assert!(
+            radix >= 2 && radix <= 36,
+            "radix must be in the range 2..=36"
+        );
+        self.radix = radix;
+        self
+    }
+
+    /// Uses the letters `A-Z` instead of `a-z` for digits above 9.
+    #[inline]
+    pub const fn uppercase(mut self, uppercase: bool) -> Self {
+        self.uppercase = uppercase;
+        self
+    }
+
+    /// Pads the integer with `0`s, after the sign, so it's at least `width` characters long.
+    #[inline]
+    pub const fn zero_pad(mut self, width: usize) -> Self {
+        self.width = width;
+        self.fill = b'0';
+        self
+    }
+
+    /// Pads the integer with spaces, before the sign, so it's at least `width` characters long.
+    #[inline]
+    pub const fn space_pad(mut self, width: usize) -> Self {
+        self.width = width;
+        self.fill = b' ';
+        self
+    }
+}
+
+impl Default for IntFormat {
+    #[inline]
+    fn default() -> Self {
+        IntFormat::new()
+    }
+}
+
+/// An integer type that can be formatted with an [`IntFormat`], or parsed with
+/// [`CompactString::parse_int`](crate::CompactString::parse_int).
+///
+/// This trait is sealed, it's implemented for all of the primitive integer types.
+pub trait Integer: sealed::Integer {}
+
+/// A float type that can be formatted with a [`FloatFormat`], or parsed with
+/// [`CompactString::parse_float`](crate::CompactString::parse_float).
+///
+/// This trait is sealed, it's implemented for [`f32`] and [`f64`].
+pub trait Float: sealed::Float {}
+
+pub(crate) mod sealed {
+    use super::*;
+
+    pub trait Integer: Copy {
+        /// Whether the integer type can be negative.
+        const SIGNED: bool;
+
+        /// Returns the magnitude of the integer, and whether it's negative.
+        fn into_parts(self) -> (u128, bool);
+
+        /// Creates an integer from its magnitude and sign, the inverse of `into_parts`.
+        fn from_parts(magnitude: u128, negative: bool) -> Result<Self, IntErrorKind>;
+    }
+
+    pub trait Float:
+        Copy
+        + 'static
+        + fmt::Display
+        + fmt::LowerExp
+        + fmt::UpperExp
+        + FromStr<Err = core::num::ParseFloatError>
+        + zmij::Float
+    {
+        /// The number of significant decimal digits that are guaranteed to be correct after
+        /// rounding the shortest representation of any value, i.e. C's `DBL_DIG`/`FLT_DIG`.
+        const DIGITS: usize;
+        /// The largest mantissa that converts to the float exactly.
+        const MAX_EXACT_MANTISSA: u64;
+        /// The powers of 10 that convert to the float exactly.
+        const EXACT_POWERS_OF_10: &'static [Self];
+
+        fn from_u64(n: u64) -> Self;
+        fn mul(self, other: Self) -> Self;
+        fn div(self, other: Self) -> Self;
+        fn neg(self) -> Self;
+
+        fn is_nan(self) -> bool;
+        fn is_infinite(self) -> bool;
+        fn is_sign_negative(self) -> bool;
+        fn is_subnormal(self) -> bool;
+    }
+}
+
+macro_rules! impl_unsigned {
+    ($($t:ty),*) => {$(
+        impl Integer for $t {}
+
+        impl sealed::Integer for $t {
+            const SIGNED: bool = false;
+
+            #[inline]
+            fn into_parts(self) -> (u128, bool) {
+                (self as u128, false)
+            }
+
+            #[inline]
+            fn from_parts(magnitude: u128, negative: bool) -> Result<Self, IntErrorKind> {
+                debug_assert!(!negative, "unsigned integers can't be negative");
+                <$t>::try_from(magnitude).map_err(|_| IntErrorKind::PosOverflow)
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_signed {
+    ($($t:ty),*) => {$(
+        impl Integer for $t {}
+
+        impl sealed::Integer for $t {
+            const SIGNED: bool = true;
+
+            #[inline]
+            fn into_parts(self) -> (u128, bool) {
+                (self.unsigned_abs() as u128, self < 0)
+            }
+
+            #[inline]
+            fn from_parts(magnitude: u128, negative: bool) -> Result<Self, IntErrorKind> {
+                if !negative {
+                    <$t>::try_from(magnitude).map_err(|_| IntErrorKind::PosOverflow)
+                } else if magnitude <= <$t>::MIN.unsigned_abs() as u128 {
+                    // `MIN` doesn't have a positive counterpart, so negate after the cast
+                    Ok((magnitude as $t).wrapping_neg())
+                } else {
+                    Err(IntErrorKind::NegOverflow)
+                }
+            }
+        }
+    )*};
+}
+
+impl_unsigned!(u8, u16, u32, u64, u128, usize);
+impl_signed!(i8, i16, i32, i64, i128, isize);
+
+macro_rules! impl_float {
+    ($t:ty, $digits:expr, $mantissa_bits:expr, [$($pow:expr),*]) => {
+        impl Float for $t {}
+
+        impl sealed::Float for $t {
+            const DIGITS: usize = $digits;
+            const MAX_EXACT_MANTISSA: u64 = 1 << $mantissa_bits;
+            const EXACT_POWERS_OF_10: &'static [Self] = &[$($pow),*];
+
+            #[inline]
+            fn from_u64(n: u64) -> Self {
+                n as $t
+            }
+
+            #[inline]
+            fn mul(self, other: Self) -> Self {
+                self * other
+            }
+
+            #[inline]
+            fn div(self, other: Self) -> Self {
+                self / other
+            }
+
+            #[inline]
+            fn neg(self) -> Self {
+                -self
+            }
+
+            #[inline]
+            fn is_nan(self) -> bool {
+                <$t>::is_nan(self)
+            }
+
+            #[inline]
+            fn is_infinite(self) -> bool {
+                <$t>::is_infinite(self)
+            }
+
+            #[inline]
+            fn is_sign_negative(self) -> bool {
+                <$t>::is_sign_negative(self)
+            }
+
+            #[inline]
+            fn is_subnormal(self) -> bool {
+                <$t>::is_subnormal(self)
+            }
+        }
+    };
+}
+
+impl_float!(
+    f32,
+    6,
+    24,
+    [1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10]
+);
+impl_float!(
+    f64,
+    15,
+    53,
+    [
+        1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16,
+        1e17, 1e18, 1e19, 1e20, 1e21, 1e22
+    ]
+);
+
+/// Options for formatting a float into a [`CompactString`](crate::CompactString), see
+/// [`CompactString::from_f64_with`](crate::CompactString::from_f64_with).
+///
+/// By default floats are formatted the same as
+/// [`ToCompactString`](crate::ToCompactString), with the shortest representation that
+/// roundtrips.
+///
+/// # Examples
+/// ```
+/// use compact_str::{CompactString, Exponent, FloatFormat};
+///
+/// const PRICE: FloatFormat = FloatFormat::new().precision(2);
+/// assert_eq!(CompactString::from_f64_with(9.5, PRICE), "9.50");
+///
+/// let scientific = FloatFormat::new().exponent(Exponent::Lower);
+/// assert_eq!(CompactString::from_f64_with(1234.5, scientific), "1.2345e3");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloatFormat {
+    pub(crate) precision: Option<usize>,
+    pub(crate) exponent: Exponent,
+    pub(crate) nan_inf_style: NanInfStyle,
+}
+
+impl FloatFormat {
+    /// Creates the default [`FloatFormat`], the shortest representation that roundtrips.
+    #[inline]
+    pub const fn new() -> Self {
+        FloatFormat {
+            precision: None,
+            exponent: Exponent::Auto,
+            nan_inf_style: NanInfStyle::Rust,
+        }
+    }
+
+    /// Formats the float with exactly `precision` digits after the decimal point, rounding half
+    /// to even, the same as `{:.precision$}`.
+    #[inline]
+    pub const fn precision(mut self, precision: usize) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Sets when to use scientific notation, see [`Exponent`].
+    #[inline]
+    pub const fn exponent(mut self, exponent: Exponent) -> Self {
+        self.exponent = exponent;
+        self
+    }
+
+    /// Sets how to format `NaN` and infinity, see [`NanInfStyle`].
+    #[inline]
+    pub const fn nan_inf_style(mut self, nan_inf_style: NanInfStyle) -> Self {
+        self.nan_inf_style = nan_inf_style;
+        self
+    }
+}
+
+impl Default for FloatFormat {
+    #[inline]
+    fn default() -> Self {
+        FloatFormat::new()
+    }
+}
+
+/// When a [`FloatFormat`] uses scientific notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Exponent {
+    /// Use scientific notation for very large and very small numbers, e.g. `1e+16`, like
+    /// [`ToCompactString`](crate::ToCompactString). With a precision set, this behaves like
+    /// [`Exponent::Never`].
+    Auto,
+    /// Never use scientific notation, e.g. `10000000000000000`, like `{}`.
+    Never,
+    /// Always use scientific notation with a lower case `e`, e.g. `1.5e3`, like `{:e}`.
+    Lower,
+    /// Always use scientific notation with an upper case `E`, e.g. `1.5E3`, like `{:E}`.
+    Upper,
+}
+
+/// How a [`FloatFormat`] formats `NaN` and infinity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NanInfStyle {
+    /// `NaN`, `inf` and `-inf`, like [`core::fmt`].
+    Rust,
+    /// `nan`, `inf` and `-inf`, like C's `printf`.
+    Lower,
+    /// `NaN`, `Infinity` and `-Infinity`, like JavaScript.
+    JavaScript,
+}
@@ -0,0 +1,186 @@
+use core::hash::{BuildHasher, Hasher};
+
+use crate::repr::{Repr, MAX_SIZE, WORDS};
+
+/// A fast, non-cryptographic [`Hasher`] that hashes inline [`CompactString`]s as a few whole
+/// words, instead of byte by byte.
+///
+/// It's built for hash maps with [`CompactString`] keys, and hashes a [`CompactString`] exactly
+/// like the equivalent `&str`, so lookups with a `&str` still work. Like `FxHash`, which it's
+/// modeled after, it isn't resistant to HashDoS attacks, so don't use it for keys an attacker
+/// controls. The hashes aren't stable across platforms or versions of this crate either.
+///
+/// # Examples
+/// ```
+/// use std::collections::HashMap;
+///
+/// use compact_str::{CompactString, FastBuildHasher};
+///
+/// let mut map: HashMap<CompactString, u32, FastBuildHasher> = HashMap::default();
+/// map.insert(CompactString::new("hello"), 42);
+/// assert_eq!(map.get("hello"), Some(&42));
+/// ```
+///
+/// [`CompactString`]: crate::CompactString
+#[derive(Debug, Clone, Default)]
+pub struct FastHasher {
+    hash: u64,
+}
+
+// SAFETY: `FastHasher` doesn't contain any references, so it's `'static`.
+unsafe impl castaway::LifetimeFree for FastHasher {}
+
+/// A [`BuildHasher`] that creates [`FastHasher`]s.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FastBuildHasher;
+
+// From `rustc-hash`, an odd number with a roughly even mix of bits.
+const K: u64 = 0xf1357aea2e62a9c5;
+
+impl FastHasher {
+    #[inline(always)]
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(K);
+    }
+
+    /// Hashes the words of an inline string, see [`Repr::inline_words`].
+    #[inline(always)]
+    pub(crate) fn write_inline(&mut self, words: [usize; WORDS]) {
+        for word in words {
+            self.add(word as u64);
+        }
+    }
+}
+
+impl Hasher for FastHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        if bytes.len() <= MAX_SIZE {
+            // hash it exactly like an inline `CompactString`
+            return self.write_inline(Repr::inline_words_of(bytes));
+        }
+
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            self.add(u64::from_le_bytes(word));
+        }
+        let mut tail = [0; 8];
+        tail[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+        self.add(u64::from_le_bytes(tail));
+        self.add(bytes.len() as u64);
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add(i as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add(i as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add(i as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add(i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add(i as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        // the multiplication leaves the high bits best mixed, but hash tables use the low bits
+        self.hash.rotate_left(26)
+    }
+}
+
+impl BuildHasher for FastBuildHasher {
+    type Hasher = FastHasher;
+
+    #[inline]
+    fn build_hasher(&self) -> FastHasher {
+        FastHasher::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use core::hash::{BuildHasher, Hash, Hasher};
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use super::{FastBuildHasher, FastHasher};
+    use crate::repr::MAX_SIZE;
+    use crate::tests::rand_unicode;
+    use crate::CompactString;
+
+    fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+        let mut hasher = FastHasher::default();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_hash_matches_str(#[strategy(rand_unicode())] s: String) {
+        let compact = CompactString::new(&s);
+        let expected = hash(s.as_str());
+        prop_assert_eq!(hash(&compact), expected);
+        prop_assert_eq!(compact.fast_hash(), expected);
+        prop_assert_eq!(FastBuildHasher.hash_one(&compact), expected);
+
+        // the same string, but stored on the heap
+        let mut heap = CompactString::with_capacity(64);
+        heap.push_str(&s);
+        prop_assert_eq!(hash(&heap), expected);
+    }
+
+    #[test]
+    fn test_truncated_inline() {
+        // truncating leaves the old bytes in the inline buffer, they can't affect the hash
+        let mut compact = CompactString::new("hello world");
+        compact.truncate(5);
+        assert_eq!(compact.fast_hash(), CompactString::new("hello").fast_hash());
+        assert_eq!(compact, CompactString::new("hello"));
+    }
+
+    #[test]
+    fn test_all_inline_lengths() {
+        let text = "abcdefghijklmnopqrstuvwxyz";
+        for len in 0..=MAX_SIZE {
+            let compact = CompactString::new(&text[..len]);
+            assert_eq!(compact.fast_hash(), hash(&text[..len]), "{len}");
+        }
+    }
+
+    #[test]
+    fn test_distinct_hashes() {
+        let strings = [
+            "",
+            "\0",
+            "\0\0",
+            "a",
+            "a\0",
+            "ab",
+            "ba",
+            "abcdefghijklmnopqrstuvwx",
+        ];
+        for (i, a) in strings.iter().enumerate() {
+            for b in &strings[i + 1..] {
+                assert_ne!(hash(*a), hash(*b), "{a:?} {b:?}");
+            }
+        }
+    }
+}
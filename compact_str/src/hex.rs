@@ -0,0 +1,146 @@
+//! Encoding bytes as hex into a [`CompactString`](crate::CompactString), and decoding them back.
+//!
+//! The length of the output is known upfront in both directions, so we allocate once and write
+//! the digits straight into the buffer.
+
+use core::fmt;
+
+use crate::repr::Repr;
+use crate::{CompactBytes, ReserveError};
+
+const LOWER_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Maps an ASCII byte to the value of the hex digit, or `INVALID`.
+const DECODE: [u8; 256] = {
+    let mut table = [INVALID; 256];
+    let mut i = 0;
+    while i < 10 {
+        table[b'0' as usize + i] = i as u8;
+        i += 1;
+    }
+    let mut i = 0;
+    while i < 6 {
+        table[b'a' as usize + i] = 10 + i as u8;
+        table[b'A' as usize + i] = 10 + i as u8;
+        i += 1;
+    }
+    table
+};
+const INVALID: u8 = 0xff;
+
+/// The error returned by [`CompactString::decode_hex`](crate::CompactString::decode_hex).
+///
+/// # Examples
+/// ```
+/// use compact_str::{CompactString, DecodeHexError};
+///
+/// assert_eq!(CompactString::new("abc").decode_hex(), Err(DecodeHexError::OddLength));
+/// assert_eq!(
+///     CompactString::new("0x12").decode_hex(),
+///     Err(DecodeHexError::InvalidDigit(1))
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeHexError {
+    /// The string has an odd number of digits.
+    OddLength,
+    /// The byte at this offset isn't a hex digit.
+    InvalidDigit(usize),
+}
+
+impl fmt::Display for DecodeHexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeHexError::OddLength => f.write_str("odd number of hex digits"),
+            DecodeHexError::InvalidDigit(position) => {
+                write!(f, "invalid hex digit at position {position}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for DecodeHexError {}
+
+/// Encodes `bytes` as lower case hex.
+pub(crate) fn encode(bytes: &[u8]) -> Result<Repr, ReserveError> {
+    let len = bytes.len().checked_mul(2).ok_or(ReserveError(()))?;
+    let mut repr = Repr::with_capacity(len)?;
+    // SAFETY: We have capacity for `len` bytes, and write exactly that many ASCII digits before
+    // setting the length.
+    unsafe {
+        let ptr = repr.as_mut_ptr();
+        for (i, byte) in bytes.iter().enumerate() {
+            *ptr.add(2 * i) = LOWER_DIGITS[(byte >> 4) as usize];
+            *ptr.add(2 * i + 1) = LOWER_DIGITS[(byte & 0xf) as usize];
+        }
+        repr.set_len(len);
+    }
+    Ok(repr)
+}
+
+/// Decodes pairs of upper or lower case hex digits into bytes.
+pub(crate) fn decode(s: &str) -> Result<CompactBytes, DecodeHexError> {
+    let digits = s.as_bytes();
+    if digits.len() % 2 != 0 {
+        return Err(DecodeHexError::OddLength);
+    }
+
+    let len = digits.len() / 2;
+    let mut bytes = CompactBytes::with_capacity(len);
+    let ptr = bytes.as_mut_ptr();
+    for (i, pair) in digits.chunks_exact(2).enumerate() {
+        let (hi, lo) = (DECODE[pair[0] as usize], DECODE[pair[1] as usize]);
+        if hi == INVALID {
+            return Err(DecodeHexError::InvalidDigit(2 * i));
+        }
+        if lo == INVALID {
+            return Err(DecodeHexError::InvalidDigit(2 * i + 1));
+        }
+        // SAFETY: We have capacity for `len` bytes, and `i < len`
+        unsafe { *ptr.add(i) = hi << 4 | lo };
+    }
+    // SAFETY: We just initialized all `len` bytes
+    unsafe { bytes.set_len(len) };
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use super::DecodeHexError;
+    use crate::repr::MAX_SIZE;
+    use crate::tests::rand_bytes;
+    use crate::CompactString;
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_roundtrip(#[strategy(rand_bytes())] bytes: Vec<u8>) {
+        let hex = CompactString::from_hex_bytes(&bytes);
+        let expected: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+        prop_assert_eq!(&hex, &expected);
+        prop_assert_eq!(hex.is_heap_allocated(), expected.len() > MAX_SIZE);
+
+        prop_assert_eq!(hex.decode_hex().unwrap(), &bytes);
+        let upper = CompactString::new(expected.to_ascii_uppercase());
+        prop_assert_eq!(upper.decode_hex().unwrap(), &bytes);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let decode = |s: &str| CompactString::new(s).decode_hex();
+        assert_eq!(decode("").unwrap(), b"");
+        assert_eq!(decode("0").unwrap_err(), DecodeHexError::OddLength);
+        assert_eq!(decode("0g").unwrap_err(), DecodeHexError::InvalidDigit(1));
+        assert_eq!(decode("ff g0").unwrap_err(), DecodeHexError::OddLength);
+        assert_eq!(decode("ff0 ").unwrap_err(), DecodeHexError::InvalidDigit(3));
+        assert_eq!(decode("ffé").unwrap_err(), DecodeHexError::InvalidDigit(2));
+        assert_eq!(decode("DEADbeef").unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+    }
+}
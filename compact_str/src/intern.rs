@@ -0,0 +1,232 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use core::fmt;
+use std::collections::HashSet;
+use std::sync::{OnceLock, RwLock};
+
+use crate::repr::MAX_SIZE;
+use crate::CompactString;
+
+/// Size of each chunk of memory the interned strings get copied into.
+const CHUNK_SIZE: usize = 16 * 1024;
+/// Strings larger than this get their own allocation, so they don't waste the rest of a chunk.
+const MAX_CHUNKED_SIZE: usize = CHUNK_SIZE / 4;
+
+/// A thread-safe string interner that hands out [`CompactString`]s.
+///
+/// Strings that are short enough to be inlined are returned as inline [`CompactString`]s without
+/// ever touching the interner. Longer strings are copied once into an arena, and every call to
+/// [`Interner::intern`] with the same text returns a [`CompactString`] that points at that copy,
+/// the same way [`CompactString::const_new`] points at a `&'static str`. This makes interned
+/// strings `O(1)` to clone, and [`CompactString::as_static_str`] returns the interned text.
+///
+/// Note: Interned strings are never freed, they live for the rest of the program, even if the
+/// [`Interner`] itself gets dropped.
+///
+/// # Examples
+/// ```
+/// use compact_str::Interner;
+///
+/// let interner = Interner::new();
+///
+/// let a = interner.intern("a long identifier that does not fit inline");
+/// let b = interner.intern("a long identifier that does not fit inline");
+/// assert!(!a.is_heap_allocated());
+/// assert_eq!(a.as_static_str().unwrap().as_ptr(), b.as_static_str().unwrap().as_ptr());
+///
+/// // short strings are inlined
+/// let short = interner.intern("short");
+/// assert!(short.as_static_str().is_none());
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "intern")))]
+pub struct Interner {
+    state: RwLock<State>,
+}
+
+#[derive(Default)]
+struct State {
+    strings: HashSet<&'static str>,
+    /// The remaining, unused, bytes of our current chunk.
+    chunk: &'static mut [u8],
+}
+
+impl Interner {
+    /// Creates a new, empty, [`Interner`].
+    pub fn new() -> Self {
+        Interner {
+            state: RwLock::new(State::default()),
+        }
+    }
+
+    /// Returns the global [`Interner`] used by [`intern`].
+    pub fn global() -> &'static Interner {
+        static GLOBAL: OnceLock<Interner> = OnceLock::new();
+        GLOBAL.get_or_init(Interner::new)
+    }
+
+    /// Interns `text`, returning a [`CompactString`] that is either inlined, or points at the
+    /// interned copy of `text`.
+    pub fn intern(&self, text: &str) -> CompactString {
+        if text.len() <= MAX_SIZE {
+            return CompactString::new(text);
+        }
+
+        // Fast path, the string has already been interned.
+        let existing = self.read_state().strings.get(text).copied();
+        if let Some(interned) = existing {
+            return CompactString::const_new(interned);
+        }
+
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+        // Another thread could have interned the string while we were waiting for the lock.
+        let interned = match state.strings.get(text) {
+            Some(interned) => *interned,
+            None => {
+                let interned = state.alloc(text);
+                state.strings.insert(interned);
+                interned
+            }
+        };
+        CompactString::const_new(interned)
+    }
+
+    /// Returns the number of strings that have been interned, excluding inlined strings.
+    pub fn len(&self) -> usize {
+        self.read_state().strings.len()
+    }
+
+    /// Returns `true` if no strings have been interned.
+    pub fn is_empty(&self) -> bool {
+        self.read_state().strings.is_empty()
+    }
+
+    fn read_state(&self) -> std::sync::RwLockReadGuard<'_, State> {
+        // `State` is always left consistent, so it's fine to ignore poisoning.
+        self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl State {
+    /// Copies `text` into memory that lives for the rest of the program.
+    fn alloc(&mut self, text: &str) -> &'static str {
+        let len = text.len();
+
+        let buf: &'static mut [u8] = if len > MAX_CHUNKED_SIZE {
+            Box::leak(vec![0; len].into_boxed_slice())
+        } else {
+            if self.chunk.len() < len {
+                self.chunk = Box::leak(vec![0; CHUNK_SIZE].into_boxed_slice());
+            }
+            let (buf, rest) = core::mem::take(&mut self.chunk).split_at_mut(len);
+            self.chunk = rest;
+            buf
+        };
+
+        buf.copy_from_slice(text.as_bytes());
+        // SAFETY: We just copied the bytes of a valid `str` into `buf`
+        unsafe { core::str::from_utf8_unchecked(buf) }
+    }
+}
+
+impl Default for Interner {
+    fn default() -> Self {
+        Interner::new()
+    }
+}
+
+impl fmt::Debug for Interner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interner")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Interns `text` in the global [`Interner`].
+///
+/// # Examples
+/// ```
+/// let a = compact_str::intern("some_rather_long_identifier_name");
+/// let b = compact_str::intern("some_rather_long_identifier_name");
+///
+/// assert_eq!(a, b);
+/// assert_eq!(a.as_static_str().unwrap().as_ptr(), b.as_static_str().unwrap().as_ptr());
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "intern")))]
+#[inline]
+pub fn intern(text: &str) -> CompactString {
+    Interner::global().intern(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use super::{Interner, CHUNK_SIZE, MAX_CHUNKED_SIZE};
+    use crate::repr::MAX_SIZE;
+    use crate::tests::rand_unicode;
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_intern(#[strategy(rand_unicode())] s: String) {
+        let interner = Interner::new();
+        let a = interner.intern(&s);
+        let b = interner.intern(&s);
+
+        prop_assert_eq!(&a, &s);
+        prop_assert_eq!(&a, &b);
+        prop_assert!(!a.is_heap_allocated());
+        prop_assert_eq!(a.as_static_str().is_some(), s.len() > MAX_SIZE);
+        prop_assert_eq!(a.as_ptr() == b.as_ptr(), s.len() > MAX_SIZE);
+        prop_assert_eq!(interner.len(), usize::from(s.len() > MAX_SIZE));
+    }
+
+    #[test]
+    fn test_chunks() {
+        let interner = Interner::new();
+        let strings: Vec<String> = (0..2 * CHUNK_SIZE / MAX_SIZE)
+            .map(|i| format!("{i:0>width$}", width = MAX_SIZE + 1))
+            .chain([
+                "a".repeat(MAX_CHUNKED_SIZE),
+                "b".repeat(MAX_CHUNKED_SIZE + 1),
+                "c".repeat(CHUNK_SIZE * 2),
+            ])
+            .collect();
+
+        let interned: Vec<_> = strings.iter().map(|s| interner.intern(s)).collect();
+        assert_eq!(interner.len(), strings.len());
+        for (s, compact) in strings.iter().zip(&interned) {
+            assert_eq!(compact, s);
+            assert_eq!(interner.intern(s).as_ptr(), compact.as_ptr());
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_threads() {
+        let interner = Interner::new();
+        let text = "a string that is shared between many threads";
+
+        let ptrs: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| interner.intern(text).as_ptr() as usize))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        assert!(ptrs.windows(2).all(|w| w[0] == w[1]));
+        assert_eq!(interner.len(), 1);
+    }
+
+    #[test]
+    fn test_global() {
+        let a = super::intern("a string interned in the global interner");
+        let b = super::intern(&String::from("a string interned in the global interner"));
+        assert_eq!(a.as_ptr(), b.as_ptr());
+        assert!(a.as_static_str().is_some());
+    }
+}
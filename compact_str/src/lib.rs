@@ -12,6 +12,8 @@ extern crate alloc;
 use alloc::borrow::Cow;
 use alloc::boxed::Box;
 use alloc::string::String;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
 use castaway::match_type;
 #[doc(hidden)] // Referenced in macros.
 pub use core;
@@ -26,14 +28,47 @@ use core::{fmt, mem, slice};
 use std::ffi::OsStr;
 
 mod features;
+#[cfg(feature = "unicode-width")]
+pub use features::Align;
 mod macros;
+#[doc(hidden)] // Referenced in macros.
+pub use macros::__private;
 mod unicode_data;
 
 mod repr;
-use repr::Repr;
+use repr::{Repr, MAX_SIZE};
+
+mod format;
+pub use format::{Exponent, Float, FloatFormat, IntFormat, Integer, NanInfStyle};
+mod parse;
+pub use parse::{FloatErrorKind, ParseFloatError, ParseIntError};
+mod hash;
+pub use hash::{FastBuildHasher, FastHasher};
+mod hex;
+pub use hex::DecodeHexError;
+mod sort;
+pub use sort::sort_compact_strings;
+mod casing;
+pub use casing::CaseLocale;
+mod builder;
+pub use builder::CompactStringBuilder;
 
 mod traits;
-pub use traits::{CompactStringExt, ToCompactString};
+pub use traits::{CompactStrExt, CompactStrIter, CompactStringExt, ToCompactString};
+
+mod allocator;
+pub use allocator::{AllocError, Allocator};
+mod compact_bytes;
+pub use compact_bytes::CompactBytes;
+mod compact_cow;
+pub use compact_cow::CompactCow;
+mod compact_string_n;
+pub use compact_string_n::{CompactStringN, DrainN, InlineSize, SupportedInlineSize};
+
+#[cfg(feature = "intern")]
+mod intern;
+#[cfg(feature = "intern")]
+pub use intern::{intern, Interner};
 
 #[cfg(test)]
 mod tests;
@@ -246,6 +281,374 @@ impl CompactString {
         self.0.as_static_str()
     }
 
+    /// Formats a [`u64`] into an inline [`CompactString`] at compile time.
+    ///
+    /// The result is the same as [`ToCompactString::to_compact_string`], but this can be used to
+    /// build `const` and `static` items, like tables of numeric labels.
+    ///
+    /// # Panics
+    /// Panics if the formatted number doesn't fit inline, which can only happen for numbers with
+    /// more than 12 digits on 32-bit targets. In a `const` context this is a compile error.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// static PORTS: [CompactString; 3] = [
+    ///     CompactString::const_from_u64(80),
+    ///     CompactString::const_from_u64(443),
+    ///     CompactString::const_from_u64(8080),
+    /// ];
+    /// assert_eq!(PORTS[1], "443");
+    /// ```
+    #[inline]
+    pub const fn const_from_u64(n: u64) -> Self {
+        CompactString(Repr::const_from_u64(n, false))
+    }
+
+    /// Formats an [`i64`] into an inline [`CompactString`] at compile time.
+    ///
+    /// # Panics
+    /// Panics if the formatted number doesn't fit inline, which can only happen for numbers with
+    /// more than 11 digits on 32-bit targets. In a `const` context this is a compile error.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// const OFFSET: CompactString = CompactString::const_from_i64(-42);
+    /// assert_eq!(OFFSET, "-42");
+    /// ```
+    #[inline]
+    pub const fn const_from_i64(n: i64) -> Self {
+        CompactString(Repr::const_from_u64(n.unsigned_abs(), n < 0))
+    }
+
+    /// Formats a [`u32`] into an inline [`CompactString`] at compile time, see
+    /// [`CompactString::const_from_u64`].
+    #[inline]
+    pub const fn const_from_u32(n: u32) -> Self {
+        CompactString(Repr::const_from_u64(n as u64, false))
+    }
+
+    /// Formats a [`i32`] into an inline [`CompactString`] at compile time, see
+    /// [`CompactString::const_from_i64`].
+    #[inline]
+    pub const fn const_from_i32(n: i32) -> Self {
+        CompactString(Repr::const_from_u64(n.unsigned_abs() as u64, n < 0))
+    }
+
+    /// Formats a [`u16`] into an inline [`CompactString`] at compile time, see
+    /// [`CompactString::const_from_u64`].
+    #[inline]
+    pub const fn const_from_u16(n: u16) -> Self {
+        CompactString(Repr::const_from_u64(n as u64, false))
+    }
+
+    /// Formats a [`i16`] into an inline [`CompactString`] at compile time, see
+    /// [`CompactString::const_from_i64`].
+    #[inline]
+    pub const fn const_from_i16(n: i16) -> Self {
+        CompactString(Repr::const_from_u64(n.unsigned_abs() as u64, n < 0))
+    }
+
+    /// Formats a [`u8`] into an inline [`CompactString`] at compile time, see
+    /// [`CompactString::const_from_u64`].
+    #[inline]
+    pub const fn const_from_u8(n: u8) -> Self {
+        CompactString(Repr::const_from_u64(n as u64, false))
+    }
+
+    /// Formats a [`i8`] into an inline [`CompactString`] at compile time, see
+    /// [`CompactString::const_from_i64`].
+    #[inline]
+    pub const fn const_from_i8(n: i8) -> Self {
+        CompactString(Repr::const_from_u64(n.unsigned_abs() as u64, n < 0))
+    }
+
+    /// Formats a [`usize`] into an inline [`CompactString`] at compile time, see
+    /// [`CompactString::const_from_u64`].
+    #[inline]
+    pub const fn const_from_usize(n: usize) -> Self {
+        CompactString(Repr::const_from_u64(n as u64, false))
+    }
+
+    /// Formats a [`isize`] into an inline [`CompactString`] at compile time, see
+    /// [`CompactString::const_from_i64`].
+    #[inline]
+    pub const fn const_from_isize(n: isize) -> Self {
+        CompactString(Repr::const_from_u64(n.unsigned_abs() as u64, n < 0))
+    }
+
+    /// Formats an integer into a [`CompactString`] according to `format`, which sets the radix,
+    /// the case of the letters, and the padding.
+    ///
+    /// The digits are written directly into the [`CompactString`], without going through
+    /// [`fmt::Write`]. Unlike the `{:x}` formatting of [`core::fmt`], negative numbers are
+    /// formatted as a `-` followed by their magnitude, the same format that
+    /// [`i32::from_str_radix`] parses.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::{CompactString, IntFormat};
+    ///
+    /// let format = IntFormat::new().radix(2).zero_pad(8);
+    /// assert_eq!(CompactString::from_int_with(5_u8, format), "00000101");
+    ///
+    /// let format = IntFormat::new().radix(16).uppercase(true).space_pad(5);
+    /// assert_eq!(CompactString::from_int_with(-255, format), "  -FF");
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn from_int_with<T: Integer>(value: T, format: IntFormat) -> Self {
+        let (n, negative) = format::sealed::Integer::into_parts(value);
+        CompactString(Repr::from_int_with(n, negative, &format).unwrap_with_msg())
+    }
+
+    /// Formats an integer in base `radix` into a [`CompactString`], using the digits `0-9` and
+    /// then the letters `a-z`.
+    ///
+    /// # Panics
+    /// Panics if `radix` is not in the range `2..=36`.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// assert_eq!(CompactString::from_int_radix(255_u8, 2), "11111111");
+    /// assert_eq!(CompactString::from_int_radix(-35, 36), "-z");
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn from_int_radix<T: Integer>(value: T, radix: u32) -> Self {
+        Self::from_int_with(value, IntFormat::new().radix(radix))
+    }
+
+    /// Formats an integer as lower case hexadecimal into a [`CompactString`].
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// assert_eq!(CompactString::from_hex_lower(0xdead_beef_u32), "deadbeef");
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn from_hex_lower<T: Integer>(value: T) -> Self {
+        Self::from_int_with(value, IntFormat::new().radix(16))
+    }
+
+    /// Formats an integer as upper case hexadecimal into a [`CompactString`].
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// assert_eq!(CompactString::from_hex_upper(0xdead_beef_u32), "DEADBEEF");
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn from_hex_upper<T: Integer>(value: T) -> Self {
+        Self::from_int_with(value, IntFormat::new().radix(16).uppercase(true))
+    }
+
+    /// Encodes `bytes` as lower case hexadecimal into a [`CompactString`], two digits per byte.
+    ///
+    /// The result is written directly into an inline buffer, or a heap buffer of exactly the right
+    /// size. See [`CompactString::decode_hex()`] to decode it again.
+    ///
+    /// # Panics
+    /// Panics if the system is out-of-memory.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// let hex = CompactString::from_hex_bytes(&[0xde, 0xad, 0x00, 0x0f]);
+    /// assert_eq!(hex, "dead000f");
+    /// assert!(!hex.is_heap_allocated());
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn from_hex_bytes(bytes: &[u8]) -> Self {
+        CompactString(hex::encode(bytes).unwrap_with_msg())
+    }
+
+    /// Formats an [`f64`] into a [`CompactString`], with the precision, notation and `NaN` style
+    /// set by `format`.
+    ///
+    /// With a precision set, the output matches [`core::fmt`], e.g. `{:.2}` or `{:.3e}`. Without
+    /// one, it uses the shortest digits that roundtrip, the same as [`ToCompactString`], which can
+    /// differ from `{}` or `{:e}` in the last digit when two candidates are equally close. Short
+    /// results are formatted directly into inline storage, without going through [`core::fmt`].
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::{CompactString, Exponent, FloatFormat, NanInfStyle};
+    ///
+    /// let fixed = FloatFormat::new().precision(3);
+    /// assert_eq!(CompactString::from_f64_with(1.0 / 3.0, fixed), "0.333");
+    /// assert_eq!(CompactString::from_f64_with(0.125, fixed.precision(2)), "0.12");
+    ///
+    /// let scientific = FloatFormat::new().exponent(Exponent::Upper).precision(2);
+    /// assert_eq!(CompactString::from_f64_with(-1234.5, scientific), "-1.23E3");
+    ///
+    /// let js = FloatFormat::new().nan_inf_style(NanInfStyle::JavaScript);
+    /// assert_eq!(CompactString::from_f64_with(f64::INFINITY, js), "Infinity");
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn from_f64_with(value: f64, format: FloatFormat) -> Self {
+        CompactString(Repr::from_float_with(value, &format))
+    }
+
+    /// Formats an [`f32`] into a [`CompactString`], see [`CompactString::from_f64_with`].
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::{CompactString, FloatFormat};
+    ///
+    /// let format = FloatFormat::new().precision(1);
+    /// assert_eq!(CompactString::from_f32_with(2.25, format), "2.2");
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn from_f32_with(value: f32, format: FloatFormat) -> Self {
+        CompactString(Repr::from_float_with(value, &format))
+    }
+
+    /// Returns the first 8 bytes of the [`CompactString`] as a big endian [`u64`], padded with
+    /// zeros.
+    ///
+    /// Comparing prefixes agrees with comparing the strings: if `a < b` then
+    /// `a.prefix_u64() <= b.prefix_u64()`, so you only need to compare the strings themselves if
+    /// their prefixes are equal. That makes it a good key for radix or prefix sorting, see
+    /// [`sort_compact_strings`].
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// assert_eq!(CompactString::new("ab").prefix_u64(), 0x6162_0000_0000_0000);
+    ///
+    /// let a = CompactString::new("apple");
+    /// let b = CompactString::new("banana");
+    /// assert!(a.prefix_u64() < b.prefix_u64());
+    /// ```
+    #[inline]
+    pub fn prefix_u64(&self) -> u64 {
+        (self.0.sort_key() >> 64) as u64
+    }
+
+    /// Returns the first 16 bytes of the [`CompactString`] as a big endian [`u128`], padded with
+    /// zeros.
+    ///
+    /// This is like [`CompactString::prefix_u64`], but covers more of the string, which helps
+    /// when many strings share a prefix, e.g. paths or URLs.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// let a = CompactString::new("https://apple.com");
+    /// let b = CompactString::new("https://pear.com");
+    /// assert_eq!(a.prefix_u64(), b.prefix_u64());
+    /// assert!(a.sort_key() < b.sort_key());
+    /// ```
+    #[inline]
+    pub fn sort_key(&self) -> u128 {
+        self.0.sort_key()
+    }
+
+    /// Hashes the [`CompactString`] with a [`FastHasher`], inline strings are hashed as a few
+    /// whole words.
+    ///
+    /// This is the same as hashing it with [`FastBuildHasher`], or hashing the equivalent `&str`
+    /// with a [`FastHasher`]. It's not resistant to HashDoS attacks, see [`FastHasher`].
+    ///
+    /// # Examples
+    /// ```
+    /// use core::hash::BuildHasher;
+    ///
+    /// use compact_str::{CompactString, FastBuildHasher};
+    ///
+    /// let compact = CompactString::new("hello");
+    /// assert_eq!(compact.fast_hash(), FastBuildHasher.hash_one("hello"));
+    /// ```
+    #[inline]
+    pub fn fast_hash(&self) -> u64 {
+        let mut hasher = FastHasher::default();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Parses the [`CompactString`] as an integer, accepting the same syntax as [`str::parse`]: an
+    /// optional sign followed by ASCII digits.
+    ///
+    /// This is faster than [`str::parse`] for short strings, because it parses eight digits at a
+    /// time, and the returned [`ParseIntError`] tells you where parsing failed.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    /// use core::num::IntErrorKind;
+    ///
+    /// assert_eq!(CompactString::new("-1234").parse_int::<i32>(), Ok(-1234));
+    ///
+    /// let err = CompactString::new("300").parse_int::<u8>().unwrap_err();
+    /// assert_eq!(err.kind(), &IntErrorKind::PosOverflow);
+    /// assert_eq!(err.position(), 2);
+    /// ```
+    #[inline]
+    pub fn parse_int<T: Integer>(&self) -> Result<T, ParseIntError> {
+        parse::parse_int(self.as_str(), self.0.as_padded_slice())
+    }
+
+    /// Decodes the [`CompactString`] as pairs of upper or lower case hexadecimal digits.
+    ///
+    /// The bytes are written into a single [`CompactBytes`] of exactly the right size, which
+    /// stores up to [`CompactBytes::MAX_INLINE`] bytes inline.
+    ///
+    /// # Panics
+    /// Panics if the system is out-of-memory.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::{CompactString, DecodeHexError};
+    ///
+    /// let bytes = CompactString::new("DEADbeef").decode_hex().unwrap();
+    /// assert_eq!(bytes, [0xde, 0xad, 0xbe, 0xef]);
+    ///
+    /// let err = CompactString::new("dead-beef").decode_hex().unwrap_err();
+    /// assert_eq!(err, DecodeHexError::OddLength);
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn decode_hex(&self) -> Result<CompactBytes, DecodeHexError> {
+        hex::decode(self.as_str())
+    }
+
+    /// Parses the [`CompactString`] as a float, accepting the same syntax as [`str::parse`], e.g.
+    /// `1.5`, `-.5e-3` or `inf`.
+    ///
+    /// Floats that can be computed exactly skip the general algorithm in [`core`], and the
+    /// returned [`ParseFloatError`] tells you where parsing failed.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// assert_eq!(CompactString::new("12.375").parse_float::<f64>(), Ok(12.375));
+    /// assert_eq!(CompactString::new("-1e3").parse_float::<f32>(), Ok(-1000.0));
+    ///
+    /// let err = CompactString::new("1.2.3").parse_float::<f64>().unwrap_err();
+    /// assert_eq!(err.position(), 3);
+    /// ```
+    #[inline]
+    pub fn parse_float<T: Float>(&self) -> Result<T, ParseFloatError> {
+        parse::parse_float(self.as_str())
+    }
+
     /// Creates a new empty [`CompactString`] with the capacity to fit at least `capacity` bytes.
     ///
     /// A `CompactString` will inline strings on the stack, if they're small enough. Specifically,
@@ -315,6 +718,118 @@ impl CompactString {
         Repr::with_capacity(capacity).map(CompactString)
     }
 
+    /// Creates a new [`CompactString`] from any type that implements `AsRef<str>`, storing it in
+    /// a heap buffer allocated by `allocator` if it doesn't fit inline.
+    ///
+    /// The allocator is stored in a header in front of the heap buffer, and is used whenever the
+    /// buffer grows, shrinks, gets cloned, or gets dropped. Every heap buffer holds a reference to
+    /// it, so the allocator is dropped together with the last string it allocated.
+    ///
+    /// It is not used by strings that are stored inline, and a [`CompactString`] only holds on to
+    /// it for as long as it stays on the heap: strings that get inlined (e.g. by
+    /// [`CompactString::shrink_to_fit`]) and then grow again use the global allocator.
+    ///
+    /// # Panics
+    /// This method panics if the allocator fails to allocate memory. See
+    /// [`CompactString::try_new_in`] for a fallible version.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::alloc::{GlobalAlloc, Layout, System};
+    /// use std::ptr::NonNull;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// use compact_str::{AllocError, Allocator, CompactString};
+    ///
+    /// struct Counting(AtomicUsize);
+    ///
+    /// unsafe impl Allocator for Counting {
+    ///     fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+    ///         self.0.fetch_add(1, Ordering::Relaxed);
+    ///         NonNull::new(unsafe { System.alloc(layout) }).ok_or(AllocError)
+    ///     }
+    ///
+    ///     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+    ///         System.dealloc(ptr.as_ptr(), layout)
+    ///     }
+    /// }
+    ///
+    /// let allocator = Arc::new(Counting(AtomicUsize::new(0)));
+    ///
+    /// let mut s = CompactString::new_in("a string that is too long to be inlined", allocator.clone());
+    /// assert!(s.allocator().is_some());
+    /// assert_eq!(allocator.0.load(Ordering::Relaxed), 1);
+    ///
+    /// // growing the string re-allocates with the same allocator
+    /// s.push_str(&"!".repeat(100));
+    /// assert_eq!(allocator.0.load(Ordering::Relaxed), 2);
+    ///
+    /// // and so does cloning it
+    /// let clone = s.clone();
+    /// assert!(clone.allocator().is_some());
+    /// assert_eq!(allocator.0.load(Ordering::Relaxed), 3);
+    ///
+    /// // short strings are still inlined
+    /// let short = CompactString::new_in("short", allocator.clone());
+    /// assert!(short.allocator().is_none());
+    /// ```
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    #[track_caller]
+    pub fn new_in<T: AsRef<str>>(text: T, allocator: Arc<dyn Allocator>) -> Self {
+        Self::try_new_in(text, allocator).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactString::new_in()`]
+    ///
+    /// This method won't panic if the allocator fails to allocate memory, but return an
+    /// [`ReserveError`]. Otherwise it behaves the same as [`CompactString::new_in()`].
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    pub fn try_new_in<T: AsRef<str>>(
+        text: T,
+        allocator: Arc<dyn Allocator>,
+    ) -> Result<Self, ReserveError> {
+        Repr::new_in(text.as_ref(), allocator).map(CompactString)
+    }
+
+    /// Creates a new empty [`CompactString`] with the capacity to fit at least `capacity` bytes,
+    /// allocated by `allocator` if it's larger than what fits inline.
+    ///
+    /// See [`CompactString::new_in`] for how the allocator is used.
+    ///
+    /// # Panics
+    /// This method panics if the allocator fails to allocate memory. See
+    /// [`CompactString::try_with_capacity_in`] for a fallible version.
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    #[track_caller]
+    pub fn with_capacity_in(capacity: usize, allocator: Arc<dyn Allocator>) -> Self {
+        Self::try_with_capacity_in(capacity, allocator).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactString::with_capacity_in()`]
+    ///
+    /// This method won't panic if the allocator fails to allocate memory, but return an
+    /// [`ReserveError`]. Otherwise it behaves the same as [`CompactString::with_capacity_in()`].
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    pub fn try_with_capacity_in(
+        capacity: usize,
+        allocator: Arc<dyn Allocator>,
+    ) -> Result<Self, ReserveError> {
+        Repr::with_capacity_in(capacity, allocator).map(CompactString)
+    }
+
+    /// Returns the custom [`Allocator`] the heap buffer of this [`CompactString`] was allocated
+    /// with, or `None` if it's stored inline, or allocated by the global allocator.
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    pub fn allocator(&self) -> Option<&Arc<dyn Allocator>> {
+        self.0.allocator()
+    }
+
     /// Convert a slice of bytes into a [`CompactString`].
     ///
     /// A [`CompactString`] is a contiguous collection of bytes (`u8`s) that is valid [`UTF-8`](https://en.wikipedia.org/wiki/UTF-8).
@@ -347,6 +862,27 @@ impl CompactString {
         Repr::from_utf8(buf).map(CompactString)
     }
 
+    /// Fallible version of [`CompactString::from_utf8()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return a
+    /// [`ToCompactStringError::Reserve`]. Invalid UTF-8 is reported as a
+    /// [`ToCompactStringError::Utf8`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::{CompactString, ToCompactStringError};
+    /// let compact = CompactString::try_from_utf8(b"hello").unwrap();
+    /// assert_eq!(compact, "hello");
+    ///
+    /// let err = CompactString::try_from_utf8([255, 255, 255]).unwrap_err();
+    /// assert!(matches!(err, ToCompactStringError::Utf8(_)));
+    /// ```
+    #[inline]
+    pub fn try_from_utf8<B: AsRef<[u8]>>(buf: B) -> Result<Self, ToCompactStringError> {
+        let s = core::str::from_utf8(buf.as_ref())?;
+        Ok(Self::try_new(s)?)
+    }
+
     /// Converts a vector of bytes to a [`CompactString`] without checking that the string contains
     /// valid UTF-8.
     ///
@@ -380,9 +916,20 @@ impl CompactString {
     #[must_use]
     #[track_caller]
     pub unsafe fn from_utf8_unchecked<B: AsRef<[u8]>>(buf: B) -> Self {
-        Repr::from_utf8_unchecked(buf)
-            .map(CompactString)
-            .unwrap_with_msg()
+        Self::try_from_utf8_unchecked(buf).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactString::from_utf8_unchecked()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactString::from_utf8_unchecked()`].
+    ///
+    /// # Safety
+    ///
+    /// * The contents pased to this method must be valid UTF-8.
+    #[inline]
+    pub unsafe fn try_from_utf8_unchecked<B: AsRef<[u8]>>(buf: B) -> Result<Self, ReserveError> {
+        Repr::from_utf8_unchecked(buf).map(CompactString)
     }
 
     /// Decode a [`UTF-16`](https://en.wikipedia.org/wiki/UTF-16) slice of bytes into a
@@ -693,6 +1240,15 @@ impl CompactString {
         self.push_str(ch.encode_utf8(&mut [0; 4]));
     }
 
+    /// Fallible version of [`CompactString::push()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactString::push()`].
+    #[inline]
+    pub fn try_push(&mut self, ch: char) -> Result<(), ReserveError> {
+        self.try_push_str(ch.encode_utf8(&mut [0; 4]))
+    }
+
     /// Removes the last character from the [`CompactString`] and returns it.
     /// Returns `None` if this [`CompactString`] is empty.
     ///
@@ -728,6 +1284,25 @@ impl CompactString {
         self.0.push_str(s)
     }
 
+    /// Fallible version of [`CompactString::push_str()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactString::push_str()`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactString;
+    /// let mut s = CompactString::new("abc");
+    ///
+    /// s.try_push_str("123").expect("out of memory");
+    ///
+    /// assert_eq!("abc123", s);
+    /// ```
+    #[inline]
+    pub fn try_push_str(&mut self, s: &str) -> Result<(), ReserveError> {
+        self.0.try_push_str(s)
+    }
+
     /// Removes a [`char`] from this [`CompactString`] at a byte position and returns it.
     ///
     /// This is an *O*(*n*) operation, as it requires copying every element in the
@@ -810,6 +1385,8 @@ impl CompactString {
 
     /// Returns whether or not the [`CompactString`] is heap allocated.
     ///
+    /// Note: A shared string, see [`CompactString::into_shared`], is also heap allocated.
+    ///
     /// # Examples
     /// ### Inlined
     /// ```
@@ -828,46 +1405,45 @@ impl CompactString {
     /// ```
     #[inline]
     pub fn is_heap_allocated(&self) -> bool {
-        self.0.is_heap_allocated()
+        self.0.is_heap_allocated() || self.0.is_shared()
     }
 
-    /// Ensure that the given range is inside the set data, and that no codepoints are split.
+    /// Returns whether or not the [`CompactString`] is a reference counted, shared string.
     ///
-    /// Returns the range `start..end` as a tuple.
+    /// Cloning a shared string is `O(1)`, it only increments the reference count. See
+    /// [`CompactString::into_shared`].
     #[inline]
-    fn ensure_range(&self, range: impl RangeBounds<usize>) -> (usize, usize) {
-        #[cold]
-        #[inline(never)]
-        fn illegal_range() -> ! {
-            panic!("illegal range");
-        }
-
-        let start = match range.start_bound() {
-            Bound::Included(&n) => n,
-            Bound::Excluded(&n) => match n.checked_add(1) {
-                Some(n) => n,
-                None => illegal_range(),
-            },
-            Bound::Unbounded => 0,
-        };
-        let end = match range.end_bound() {
-            Bound::Included(&n) => match n.checked_add(1) {
-                Some(n) => n,
-                None => illegal_range(),
-            },
-            Bound::Excluded(&n) => n,
-            Bound::Unbounded => self.len(),
-        };
-        if end < start {
-            illegal_range();
-        }
-
-        let s = self.as_str();
-        if !s.is_char_boundary(start) || !s.is_char_boundary(end) {
-            illegal_range();
-        }
+    pub fn is_shared(&self) -> bool {
+        self.0.is_shared()
+    }
 
-        (start, end)
+    /// Moves a heap allocated [`CompactString`] into a reference counted buffer, so that cloning
+    /// it only increments a reference count instead of copying the string.
+    ///
+    /// Inline strings and strings created via [`CompactString::const_new`] are already cheap to
+    /// clone, so they are returned unchanged. Mutating a shared string first copies it into a
+    /// buffer that is owned by just that [`CompactString`], i.e. copy-on-write.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactString;
+    /// let long = CompactString::new("this is a long string that will be on the heap");
+    /// let shared = long.into_shared();
+    /// assert!(shared.is_shared());
+    ///
+    /// // clones point at the same buffer
+    /// let mut clone = shared.clone();
+    /// assert_eq!(clone.as_ptr(), shared.as_ptr());
+    ///
+    /// // mutating a clone copies it
+    /// clone.push('!');
+    /// assert!(!clone.is_shared());
+    /// assert_eq!(shared, "this is a long string that will be on the heap");
+    /// ```
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    pub fn into_shared(self) -> Self {
+        CompactString(self.0.into_shared())
     }
 
     /// Removes the specified range in the [`CompactString`],
@@ -897,14 +1473,41 @@ impl CompactString {
     /// assert_eq!(s, "Hello! Is it me you're looking for?");
     /// ```
     #[inline]
+    #[track_caller]
     pub fn replace_range(&mut self, range: impl RangeBounds<usize>, replace_with: &str) {
-        let (start, end) = self.ensure_range(range);
+        self.try_replace_range(range, replace_with)
+            .unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactString::replace_range()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactString::replace_range()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point or end point do not lie on a [`char`]
+    /// boundary, or if they're out of bounds.
+    #[inline]
+    pub fn try_replace_range(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        replace_with: &str,
+    ) -> Result<(), ReserveError> {
+        let (start, end) = ensure_range(self, range);
         let dest_len = end - start;
         match dest_len.cmp(&replace_with.len()) {
-            Ordering::Equal => unsafe { self.replace_range_same_size(start, end, replace_with) },
-            Ordering::Greater => unsafe { self.replace_range_shrink(start, end, replace_with) },
-            Ordering::Less => unsafe { self.replace_range_grow(start, end, replace_with) },
+            Ordering::Equal => {
+                self.0.try_make_mut()?;
+                unsafe { self.replace_range_same_size(start, end, replace_with) };
+            }
+            Ordering::Greater => {
+                self.0.try_make_mut()?;
+                unsafe { self.replace_range_shrink(start, end, replace_with) };
+            }
+            Ordering::Less => unsafe { self.replace_range_grow(start, end, replace_with)? },
         }
+        Ok(())
     }
 
     /// Replace into the same size.
@@ -936,9 +1539,14 @@ impl CompactString {
     }
 
     /// Replace, so self.len() gets bigger.
-    unsafe fn replace_range_grow(&mut self, start: usize, end: usize, replace_with: &str) {
+    unsafe fn replace_range_grow(
+        &mut self,
+        start: usize,
+        end: usize,
+        replace_with: &str,
+    ) -> Result<(), ReserveError> {
         let dest_len = end - start;
-        self.reserve(replace_with.len() - dest_len);
+        self.try_reserve(replace_with.len() - dest_len)?;
         let total_len = self.len();
         let new_len = total_len + (replace_with.len() - dest_len);
         let amount = total_len - end;
@@ -953,43 +1561,252 @@ impl CompactString {
         );
         // and lastly insert the replacement string
         core::ptr::copy_nonoverlapping(replace_with.as_ptr(), data.add(start), replace_with.len());
+        Ok(())
+    }
+
+    /// Creates a new [`CompactString`] by repeating a string `n` times.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the capacity would overflow.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use compact_str::CompactString;
+    /// assert_eq!(CompactString::new("abc").repeat(4), CompactString::new("abcabcabcabc"));
+    /// ```
+    ///
+    /// A panic upon overflow:
+    ///
+    /// ```should_panic
+    /// use compact_str::CompactString;
+    ///
+    /// // this will panic at runtime
+    /// let huge = CompactString::new("0123456789abcdef").repeat(usize::MAX);
+    /// ```
+    #[must_use]
+    pub fn repeat(&self, n: usize) -> Self {
+        if n == 0 || self.is_empty() {
+            Self::const_new("")
+        } else if n == 1 {
+            self.clone()
+        } else {
+            let cap = self.len().checked_mul(n).expect("capacity overflow");
+            let mut out = Self::with_capacity(cap);
+            (0..n).for_each(|_| out.push_str(self));
+            out
+        }
+    }
+
+    /// Fallible version of [`CompactString::repeat()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, or if the capacity would overflow,
+    /// but return an [`ReserveError`]. Otherwise it behaves the same as
+    /// [`CompactString::repeat()`].
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// let s = CompactString::new("abc");
+    /// assert_eq!(s.try_repeat(4).unwrap(), "abcabcabcabc");
+    /// assert!(s.try_repeat(usize::MAX).is_err());
+    /// ```
+    pub fn try_repeat(&self, n: usize) -> Result<Self, ReserveError> {
+        if n == 0 || self.is_empty() {
+            Ok(Self::const_new(""))
+        } else if n == 1 {
+            self.try_clone()
+        } else {
+            let cap = self.len().checked_mul(n).ok_or(ReserveError(()))?;
+            let mut out = Self::try_with_capacity(cap)?;
+            (0..n).for_each(|_| out.push_str(self));
+            Ok(out)
+        }
+    }
+
+    /// Fallible version of [`Clone::clone()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Only heap allocated strings need to allocate when they get cloned, inline strings,
+    /// `&'static str`s and shared strings are cloned without allocating.
+    #[inline]
+    pub fn try_clone(&self) -> Result<Self, ReserveError> {
+        self.0.try_clone().map(CompactString)
+    }
+
+    /// Appends every item of `iter` to the end of this [`CompactString`].
+    ///
+    /// This is a fallible version of [`Extend::extend()`]: it won't panic if the system is
+    /// out-of-memory, but return an [`ReserveError`]. The items that were appended before the
+    /// allocation failed are kept.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactString;
+    /// let mut s = CompactString::new("a");
+    ///
+    /// s.try_extend(["b", "c"]).expect("out of memory");
+    /// assert_eq!(s, "abc");
+    /// ```
+    pub fn try_extend<I, S>(&mut self, iter: I) -> Result<(), ReserveError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        iter.into_iter()
+            .try_for_each(|s| self.try_push_str(s.as_ref()))
+    }
+
+    /// Appends every [`char`] of `iter` to the end of this [`CompactString`].
+    ///
+    /// This is a fallible version of [`Extend::extend()`]: it won't panic if the system is
+    /// out-of-memory, but return an [`ReserveError`]. The chars that were appended before the
+    /// allocation failed are kept.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactString;
+    /// let mut s = CompactString::new("a");
+    ///
+    /// s.try_extend_chars("bc".chars()).expect("out of memory");
+    /// assert_eq!(s, "abc");
+    /// ```
+    #[inline]
+    pub fn try_extend_chars<I: IntoIterator<Item = char>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), ReserveError> {
+        self.0.try_extend_chars(iter)
     }
 
-    /// Creates a new [`CompactString`] by repeating a string `n` times.
-    ///
-    /// # Panics
+    /// Replaces all matches of `from` with `to`, returning the result as a new
+    /// [`CompactString`].
     ///
-    /// This function will panic if the capacity would overflow.
+    /// This has the same semantics as [`str::replace`], but the result is built directly into a
+    /// [`CompactString`], so it's inlined if it's short enough and no intermediate [`String`] is
+    /// allocated.
     ///
     /// # Examples
+    /// ```
+    /// # use compact_str::CompactString;
+    /// let s = CompactString::new("this is old");
     ///
-    /// Basic usage:
-    ///
+    /// assert_eq!(s.replace("old", "new"), "this is new");
+    /// assert_eq!(s.replace("is", "an"), "than an old");
     /// ```
-    /// use compact_str::CompactString;
-    /// assert_eq!(CompactString::new("abc").repeat(4), CompactString::new("abcabcabcabc"));
+    #[must_use]
+    pub fn replace(&self, from: &str, to: &str) -> Self {
+        replace_matches(self, self.match_indices(from), to, from.len() <= to.len())
+    }
+
+    /// Replaces the first `count` matches of `from` with `to`, returning the result as a new
+    /// [`CompactString`].
+    ///
+    /// This has the same semantics as [`str::replacen`].
+    ///
+    /// # Examples
     /// ```
+    /// # use compact_str::CompactString;
+    /// let s = CompactString::new("foo foo 123 foo");
     ///
-    /// A panic upon overflow:
+    /// assert_eq!(s.replacen("foo", "new", 2), "new new 123 foo");
+    /// assert_eq!(s.replacen("foo", "new", 0), "foo foo 123 foo");
+    /// ```
+    #[must_use]
+    pub fn replacen(&self, from: &str, to: &str, count: usize) -> Self {
+        let matches = self.match_indices(from).take(count);
+        replace_matches(self, matches, to, from.len() <= to.len())
+    }
+
+    /// Replaces all occurrences of the character `from` with `to`, returning the result as a new
+    /// [`CompactString`].
     ///
-    /// ```should_panic
-    /// use compact_str::CompactString;
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactString;
+    /// let s = CompactString::new("snake_case_name");
     ///
-    /// // this will panic at runtime
-    /// let huge = CompactString::new("0123456789abcdef").repeat(usize::MAX);
+    /// assert_eq!(s.replace_char('_', '-'), "snake-case-name");
+    /// assert_eq!(s.replace_char('_', '🐍'), "snake🐍case🐍name");
     /// ```
     #[must_use]
-    pub fn repeat(&self, n: usize) -> Self {
-        if n == 0 || self.is_empty() {
-            Self::const_new("")
-        } else if n == 1 {
-            self.clone()
-        } else {
-            let cap = self.len().checked_mul(n).expect("capacity overflow");
-            let mut out = Self::with_capacity(cap);
-            (0..n).for_each(|_| out.push_str(self));
-            out
+    pub fn replace_char(&self, from: char, to: char) -> Self {
+        let mut buf = [0; 4];
+        let to = to.encode_utf8(&mut buf);
+        replace_matches(
+            self,
+            self.match_indices(from),
+            to,
+            from.len_utf8() <= to.len(),
+        )
+    }
+
+    /// Replaces all matches of `from` with `to`, in place.
+    ///
+    /// If `to` is not longer than `from`, the string is rewritten within its existing buffer and
+    /// never reallocates. Otherwise this is equivalent to `*self = self.replace(from, to)`. If
+    /// there are no matches, `self` is left untouched.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactString;
+    /// let mut s = CompactString::new("a long string with some words in it, words words words");
+    /// let capacity = s.capacity();
+    ///
+    /// s.replace_in_place("words", "ws");
+    /// assert_eq!(s, "a long string with some ws in it, ws ws ws");
+    /// assert_eq!(s.capacity(), capacity);
+    /// ```
+    pub fn replace_in_place(&mut self, from: &str, to: &str) {
+        if from.is_empty() || to.len() > from.len() {
+            // The string might grow, so we build a new one
+            if self.contains(from) {
+                *self = self.replace(from, to);
+            }
+            return;
+        }
+
+        let Some(first) = self.find(from) else {
+            return;
+        };
+
+        // SAFETY: We only ever write valid UTF-8 into the buffer, and only read the string from
+        // positions that are char boundaries of the original string.
+        let buf = unsafe { self.as_mut_bytes() };
+        let len = buf.len();
+
+        // `buf[..write]` contains the replaced string, `buf[read..]` the remaining original string
+        let mut read = first;
+        let mut write = first;
+        loop {
+            // `buf[read..read + from.len()]` is a match
+            buf[write..write + to.len()].copy_from_slice(to.as_bytes());
+            write += to.len();
+            read += from.len();
+
+            // SAFETY: `read` is right after a match, so it's a char boundary of the original
+            // string, which is still intact from `read` onwards
+            let rest = unsafe { core::str::from_utf8_unchecked(&buf[read..]) };
+            let next = rest.find(from).map(|idx| read + idx);
+
+            let end = next.unwrap_or(len);
+            if write != read {
+                buf.copy_within(read..end, write);
+            }
+            write += end - read;
+            read = end;
+
+            if next.is_none() {
+                break;
+            }
         }
+
+        // SAFETY: `buf[..write]` contains valid UTF-8, and `write <= len`
+        unsafe { self.set_len(write) };
     }
 
     /// Truncate the [`CompactString`] to a shorter length.
@@ -1026,6 +1843,147 @@ impl CompactString {
         unsafe { self.set_len(new_len) };
     }
 
+    /// Removes leading and trailing whitespace, in place.
+    ///
+    /// The remaining characters are moved to the front of the existing buffer, so calling this
+    /// function does not reallocate. An inline string stays inline, and a heap allocated string
+    /// that now fits inline is moved back inline, freeing its buffer. Otherwise the capacity
+    /// doesn't change, unless the [`CompactString`] is backed by a `&'static str` or is shared.
+    ///
+    /// 'Whitespace' is defined according to the terms of the Unicode Derived Core Property
+    /// `White_Space`, the same as [`str::trim`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactString;
+    /// let mut s = CompactString::new("   a string that is too long to be inlined   ");
+    /// let capacity = s.capacity();
+    ///
+    /// s.trim_in_place();
+    /// assert_eq!(s, "a string that is too long to be inlined");
+    /// assert_eq!(s.capacity(), capacity);
+    ///
+    /// let mut s = CompactString::new("        a string that fits         ");
+    /// assert!(s.is_heap_allocated());
+    /// s.trim_in_place();
+    /// assert_eq!(s, "a string that fits");
+    /// assert!(!s.is_heap_allocated());
+    /// ```
+    pub fn trim_in_place(&mut self) {
+        let trimmed = self.trim();
+        let start = trimmed.as_ptr() as usize - self.as_ptr() as usize;
+        let end = start + trimmed.len();
+        self.keep_range(start, end);
+    }
+
+    /// Removes leading whitespace, in place.
+    ///
+    /// See [`CompactString::trim_in_place`] for how the buffer is reused.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactString;
+    /// let mut s = CompactString::new("  hello  ");
+    /// s.trim_start_in_place();
+    /// assert_eq!(s, "hello  ");
+    /// ```
+    pub fn trim_start_in_place(&mut self) {
+        let start = self.len() - self.trim_start().len();
+        self.keep_range(start, self.len());
+    }
+
+    /// Removes trailing whitespace, in place.
+    ///
+    /// See [`CompactString::trim_in_place`] for how the buffer is reused.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactString;
+    /// let mut s = CompactString::new("  hello  ");
+    /// s.trim_end_in_place();
+    /// assert_eq!(s, "  hello");
+    /// ```
+    pub fn trim_end_in_place(&mut self) {
+        let end = self.trim_end().len();
+        self.keep_range(0, end);
+    }
+
+    /// Removes `prefix` from the start of the string, in place. Returns `true` if the string
+    /// started with `prefix`, otherwise the string is left untouched and `false` is returned.
+    ///
+    /// See [`CompactString::trim_in_place`] for how the buffer is reused.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactString;
+    /// let mut s = CompactString::new("foo:bar");
+    ///
+    /// assert!(s.strip_prefix_in_place("foo:"));
+    /// assert_eq!(s, "bar");
+    ///
+    /// assert!(!s.strip_prefix_in_place("foo:"));
+    /// assert_eq!(s, "bar");
+    /// ```
+    pub fn strip_prefix_in_place(&mut self, prefix: &str) -> bool {
+        if !self.starts_with(prefix) {
+            return false;
+        }
+        self.keep_range(prefix.len(), self.len());
+        true
+    }
+
+    /// Removes `suffix` from the end of the string, in place. Returns `true` if the string ended
+    /// with `suffix`, otherwise the string is left untouched and `false` is returned.
+    ///
+    /// See [`CompactString::trim_in_place`] for how the buffer is reused.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactString;
+    /// let mut s = CompactString::new("file.tar.gz");
+    ///
+    /// assert!(s.strip_suffix_in_place(".gz"));
+    /// assert_eq!(s, "file.tar");
+    ///
+    /// assert!(!s.strip_suffix_in_place(".gz"));
+    /// assert_eq!(s, "file.tar");
+    /// ```
+    pub fn strip_suffix_in_place(&mut self, suffix: &str) -> bool {
+        if !self.ends_with(suffix) {
+            return false;
+        }
+        self.keep_range(0, self.len() - suffix.len());
+        true
+    }
+
+    /// Keeps only `self[start..end]`, moving it to the front of the buffer.
+    ///
+    /// Strings backed by a `&'static str` are re-sliced, and shared strings are copied, since
+    /// neither can be modified in place. Heap allocated strings whose result fits inline are
+    /// moved inline.
+    fn keep_range(&mut self, start: usize, end: usize) {
+        if start == 0 && end == self.len() {
+            return;
+        }
+
+        if let Some(s) = self.as_static_str() {
+            *self = Self::const_new(&s[start..end]);
+        } else if self.is_shared() || (self.is_heap_allocated() && end - start <= MAX_SIZE) {
+            *self = Self::new(&self[start..end]);
+        } else {
+            assert!(
+                self.is_char_boundary(start) && self.is_char_boundary(end) && start <= end,
+                "range must lie on char boundaries",
+            );
+            // SAFETY: We just checked that `start` and `end` lie on char boundaries, so both
+            // `self[..end]` and `self[start..end]` are valid UTF-8
+            unsafe {
+                self.set_len(end);
+                self.replace_range_shrink(0, start, "");
+            }
+        }
+    }
+
     /// Converts a [`CompactString`] to a raw pointer.
     #[inline]
     pub fn as_ptr(&self) -> *const u8 {
@@ -1050,11 +2008,24 @@ impl CompactString {
     /// s.insert_str(5, ", world");
     /// assert_eq!(s, "Hello, world!");
     /// ```
+    #[track_caller]
     pub fn insert_str(&mut self, idx: usize, string: &str) {
+        self.try_insert_str(idx, string).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactString::insert_str()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactString::insert_str()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` does not lie on a [`char`] boundary.
+    pub fn try_insert_str(&mut self, idx: usize, string: &str) -> Result<(), ReserveError> {
         assert!(self.is_char_boundary(idx), "idx must lie on char boundary");
 
         let new_len = self.len() + string.len();
-        self.reserve(string.len());
+        self.try_reserve(string.len())?;
 
         // SAFETY: We just checked that we may split self at idx.
         //         We set the length only after reserving the memory.
@@ -1074,6 +2045,7 @@ impl CompactString {
             // and lastly resize the string
             self.set_len(new_len);
         }
+        Ok(())
     }
 
     /// Insert a character at an index.
@@ -1092,6 +2064,14 @@ impl CompactString {
         self.insert_str(idx, ch.encode_utf8(&mut [0; 4]));
     }
 
+    /// Fallible version of [`CompactString::insert()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactString::insert()`].
+    pub fn try_insert(&mut self, idx: usize, ch: char) -> Result<(), ReserveError> {
+        self.try_insert_str(idx, ch.encode_utf8(&mut [0; 4]))
+    }
+
     /// Reduces the length of the [`CompactString`] to zero.
     ///
     /// Calling this function does not change the capacity of the [`CompactString`], unless the
@@ -1170,7 +2150,7 @@ impl CompactString {
     /// assert_eq!(s, "Hello!");
     /// ```
     pub fn drain(&mut self, range: impl RangeBounds<usize>) -> Drain<'_> {
-        let (start, end) = self.ensure_range(range);
+        let (start, end) = ensure_range(self, range);
         Drain {
             compact_string: self as *mut Self,
             start,
@@ -1753,6 +2733,15 @@ impl CompactString {
         Self::from_str_to_lowercase(self.as_str())
     }
 
+    /// Fallible version of [`CompactString::to_lowercase()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactString::to_lowercase()`].
+    #[inline]
+    pub fn try_to_lowercase(&self) -> Result<Self, ReserveError> {
+        Self::try_from_str_to_lowercase(self.as_str())
+    }
+
     /// Returns the lowercase equivalent of this string slice, as a new [`CompactString`].
     ///
     /// 'Lowercase' is defined according to the terms of the Unicode Derived Core Property
@@ -1793,8 +2782,17 @@ impl CompactString {
     /// ```
     #[must_use = "this returns the lowercase string as a new CompactString, \
                   without modifying the original"]
+    #[track_caller]
     pub fn from_str_to_lowercase(input: &str) -> Self {
-        let mut s = convert_while_ascii(input.as_bytes(), u8::to_ascii_lowercase);
+        Self::try_from_str_to_lowercase(input).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactString::from_str_to_lowercase()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactString::from_str_to_lowercase()`].
+    pub fn try_from_str_to_lowercase(input: &str) -> Result<Self, ReserveError> {
+        let mut s = convert_while_ascii(input.as_bytes(), u8::to_ascii_lowercase)?;
 
         // Safety: we know this is a valid char boundary since
         // out.len() is only progressed if ascii bytes are found
@@ -1807,30 +2805,12 @@ impl CompactString {
                 // in `SpecialCasing.txt`,
                 // so hard-code it rather than have a generic "condition" mechanism.
                 // See https://github.com/rust-lang/rust/issues/26035
-                map_uppercase_sigma(rest, i, &mut s)
+                casing::map_uppercase_sigma(rest, i, &mut s)?;
             } else {
-                s.extend(c.to_lowercase());
-            }
-        }
-        return s;
-
-        fn map_uppercase_sigma(from: &str, i: usize, to: &mut CompactString) {
-            // See https://www.unicode.org/versions/Unicode7.0.0/ch03.pdf#G33992
-            // for the definition of `Final_Sigma`.
-            debug_assert!('Σ'.len_utf8() == 2);
-            let is_word_final = case_ignorable_then_cased(from[..i].chars().rev())
-                && !case_ignorable_then_cased(from[i + 2..].chars());
-            to.push_str(if is_word_final { "ς" } else { "σ" });
-        }
-
-        fn case_ignorable_then_cased<I: Iterator<Item = char>>(mut iter: I) -> bool {
-            use unicode_data::case_ignorable::lookup as Case_Ignorable;
-            use unicode_data::cased::lookup as Cased;
-            match iter.find(|&c| !Case_Ignorable(c)) {
-                Some(c) => Cased(c),
-                None => false,
+                s.try_extend_chars(c.to_lowercase())?;
             }
         }
+        Ok(s)
     }
 
     /// Returns the uppercase equivalent of this string slice, as a new [`CompactString`].
@@ -1875,6 +2855,15 @@ impl CompactString {
         Self::from_str_to_uppercase(self.as_str())
     }
 
+    /// Fallible version of [`CompactString::to_uppercase()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactString::to_uppercase()`].
+    #[inline]
+    pub fn try_to_uppercase(&self) -> Result<Self, ReserveError> {
+        Self::try_from_str_to_uppercase(self.as_str())
+    }
+
     /// Returns the uppercase equivalent of this string slice, as a new [`CompactString`].
     ///
     /// 'Uppercase' is defined according to the terms of the Unicode Derived Core Property
@@ -1911,29 +2900,186 @@ impl CompactString {
     /// ```
     #[must_use = "this returns the uppercase string as a new CompactString, \
                   without modifying the original"]
+    #[track_caller]
     pub fn from_str_to_uppercase(input: &str) -> Self {
-        let mut out = convert_while_ascii(input.as_bytes(), u8::to_ascii_uppercase);
+        Self::try_from_str_to_uppercase(input).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactString::from_str_to_uppercase()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactString::from_str_to_uppercase()`].
+    pub fn try_from_str_to_uppercase(input: &str) -> Result<Self, ReserveError> {
+        let mut out = convert_while_ascii(input.as_bytes(), u8::to_ascii_uppercase)?;
 
         // Safety: we know this is a valid char boundary since
         // out.len() is only progressed if ascii bytes are found
         let rest = unsafe { input.get_unchecked(out.len()..) };
 
         for c in rest.chars() {
-            out.extend(c.to_uppercase());
+            out.try_extend_chars(c.to_uppercase())?;
         }
 
-        out
+        Ok(out)
+    }
+
+    /// Returns the full Unicode case folding of this string, as a new [`CompactString`].
+    ///
+    /// Case folding maps strings that only differ in case to the same string, which makes it the
+    /// right tool for case-insensitive keys. It's similar to [`CompactString::to_lowercase`], but
+    /// uses the full mappings from `CaseFolding.txt`, e.g. `ß` folds to `ss`, and `ς` to `σ`.
+    /// This doesn't apply the special mappings for Turkish and Azerbaijani.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// let a = CompactString::new("Straße");
+    /// let b = CompactString::new("STRASSE");
+    /// assert_ne!(a.to_lowercase(), b.to_lowercase());
+    /// assert_eq!(a.to_casefold(), b.to_casefold());
+    /// assert_eq!(a.to_casefold(), "strasse");
+    /// ```
+    #[must_use = "this returns the case folded string as a new CompactString, \
+                  without modifying the original"]
+    #[track_caller]
+    pub fn to_casefold(&self) -> Self {
+        self.try_to_casefold().unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactString::to_casefold()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactString::to_casefold()`].
+    #[inline]
+    pub fn try_to_casefold(&self) -> Result<Self, ReserveError> {
+        casing::try_casefold(self.as_str())
+    }
+
+    /// Returns `true` if this string and `other` are equal after full Unicode case folding.
+    ///
+    /// This is the same as `self.to_casefold() == CompactString::new(other).to_casefold()`, but
+    /// doesn't allocate. See [`CompactString::to_casefold`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use compact_str::CompactString;
+    ///
+    /// let s = CompactString::new("Grüße");
+    /// assert!(s.eq_ignore_case("GRÜSSE"));
+    /// assert!(s.eq_ignore_case("grüsse"));
+    /// assert!(!s.eq_ignore_case("grusse"));
+    /// ```
+    #[inline]
+    pub fn eq_ignore_case(&self, other: &str) -> bool {
+        casing::eq_ignore_case(self.as_str(), other)
+    }
+
+    /// Returns the lowercase equivalent of this string in the language `locale`, as a new
+    /// [`CompactString`].
+    ///
+    /// This applies the language specific mappings from `SpecialCasing.txt` on top of
+    /// [`CompactString::to_lowercase`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use compact_str::{CaseLocale, CompactString};
+    ///
+    /// let s = CompactString::new("DİYARBAKIR");
+    /// assert_eq!(s.to_lowercase_in(CaseLocale::Turkish), "diyarbakır");
+    ///
+    /// // Lithuanian keeps the dot of an `i` with an accent
+    /// let s = CompactString::new("Ì");
+    /// assert_eq!(s.to_lowercase_in(CaseLocale::Lithuanian), "i\u{307}\u{300}");
+    /// ```
+    #[must_use = "this returns the lowercase string as a new CompactString, \
+                  without modifying the original"]
+    #[track_caller]
+    pub fn to_lowercase_in(&self, locale: CaseLocale) -> Self {
+        self.try_to_lowercase_in(locale).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactString::to_lowercase_in()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactString::to_lowercase_in()`].
+    #[inline]
+    pub fn try_to_lowercase_in(&self, locale: CaseLocale) -> Result<Self, ReserveError> {
+        casing::try_lowercase_in(self.as_str(), locale)
+    }
+
+    /// Returns the uppercase equivalent of this string in the language `locale`, as a new
+    /// [`CompactString`].
+    ///
+    /// This applies the language specific mappings from `SpecialCasing.txt` on top of
+    /// [`CompactString::to_uppercase`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use compact_str::{CaseLocale, CompactString};
+    ///
+    /// let s = CompactString::new("istanbul");
+    /// assert_eq!(s.to_uppercase_in(CaseLocale::Turkish), "İSTANBUL");
+    /// assert_eq!(s.to_uppercase(), "ISTANBUL");
+    /// ```
+    #[must_use = "this returns the uppercase string as a new CompactString, \
+                  without modifying the original"]
+    #[track_caller]
+    pub fn to_uppercase_in(&self, locale: CaseLocale) -> Self {
+        self.try_to_uppercase_in(locale).unwrap_with_msg()
+    }
+
+    /// Fallible version of [`CompactString::to_uppercase_in()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return an [`ReserveError`].
+    /// Otherwise it behaves the same as [`CompactString::to_uppercase_in()`].
+    #[inline]
+    pub fn try_to_uppercase_in(&self, locale: CaseLocale) -> Result<Self, ReserveError> {
+        casing::try_uppercase_in(self.as_str(), locale)
     }
 }
 
+/// Copies `s` into a new [`CompactString`], substituting each of `matches` with `to`.
+///
+/// If `presize` is `true` the result is known to be at least as long as `s`, so we reserve that
+/// much space upfront.
+fn replace_matches<'a>(
+    s: &'a str,
+    matches: impl Iterator<Item = (usize, &'a str)>,
+    to: &str,
+    presize: bool,
+) -> CompactString {
+    let mut repr = if presize {
+        Repr::with_capacity(s.len()).unwrap_with_msg()
+    } else {
+        Repr::const_new("")
+    };
+
+    let mut last_end = 0;
+    for (start, part) in matches {
+        // SAFETY: `match_indices` yields char boundaries of `s`
+        repr.push_str(unsafe { s.get_unchecked(last_end..start) });
+        repr.push_str(to);
+        last_end = start + part.len();
+    }
+    // SAFETY: `last_end` is either 0, or the end of a match
+    repr.push_str(unsafe { s.get_unchecked(last_end..) });
+
+    CompactString(repr)
+}
+
 /// Converts the bytes while the bytes are still ascii.
 /// For better average performance, this is happens in chunks of `2*size_of::<usize>()`.
 /// Returns a vec with the converted bytes.
 ///
 /// Copied from https://doc.rust-lang.org/nightly/src/alloc/str.rs.html#623-666
 #[inline]
-fn convert_while_ascii(b: &[u8], convert: fn(&u8) -> u8) -> CompactString {
-    let mut out = CompactString::with_capacity(b.len());
+fn convert_while_ascii(b: &[u8], convert: fn(&u8) -> u8) -> Result<CompactString, ReserveError> {
+    let mut out = CompactString::try_with_capacity(b.len())?;
 
     const USIZE_SIZE: usize = mem::size_of::<usize>();
     const MAGIC_UNROLL: usize = 2;
@@ -1971,7 +3117,7 @@ fn convert_while_ascii(b: &[u8], convert: fn(&u8) -> u8) -> CompactString {
         out.set_len(i);
     }
 
-    out
+    Ok(out)
 }
 
 impl Clone for CompactString {
@@ -2048,7 +3194,14 @@ impl BorrowMut<str> for CompactString {
 impl Eq for CompactString {}
 
 impl<T: AsRef<str> + ?Sized> PartialEq<T> for CompactString {
+    #[inline]
     fn eq(&self, other: &T) -> bool {
+        // two inline strings can be compared a word at a time
+        if let Ok(other) = castaway::cast!(other, &CompactString) {
+            if let (Some(a), Some(b)) = (self.0.inline_words(), other.0.inline_words()) {
+                return a == b;
+            }
+        }
         self.as_str() == other.as_ref()
     }
 }
@@ -2138,8 +3291,19 @@ impl PartialOrd for CompactString {
 }
 
 impl Hash for CompactString {
+    #[inline]
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.as_str().hash(state)
+        match castaway::cast!(state, &mut FastHasher) {
+            Ok(hasher) => match self.0.inline_words() {
+                Some(words) => {
+                    // the same as `str::hash`, which writes the bytes followed by `0xff`
+                    hasher.write_inline(words);
+                    hasher.write_u8(0xff);
+                }
+                None => self.as_str().hash(hasher),
+            },
+            Err(state) => self.as_str().hash(state),
+        }
     }
 }
 
@@ -2215,8 +3379,19 @@ impl<'a> From<&'a CompactString> for Cow<'a, str> {
 
 #[cfg(target_has_atomic = "ptr")]
 impl From<CompactString> for alloc::sync::Arc<str> {
+    /// Converts a [`CompactString`] into an `Arc<str>`, in `O(1)` time if the string is shared
     fn from(value: CompactString) -> Self {
-        Self::from(value.as_str())
+        value.0.into_arc()
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl From<alloc::sync::Arc<str>> for CompactString {
+    /// Creates a [`CompactString`] from an `Arc<str>`. Short strings get inlined, longer strings
+    /// become shared strings that re-use the `Arc`'s buffer, see [`CompactString::into_shared`].
+    #[inline]
+    fn from(value: alloc::sync::Arc<str>) -> Self {
+        CompactString(Repr::from_arc(value))
     }
 }
 
@@ -2490,6 +3665,44 @@ impl AddAssign<&str> for CompactString {
     }
 }
 
+/// Ensure that the given range is inside `s`, and that no codepoints are split.
+///
+/// Returns the range `start..end` as a tuple.
+#[inline]
+pub(crate) fn ensure_range(s: &str, range: impl RangeBounds<usize>) -> (usize, usize) {
+    #[cold]
+    #[inline(never)]
+    fn illegal_range() -> ! {
+        panic!("illegal range");
+    }
+
+    let start = match range.start_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => match n.checked_add(1) {
+            Some(n) => n,
+            None => illegal_range(),
+        },
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&n) => match n.checked_add(1) {
+            Some(n) => n,
+            None => illegal_range(),
+        },
+        Bound::Excluded(&n) => n,
+        Bound::Unbounded => s.len(),
+    };
+    if end < start {
+        illegal_range();
+    }
+
+    if !s.is_char_boundary(start) || !s.is_char_boundary(end) {
+        illegal_range();
+    }
+
+    (start, end)
+}
+
 /// A possible error value when converting a [`CompactString`] from a UTF-16 byte slice.
 ///
 /// This type is the error type for the [`from_utf16`] method on [`CompactString`].
@@ -2620,7 +3833,8 @@ impl fmt::Display for ReserveError {
 #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
 impl std::error::Error for ReserveError {}
 
-/// A possible error value if [`ToCompactString::try_to_compact_string()`] failed.
+/// A possible error value if [`ToCompactString::try_to_compact_string()`],
+/// [`try_format_compact!`] or [`CompactString::try_from_utf8()`] failed.
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[non_exhaustive]
 pub enum ToCompactStringError {
@@ -2628,6 +3842,8 @@ pub enum ToCompactStringError {
     Reserve(ReserveError),
     /// [`Display::fmt()`][core::fmt::Display::fmt] returned an error
     Fmt(fmt::Error),
+    /// The input was not valid UTF-8
+    Utf8(Utf8Error),
 }
 
 impl fmt::Display for ToCompactStringError {
@@ -2635,6 +3851,7 @@ impl fmt::Display for ToCompactStringError {
         match self {
             ToCompactStringError::Reserve(err) => err.fmt(f),
             ToCompactStringError::Fmt(err) => err.fmt(f),
+            ToCompactStringError::Utf8(err) => err.fmt(f),
         }
     }
 }
@@ -2653,6 +3870,13 @@ impl From<fmt::Error> for ToCompactStringError {
     }
 }
 
+impl From<Utf8Error> for ToCompactStringError {
+    #[inline]
+    fn from(value: Utf8Error) -> Self {
+        Self::Utf8(value)
+    }
+}
+
 #[cfg(feature = "std")]
 #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
 impl std::error::Error for ToCompactStringError {
@@ -2660,6 +3884,7 @@ impl std::error::Error for ToCompactStringError {
         match self {
             ToCompactStringError::Reserve(err) => Some(err),
             ToCompactStringError::Fmt(err) => Some(err),
+            ToCompactStringError::Utf8(err) => Some(err),
         }
     }
 }
@@ -31,6 +31,249 @@ macro_rules! format_compact {
     }
 }
 
+/// Fallible version of [`format_compact!`], returning a
+/// `Result<CompactString, ToCompactStringError>`.
+///
+/// This macro won't panic if the system is out-of-memory, or if a formatting trait
+/// implementation returns an error, but return a `ToCompactStringError` instead.
+///
+/// # Examples
+/// ```
+/// use compact_str::try_format_compact;
+///
+/// let s = try_format_compact!("{} + {} = {}", 1, 2, 1 + 2).expect("out of memory");
+/// assert_eq!(s, "1 + 2 = 3");
+/// ```
+#[macro_export]
+macro_rules! try_format_compact {
+    ($($arg:tt)*) => {
+        $crate::ToCompactString::try_to_compact_string(&$crate::core::format_args!($($arg)*))
+    }
+}
+
+/// Like [`format_compact!`], but measures the result first, so it allocates at most once.
+///
+/// [`format_compact!`] writes into the string piece by piece, so a result that's too long to be
+/// inlined can end up reallocating a few times as it grows. This macro formats the arguments
+/// twice instead: once to count how many bytes they produce, and then into a
+/// [`CompactString`](crate::CompactString) with exactly that capacity. If the format string has no
+/// arguments, it's used as-is without formatting it at all.
+///
+/// Whether this is faster depends on how expensive the arguments are to format, it's worth it for
+/// long strings made of a few cheap pieces. The `Display` implementations of the arguments should
+/// write the same thing both times, if they don't the result is still correct, but may
+/// reallocate.
+///
+/// # Panics
+///
+/// Panics if the system is out-of-memory, or if a formatting trait implementation returns an
+/// error, see [`try_format_compact_exact!`](crate::try_format_compact_exact).
+///
+/// # Examples
+/// ```
+/// use compact_str::format_compact_exact;
+///
+/// let path = "/usr/local/share";
+/// let s = format_compact_exact!("{path}/{}/{}.txt", "compact_str", 42);
+///
+/// assert_eq!(s, "/usr/local/share/compact_str/42.txt");
+/// assert_eq!(s.capacity(), s.len());
+/// ```
+#[macro_export]
+macro_rules! format_compact_exact {
+    ($($arg:tt)*) => {
+        $crate::__private::format_exact($crate::core::format_args!($($arg)*))
+    }
+}
+
+/// Fallible version of [`format_compact_exact!`], returning a
+/// `Result<CompactString, ToCompactStringError>`.
+///
+/// This macro won't panic if the system is out-of-memory, or if a formatting trait
+/// implementation returns an error, but return a `ToCompactStringError` instead.
+///
+/// # Examples
+/// ```
+/// use compact_str::try_format_compact_exact;
+///
+/// let s = try_format_compact_exact!("{} + {} = {}", 1, 2, 1 + 2).expect("out of memory");
+/// assert_eq!(s, "1 + 2 = 3");
+/// ```
+#[macro_export]
+macro_rules! try_format_compact_exact {
+    ($($arg:tt)*) => {
+        $crate::__private::try_format_exact($crate::core::format_args!($($arg)*))
+    }
+}
+
+/// Creates a [`CompactString`](crate::CompactString) from a string literal, or any other
+/// `&'static str` constant, at compile time. Several constants are concatenated.
+///
+/// This is the same as [`CompactString::const_new`](crate::CompactString::const_new), but the
+/// arguments are guaranteed to be evaluated in a `const` context, so it fails to compile if they
+/// aren't constants. Strings that are short enough get inlined, and longer strings reference a
+/// `&'static str` without allocating. To concatenate values at runtime, see
+/// [`compact_concat!`](crate::compact_concat).
+///
+/// # Examples
+/// ```
+/// use compact_str::{compact, CompactString};
+///
+/// static GREETING: CompactString = compact!("hello world");
+/// assert_eq!(GREETING, "hello world");
+///
+/// const LONG: &str = "a string that is too long to be inlined";
+/// let long = compact!(LONG);
+/// assert_eq!(long.as_static_str(), Some(LONG));
+///
+/// const PREFIX: &str = "compact_str";
+/// static USER_AGENT: CompactString = compact!(PREFIX, "/", "0.9");
+/// assert_eq!(USER_AGENT, "compact_str/0.9");
+/// ```
+///
+/// Runtime values are rejected:
+/// ```compile_fail
+/// use compact_str::compact;
+///
+/// let text = String::from("hello");
+/// let s = compact!(text.as_str());
+/// ```
+#[macro_export]
+macro_rules! compact {
+    ($text:expr $(,)?) => {{
+        const __COMPACT_STR_TEXT: $crate::CompactString = $crate::CompactString::const_new($text);
+        __COMPACT_STR_TEXT
+    }};
+    ($($part:expr),* $(,)?) => {{
+        const __COMPACT_STR_PARTS: &[&str] = &[$($part),*];
+        const __COMPACT_STR_LEN: usize = $crate::__private::concat_len(__COMPACT_STR_PARTS);
+        const __COMPACT_STR_BYTES: &[u8; __COMPACT_STR_LEN] =
+            &$crate::__private::concat_bytes(__COMPACT_STR_PARTS);
+        const __COMPACT_STR_TEXT: $crate::CompactString =
+            $crate::CompactString::const_new($crate::__private::to_str(__COMPACT_STR_BYTES));
+        __COMPACT_STR_TEXT
+    }};
+}
+
+/// Concatenates strings, chars, integers, floats and bools into a
+/// [`CompactString`](crate::CompactString) at runtime.
+///
+/// The length of every argument is measured first, so the result is written straight into an
+/// inline buffer, or a heap buffer of exactly the right size, instead of growing as it goes like
+/// [`format_compact!`]. The arguments are borrowed, and evaluated once each. Integers and floats
+/// are formatted the same as [`ToCompactString`](crate::ToCompactString).
+///
+/// To build a string out of a varying number of pieces, see
+/// [`CompactStringBuilder`](crate::CompactStringBuilder). To concatenate constants at compile
+/// time, see [`compact!`].
+///
+/// # Migrating from the `const` `compact_concat!`
+/// This macro used to concatenate constants at compile time, which is now done by [`compact!`].
+/// It isn't `const` anymore, so a `compact_concat!` in a `const` or `static` stops compiling, and
+/// has to be replaced by `compact!` with the same arguments:
+/// ```
+/// use compact_str::{compact, CompactString};
+///
+/// const PREFIX: &str = "compact_str";
+/// // before: static USER_AGENT: CompactString = compact_concat!(PREFIX, "/", "0.9");
+/// static USER_AGENT: CompactString = compact!(PREFIX, "/", "0.9");
+/// assert_eq!(USER_AGENT, "compact_str/0.9");
+/// ```
+///
+/// ```compile_fail
+/// use compact_str::{compact_concat, CompactString};
+///
+/// static USER_AGENT: CompactString = compact_concat!("compact_str", "/", "0.9");
+/// ```
+///
+/// # Panics
+/// Panics if the system is out-of-memory, see [`try_compact_concat!`](crate::try_compact_concat).
+///
+/// # Examples
+/// ```
+/// use compact_str::{compact_concat, CompactString};
+///
+/// let a = CompactString::new("hello");
+/// let b = String::from("world");
+/// let s = compact_concat!(a, ", ", b, ' ', 42, '/', 0.5);
+///
+/// assert_eq!(s, "hello, world 42/0.5");
+/// assert!(!s.is_heap_allocated());
+/// ```
+#[macro_export]
+macro_rules! compact_concat {
+    ($($part:expr),* $(,)?) => {
+        $crate::__private::concat_pieces(&[
+            $($crate::__private::ToPiece::to_piece(&$part)),*
+        ])
+    };
+}
+
+/// Fallible version of [`compact_concat!`], returning a `Result<CompactString, ReserveError>`.
+///
+/// This macro won't panic if the system is out-of-memory, but return a `ReserveError` instead.
+///
+/// # Examples
+/// ```
+/// use compact_str::try_compact_concat;
+///
+/// let s = try_compact_concat!("x = ", 1.5).expect("out of memory");
+/// assert_eq!(s, "x = 1.5");
+/// ```
+#[macro_export]
+macro_rules! try_compact_concat {
+    ($($part:expr),* $(,)?) => {
+        $crate::__private::try_concat_pieces(&[
+            $($crate::__private::ToPiece::to_piece(&$part)),*
+        ])
+    };
+}
+
+/// Helpers used by our macros, not public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::builder::{concat_pieces, try_concat_pieces, Piece, ToPiece};
+    pub use crate::traits::{format_exact, try_format_exact};
+
+    /// Returns the combined length of `parts`.
+    pub const fn concat_len(parts: &[&str]) -> usize {
+        let mut len = 0;
+        let mut i = 0;
+        while i < parts.len() {
+            len += parts[i].len();
+            i += 1;
+        }
+        len
+    }
+
+    /// Concatenates `parts`, which must have a combined length of `N`.
+    pub const fn concat_bytes<const N: usize>(parts: &[&str]) -> [u8; N] {
+        let mut buf = [0; N];
+        let mut pos = 0;
+        let mut i = 0;
+        while i < parts.len() {
+            let part = parts[i].as_bytes();
+            let mut j = 0;
+            while j < part.len() {
+                buf[pos] = part[j];
+                pos += 1;
+                j += 1;
+            }
+            i += 1;
+        }
+        assert!(pos == N, "combined length of the parts must be N");
+        buf
+    }
+
+    /// Converts the output of [`concat_bytes`] back into a `&str`.
+    pub const fn to_str(bytes: &[u8]) -> &str {
+        match core::str::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(_) => panic!("concatenated strings are valid UTF-8"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -41,4 +284,50 @@ mod tests {
         assert!(!format_compact!("2").is_heap_allocated());
         assert!(!format_compact!("{}", 2).is_heap_allocated());
     }
+
+    #[test]
+    fn test_try_macros() {
+        use core::fmt;
+
+        use crate::ToCompactStringError;
+
+        struct Failing;
+
+        impl fmt::Display for Failing {
+            fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Err(fmt::Error)
+            }
+        }
+
+        assert_eq!(try_format_compact!("2").unwrap(), "2");
+        assert_eq!(try_format_compact!("{}", 2).unwrap(), "2");
+        assert_eq!(
+            try_format_compact!("{}", "a".repeat(100)).unwrap(),
+            "a".repeat(100)
+        );
+        assert_eq!(
+            try_format_compact!("a{}", Failing),
+            Err(ToCompactStringError::Fmt(fmt::Error))
+        );
+    }
+
+    #[test]
+    fn test_const_macros() {
+        use crate::repr::MAX_SIZE;
+        use crate::CompactString;
+
+        const LONG: &str = "a string that is too long to be inlined";
+        static EMPTY: CompactString = compact!();
+        static SHORT: CompactString = compact!("short",);
+        static CONCAT: CompactString = compact!("1", LONG, "🦀",);
+
+        assert_eq!(EMPTY, "");
+        assert_eq!(SHORT, "short");
+        assert_eq!(SHORT.as_static_str(), None);
+
+        assert_eq!(compact!(LONG).as_static_str(), Some(LONG));
+        assert_eq!(CONCAT, format!("1{LONG}🦀"));
+        assert!(CONCAT.len() > MAX_SIZE && CONCAT.as_static_str().is_some());
+        assert!(!compact!("a", "b").is_heap_allocated());
+    }
 }
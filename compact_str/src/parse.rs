@@ -0,0 +1,582 @@
+//! Parsing numbers out of a [`CompactString`](crate::CompactString).
+//!
+//! These parse the same syntax as [`str::parse`], but skip the generic machinery in `core`.
+//! Integers are parsed eight digits at a time with SWAR tricks, and floats that can be computed
+//! exactly with a single multiplication or division take a fast path, otherwise we fall back to
+//! `core`.
+
+use core::fmt;
+use core::num::IntErrorKind;
+
+use crate::format::sealed;
+use crate::{Float, Integer};
+
+/// The error returned by [`CompactString::parse_int`](crate::CompactString::parse_int).
+///
+/// Unlike [`core::num::ParseIntError`] it also tells you where in the string parsing failed.
+///
+/// # Examples
+/// ```
+/// use compact_str::CompactString;
+/// use core::num::IntErrorKind;
+///
+/// let err = CompactString::new("12x4").parse_int::<u32>().unwrap_err();
+/// assert_eq!(err.kind(), &IntErrorKind::InvalidDigit);
+/// assert_eq!(err.position(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseIntError {
+    kind: IntErrorKind,
+    position: usize,
+}
+
+impl ParseIntError {
+    /// Returns why parsing failed.
+    #[inline]
+    pub fn kind(&self) -> &IntErrorKind {
+        &self.kind
+    }
+
+    /// Returns the byte offset where parsing failed. That's the invalid digit, the digit that made
+    /// the value overflow, or the length of the string if it ended early.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for ParseIntError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self.kind {
+            IntErrorKind::Empty => "cannot parse integer from empty string",
+            IntErrorKind::InvalidDigit => "invalid digit found in string",
+            IntErrorKind::PosOverflow => "number too large to fit in target type",
+            IntErrorKind::NegOverflow => "number too small to fit in target type",
+            _ => "number would be zero for non-zero type",
+        };
+        write!(f, "{msg} at position {}", self.position)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for ParseIntError {}
+
+/// The error returned by [`CompactString::parse_float`](crate::CompactString::parse_float).
+///
+/// Unlike [`core::num::ParseFloatError`] it also tells you where in the string parsing failed.
+///
+/// # Examples
+/// ```
+/// use compact_str::{CompactString, FloatErrorKind};
+///
+/// let err = CompactString::new("1.5e").parse_float::<f64>().unwrap_err();
+/// assert_eq!(err.kind(), FloatErrorKind::Invalid);
+/// assert_eq!(err.position(), 4);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseFloatError {
+    kind: FloatErrorKind,
+    position: usize,
+}
+
+impl ParseFloatError {
+    /// Returns why parsing failed.
+    #[inline]
+    pub fn kind(&self) -> FloatErrorKind {
+        self.kind
+    }
+
+    /// Returns the byte offset where parsing failed, or the length of the string if it ended
+    /// early.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for ParseFloatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self.kind {
+            FloatErrorKind::Empty => "cannot parse float from empty string",
+            FloatErrorKind::Invalid => "invalid float literal",
+        };
+        write!(f, "{msg} at position {}", self.position)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for ParseFloatError {}
+
+/// Why [`CompactString::parse_float`](crate::CompactString::parse_float) failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FloatErrorKind {
+    /// The string was empty.
+    Empty,
+    /// The string wasn't a valid float literal.
+    Invalid,
+}
+
+#[inline]
+pub(crate) fn parse_int<T: Integer>(s: &str, padded: &[u8]) -> Result<T, ParseIntError> {
+    debug_assert!(padded.starts_with(s.as_bytes()));
+
+    let bytes = s.as_bytes();
+    let (negative, start) = match bytes {
+        [] => return Err(int_error(IntErrorKind::Empty, 0)),
+        [b'+' | b'-'] => return Err(int_error(IntErrorKind::InvalidDigit, 1)),
+        [b'-', ..] if <T as sealed::Integer>::SIGNED => (true, 1),
+        [b'+', ..] => (false, 1),
+        _ => (false, 0),
+    };
+
+    match parse_digits(padded, start, bytes.len()).map(|n| T::from_parts(n, negative)) {
+        Some(Ok(value)) => Ok(value),
+        _ => Err(int_slow_error::<T>(&bytes[start..], negative, start)),
+    }
+}
+
+#[inline(always)]
+fn int_error(kind: IntErrorKind, position: usize) -> ParseIntError {
+    ParseIntError { kind, position }
+}
+
+/// Figures out why parsing `digits` failed, this matches the order `core` checks in, so an
+/// overflow is reported before any invalid digits that come after it.
+#[cold]
+fn int_slow_error<T: Integer>(digits: &[u8], negative: bool, offset: usize) -> ParseIntError {
+    let mut magnitude = 0u128;
+    for (i, &b) in digits.iter().enumerate() {
+        if !b.is_ascii_digit() {
+            return int_error(IntErrorKind::InvalidDigit, offset + i);
+        }
+        let next = magnitude
+            .checked_mul(10)
+            .and_then(|magnitude| magnitude.checked_add((b - b'0') as u128));
+        let result = next.map(|magnitude| T::from_parts(magnitude, negative));
+        match result {
+            Some(Ok(_)) => magnitude = next.unwrap_or_default(),
+            Some(Err(kind)) => return int_error(kind, offset + i),
+            None if negative => return int_error(IntErrorKind::NegOverflow, offset + i),
+            None => return int_error(IntErrorKind::PosOverflow, offset + i),
+        }
+    }
+    unreachable!("parsing only fails on an invalid digit or an overflow")
+}
+
+/// Parses the ASCII digits in `padded[start..end]`, returning `None` if any of them is invalid,
+/// or the value doesn't fit in a `u128`.
+///
+/// The digits are parsed in chunks of 8 with SWAR, the first chunk takes the leftover digits and
+/// is loaded as a whole word from `padded`, which is why we want the inline buffer.
+#[inline(always)]
+fn parse_digits(padded: &[u8], start: usize, end: usize) -> Option<u128> {
+    match end.checked_sub(start) {
+        Some(len @ 1..=8) => load_digits(padded, start, len).map(u128::from),
+        _ => parse_long_digits(padded, start, end),
+    }
+}
+
+fn parse_long_digits(padded: &[u8], start: usize, end: usize) -> Option<u128> {
+    let len = end.checked_sub(start).filter(|&len| len > 0)?;
+    // the number of digits in the first chunk, 1 to 8
+    let first = (len - 1) % 8 + 1;
+
+    if len <= 19 {
+        // can't overflow a u64
+        let mut value = load_digits(padded, start, first)?;
+        let mut i = start + first;
+        while i < end {
+            value = value * 100_000_000 + load_digits(padded, i, 8)?;
+            i += 8;
+        }
+        Some(value as u128)
+    } else if len <= 38 {
+        // can't overflow a u128
+        let mut value = load_digits(padded, start, first)? as u128;
+        let mut i = start + first;
+        while i < end {
+            value = value * 100_000_000 + load_digits(padded, i, 8)? as u128;
+            i += 8;
+        }
+        Some(value)
+    } else {
+        padded[start..end].iter().try_fold(0u128, |acc, &b| {
+            let digit = b.wrapping_sub(b'0');
+            if digit > 9 {
+                return None;
+            }
+            acc.checked_mul(10)?.checked_add(digit as u128)
+        })
+    }
+}
+
+/// Parses the `count` ASCII digits at `padded[i..]`, where `count` is 1 to 8.
+#[inline(always)]
+fn load_digits(padded: &[u8], i: usize, count: usize) -> Option<u64> {
+    const ZEROS: u64 = u64::from_le_bytes([b'0'; 8]);
+
+    let chunk = match padded.get(i..i + 8) {
+        Some(chunk) => u64::from_le_bytes([
+            chunk[0], chunk[1], chunk[2], chunk[3], chunk[4], chunk[5], chunk[6], chunk[7],
+        ]),
+        None => load_short(&padded[i..]),
+    };
+    // move the digits to the most significant bytes, and fill the rest with leading zeros
+    let chunk = (chunk << (8 * (8 - count))) | ZEROS.checked_shr(8 * count as u32).unwrap_or(0);
+
+    if !is_8_digits(chunk) {
+        return None;
+    }
+    Some(parse_8_digits(chunk))
+}
+
+/// Loads less than 8 bytes, only needed for a short string that isn't inline.
+#[cold]
+fn load_short(bytes: &[u8]) -> u64 {
+    let mut buf = [0; 8];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    u64::from_le_bytes(buf)
+}
+
+/// Returns `true` if all 8 bytes of `chunk` are ASCII digits.
+#[inline(always)]
+fn is_8_digits(chunk: u64) -> bool {
+    // every byte has to be 0x30..=0x39, i.e. the high nibble is 3 and adding 6 doesn't carry
+    let high = chunk & 0xF0F0_F0F0_F0F0_F0F0;
+    let carry = chunk.wrapping_add(0x0606_0606_0606_0606) & 0xF0F0_F0F0_F0F0_F0F0;
+    (high | (carry >> 4)) == 0x3333_3333_3333_3333
+}
+
+/// Parses 8 ASCII digits, loaded as a little endian `u64`.
+#[inline(always)]
+fn parse_8_digits(chunk: u64) -> u64 {
+    // combine neighbouring digits, then pairs of two, then pairs of four
+    let chunk = (chunk & 0x0F0F_0F0F_0F0F_0F0F).wrapping_mul(10 << 8 | 1) >> 8;
+    let chunk = (chunk & 0x00FF_00FF_00FF_00FF).wrapping_mul(100 << 16 | 1) >> 16;
+    (chunk & 0x0000_FFFF_0000_FFFF).wrapping_mul(10_000 << 32 | 1) >> 32
+}
+
+pub(crate) fn parse_float<T: Float>(s: &str) -> Result<T, ParseFloatError> {
+    let bytes = s.as_bytes();
+    if bytes.is_empty() {
+        return Err(float_error(FloatErrorKind::Empty, 0));
+    }
+
+    let (negative, mut i) = match bytes[0] {
+        b'-' => (true, 1),
+        b'+' => (false, 1),
+        _ => (false, 0),
+    };
+    if is_special(&bytes[i..]) {
+        return parse_float_slow(s);
+    }
+
+    // `None` once the mantissa doesn't fit in a u64
+    let mut mantissa = Some(0u64);
+    let mut exponent = 0isize;
+    let mut any_digits = false;
+
+    let start = i;
+    while let Some(&b) = bytes.get(i).filter(|b| b.is_ascii_digit()) {
+        mantissa = push_digit(mantissa, b);
+        i += 1;
+    }
+    any_digits |= i > start;
+
+    if bytes.get(i) == Some(&b'.') {
+        i += 1;
+        let start = i;
+        while let Some(&b) = bytes.get(i).filter(|b| b.is_ascii_digit()) {
+            mantissa = push_digit(mantissa, b);
+            i += 1;
+        }
+        any_digits |= i > start;
+        exponent -= (i - start) as isize;
+    }
+    if !any_digits {
+        return Err(float_error(FloatErrorKind::Invalid, i));
+    }
+
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        i += 1;
+        let exp_negative = match bytes.get(i) {
+            Some(b'-') => {
+                i += 1;
+                true
+            }
+            Some(b'+') => {
+                i += 1;
+                false
+            }
+            _ => false,
+        };
+        let start = i;
+        let mut exp = 0isize;
+        while let Some(&b) = bytes.get(i).filter(|b| b.is_ascii_digit()) {
+            // anything this large is out of range for the fast path anyway
+            exp = (exp * 10 + (b - b'0') as isize).min(1 << 20);
+            i += 1;
+        }
+        if i == start {
+            return Err(float_error(FloatErrorKind::Invalid, i));
+        }
+        exponent += if exp_negative { -exp } else { exp };
+    }
+    if i != bytes.len() {
+        return Err(float_error(FloatErrorKind::Invalid, i));
+    }
+
+    match mantissa.and_then(|mantissa| fast_path::<T>(mantissa, exponent)) {
+        Some(value) if negative => Ok(value.neg()),
+        Some(value) => Ok(value),
+        None => parse_float_slow(s),
+    }
+}
+
+#[inline(always)]
+fn float_error(kind: FloatErrorKind, position: usize) -> ParseFloatError {
+    ParseFloatError { kind, position }
+}
+
+#[inline(always)]
+fn push_digit(mantissa: Option<u64>, digit: u8) -> Option<u64> {
+    mantissa?
+        .checked_mul(10)?
+        .checked_add((digit - b'0') as u64)
+}
+
+/// Returns `true` for the case insensitive `inf`, `infinity` and `nan`.
+fn is_special(s: &[u8]) -> bool {
+    [&b"inf"[..], b"infinity", b"nan"]
+        .iter()
+        .any(|special| s.eq_ignore_ascii_case(special))
+}
+
+/// Computes `mantissa * 10^exponent` if it can be done exactly, i.e. both the mantissa and the
+/// power of 10 convert to the float without rounding, so the only rounding is the single
+/// multiplication or division. This is Clinger's fast path.
+#[inline]
+fn fast_path<T: Float>(mantissa: u64, exponent: isize) -> Option<T> {
+    if mantissa == 0 {
+        return Some(T::from_u64(0));
+    }
+    if mantissa > T::MAX_EXACT_MANTISSA {
+        return None;
+    }
+    let value = T::from_u64(mantissa);
+    let pow = *T::EXACT_POWERS_OF_10.get(exponent.unsigned_abs())?;
+    match exponent < 0 {
+        true => Some(value.div(pow)),
+        false => Some(value.mul(pow)),
+    }
+}
+
+#[cold]
+fn parse_float_slow<T: Float>(s: &str) -> Result<T, ParseFloatError> {
+    // we already validated the syntax, so this can't fail
+    s.parse()
+        .map_err(|_| float_error(FloatErrorKind::Invalid, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use core::num::IntErrorKind;
+
+    use test_strategy::proptest;
+
+    use super::{is_8_digits, parse_8_digits, parse_float, parse_int, FloatErrorKind};
+    use crate::CompactString;
+
+    fn assert_int_matches_std<T>(s: &str)
+    where
+        T: crate::Integer + core::str::FromStr<Err = core::num::ParseIntError>,
+        T: PartialEq + core::fmt::Debug,
+    {
+        // parse from a `CompactString`, so we test with and without the inline buffer
+        let compact = CompactString::new(s);
+        assert_eq!(parse_int::<T>(s, s.as_bytes()), compact.parse_int::<T>());
+        match (compact.parse_int::<T>(), s.parse::<T>()) {
+            (Ok(ours), Ok(std)) => assert_eq!(ours, std, "{s:?}"),
+            (Err(ours), Err(std)) => assert_eq!(ours.kind(), std.kind(), "{s:?}"),
+            (ours, std) => panic!("{s:?}: {ours:?} != {std:?}"),
+        }
+    }
+
+    fn assert_float_matches_std<T>(s: &str)
+    where
+        T: crate::Float + PartialEq + core::fmt::Debug,
+    {
+        match (CompactString::new(s).parse_float::<T>(), s.parse::<T>()) {
+            // compare the bits so we also check NaN and the sign of zero
+            (Ok(ours), Ok(std)) => assert_eq!(format!("{ours:?}"), format!("{std:?}"), "{s:?}"),
+            (Err(_), Err(_)) => {}
+            (ours, std) => panic!("{s:?}: {ours:?} != {std:?}"),
+        }
+    }
+
+    fn check_ints(s: &str) {
+        assert_int_matches_std::<u8>(s);
+        assert_int_matches_std::<i8>(s);
+        assert_int_matches_std::<u32>(s);
+        assert_int_matches_std::<i32>(s);
+        assert_int_matches_std::<u64>(s);
+        assert_int_matches_std::<i64>(s);
+        assert_int_matches_std::<u128>(s);
+        assert_int_matches_std::<i128>(s);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_parse_int(#[strategy("[+-]?[0-9]{0,45}[0-9a+-]?[0-9]{0,3}")] s: String) {
+        check_ints(&s);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_parse_int_roundtrip(value: i128) {
+        check_ints(&format!("{value}"));
+        check_ints(&format!("{}", value as i64));
+        check_ints(&format!("{}", value as u64));
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_parse_float(
+        #[strategy("[+-]?[0-9]{0,22}(\\.[0-9]{0,22})?([eE][+-]?[0-9]{0,4})?")] s: String,
+    ) {
+        assert_float_matches_std::<f64>(&s);
+        assert_float_matches_std::<f32>(&s);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_parse_float_roundtrip(value: f64) {
+        assert_float_matches_std::<f64>(&format!("{value}"));
+        assert_float_matches_std::<f64>(&format!("{value:e}"));
+        assert_float_matches_std::<f32>(&format!("{}", value as f32));
+    }
+
+    fn parse_int_str<T: crate::Integer>(s: &str) -> Result<T, super::ParseIntError> {
+        parse_int(s, s.as_bytes())
+    }
+
+    #[test]
+    fn test_parse_int_errors() {
+        for s in [
+            "", "+", "-", "-0", "+0", "00", "1_000", " 1", "1 ", "++1", "-+1", "0x10", "255",
+            "256", "-128", "-129", "٣",
+        ] {
+            check_ints(s);
+        }
+
+        let err = parse_int_str::<u8>("").unwrap_err();
+        assert_eq!((err.kind(), err.position()), (&IntErrorKind::Empty, 0));
+        let err = parse_int_str::<u8>("-").unwrap_err();
+        assert_eq!(
+            (err.kind(), err.position()),
+            (&IntErrorKind::InvalidDigit, 1)
+        );
+        let err = parse_int_str::<u8>("-1").unwrap_err();
+        assert_eq!(
+            (err.kind(), err.position()),
+            (&IntErrorKind::InvalidDigit, 0)
+        );
+        let err = parse_int_str::<u8>("12x").unwrap_err();
+        assert_eq!(
+            (err.kind(), err.position()),
+            (&IntErrorKind::InvalidDigit, 2)
+        );
+        // the overflow comes before the invalid digit
+        let err = parse_int_str::<u8>("+2560x").unwrap_err();
+        assert_eq!(
+            (err.kind(), err.position()),
+            (&IntErrorKind::PosOverflow, 3)
+        );
+        let err = parse_int_str::<i8>("-129").unwrap_err();
+        assert_eq!(
+            (err.kind(), err.position()),
+            (&IntErrorKind::NegOverflow, 3)
+        );
+
+        // short strings that aren't inline can't be loaded a whole word at a time
+        let mut heap = CompactString::with_capacity(64);
+        heap.push_str("-123");
+        assert!(heap.is_heap_allocated());
+        assert_eq!(heap.parse_int::<i32>(), Ok(-123));
+        heap.push('x');
+        let err = heap.parse_int::<i32>().unwrap_err();
+        assert_eq!(
+            (err.kind(), err.position()),
+            (&IntErrorKind::InvalidDigit, 4)
+        );
+    }
+
+    #[test]
+    fn test_parse_float_errors() {
+        for s in [
+            "",
+            "+",
+            "-",
+            ".",
+            "e5",
+            ".e5",
+            "1.",
+            ".5",
+            "1e",
+            "1e+",
+            "1.5e-3",
+            "1,5",
+            "inf",
+            "-INF",
+            "Infinity",
+            "infinit",
+            "nan",
+            "-NaN",
+            "nanx",
+            " 1",
+            "1 ",
+            "0x1",
+            "1e400",
+            "-0",
+            "9007199254740993",
+            "1e22",
+            "1e23",
+            "123456789012345678901234567890",
+        ] {
+            assert_float_matches_std::<f64>(s);
+            assert_float_matches_std::<f32>(s);
+        }
+
+        let err = parse_float::<f64>("").unwrap_err();
+        assert_eq!((err.kind(), err.position()), (FloatErrorKind::Empty, 0));
+        let err = parse_float::<f64>("-.").unwrap_err();
+        assert_eq!((err.kind(), err.position()), (FloatErrorKind::Invalid, 2));
+        let err = parse_float::<f64>("1.5x").unwrap_err();
+        assert_eq!((err.kind(), err.position()), (FloatErrorKind::Invalid, 3));
+        let err = parse_float::<f64>("1e+").unwrap_err();
+        assert_eq!((err.kind(), err.position()), (FloatErrorKind::Invalid, 3));
+    }
+
+    #[test]
+    fn test_swar() {
+        let load = |s: &[u8; 8]| u64::from_le_bytes(*s);
+        assert!(is_8_digits(load(b"01234567")));
+        assert!(is_8_digits(load(b"99999999")));
+        for invalid in [
+            b"0123456/",
+            b"0123456:",
+            b"a1234567",
+            b"0123 567",
+            b"\xb0\xb1234567",
+        ] {
+            assert!(!is_8_digits(load(invalid)));
+        }
+        assert_eq!(parse_8_digits(load(b"01234567")), 1234567);
+        assert_eq!(parse_8_digits(load(b"99999999")), 99999999);
+        assert_eq!(parse_8_digits(load(b"10000000")), 10000000);
+    }
+}
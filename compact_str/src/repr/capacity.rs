@@ -6,9 +6,23 @@ use crate::repr::LastByte;
 const USIZE_SIZE: usize = core::mem::size_of::<usize>();
 
 /// Mask of bits in [`Capacity`] that encode the value.
+///
+/// On 64-bit archs the top bit of the value is used for [`HEADER_FLAG`] instead.
 const VALID_MASK: usize = {
     let mut bytes = [255; USIZE_SIZE];
     bytes[USIZE_SIZE - 1] = 0;
+    if cfg!(target_pointer_width = "64") {
+        bytes[USIZE_SIZE - 2] = 0x7F;
+    }
+    usize::from_ne_bytes(bytes)
+};
+
+/// Bit that is set in [`Capacity`] on 64-bit archs if the buffer starts with a header, which
+/// holds the custom [`crate::Allocator`] that allocated it.
+#[cfg(target_pointer_width = "64")]
+const HEADER_FLAG: usize = {
+    let mut bytes = [0; USIZE_SIZE];
+    bytes[USIZE_SIZE - 2] = 0x80;
     usize::from_ne_bytes(bytes)
 };
 
@@ -22,11 +36,16 @@ const HEAP_MARKER: usize = {
 /// State that describes the capacity as being stored on the heap.
 ///
 /// All bytes `255`, with the last being [`LastByte::Heap`], using the same amount of bytes
-/// as `usize`. Example (64-bit): `[255, 255, 255, 255, 255, 255, 255, 216]`
+/// as `usize`. Example (32-bit): `[255, 255, 255, 216]`
 #[cfg(not(target_pointer_width = "64"))]
 const CAPACITY_IS_ON_THE_HEAP: Capacity = Capacity(VALID_MASK | HEAP_MARKER);
 
-/// The maximum value we're able to store, e.g. on 64-bit arch this is 2^56 - 2.
+/// The maximum value we're able to store, on 64-bit archs this is 2^55 - 1.
+#[cfg(target_pointer_width = "64")]
+pub(crate) const MAX_VALUE: usize = (1 << 55) - 1;
+
+/// The maximum value we're able to store, e.g. on 32-bit arch this is 2^24 - 2.
+#[cfg(not(target_pointer_width = "64"))]
 pub(crate) const MAX_VALUE: usize = {
     let mut bytes = [255; USIZE_SIZE];
     bytes[USIZE_SIZE - 1] = 0;
@@ -43,13 +62,17 @@ pub(crate) const MAX_VALUE: usize = {
 /// capacity on the heap.
 ///
 /// # Max Values
-/// * __64-bit:__ `(2 ^ (7 * 8)) - 2 = 72_057_594_037_927_934 ~= 64 petabytes`
+/// * __64-bit:__ `(2 ^ (7 * 8 - 1)) - 1 = 36_028_797_018_963_967 ~= 32 petabytes`
 /// * __32-bit:__ `(2 ^ (3 * 8)) - 2 = 16_777_214             ~= 16 megabytes`
 ///
 /// Practically speaking, on a 64-bit architecture we'll never need to store the capacity on the
-/// heap, because with it's impossible to create a string that is 64 petabytes or larger. But for
+/// heap, because with it's impossible to create a string that is 32 petabytes or larger. But for
 /// 32-bit architectures we need to be able to store a capacity larger than 16 megabytes, since a
 /// string larger than 16 megabytes probably isn't that uncommon.
+///
+/// Strings that are allocated with a custom [`crate::Allocator`] store it in a header in front of
+/// the buffer, which also holds the capacity. On 64-bit archs the capacity is still stored inline
+/// as well, with [`HEADER_FLAG`] set, so reading it never has to look at the header.
 #[derive(Copy, Clone, PartialEq, Eq)]
 #[repr(transparent)]
 pub(crate) struct Capacity(usize);
@@ -70,10 +93,11 @@ impl Capacity {
             if #[cfg(target_pointer_width = "64")] {
                 // on 64-bit arches we can always fit the capacity inline
                 debug_assert!(capacity <= MAX_VALUE);
-                // SAFETY: a 64-bit capacity is `< 2^56`, so its `to_le()` form leaves the
-                // discriminant byte free for `HEAP_MARKER`, letting the compiler fold the dead
-                // `Err` arm at callers like `From<String>`. (`to_le()`, not the raw value:
-                // `VALID_MASK` is a byte-layout mask, so the raw check breaks on big-endian.)
+                // SAFETY: a 64-bit capacity is `< 2^55`, so its `to_le()` form leaves the
+                // discriminant byte free for `HEAP_MARKER` and `HEADER_FLAG` unset, letting the
+                // compiler fold the dead `Err` arm at callers like `From<String>`. (`to_le()`, not
+                // the raw value: `VALID_MASK` is a byte-layout mask, so the raw check breaks on
+                // big-endian.)
                 unsafe { super::assume(capacity.to_le() & !VALID_MASK == 0) };
 
                 Capacity(capacity.to_le() | HEAP_MARKER)
@@ -94,6 +118,19 @@ impl Capacity {
         }
     }
 
+    /// Returns a [`Capacity`] for a buffer that starts with a header, see [`Capacity::has_header`].
+    #[inline]
+    pub(crate) const fn with_header(capacity: usize) -> Self {
+        cfg_if::cfg_if! {
+            if #[cfg(target_pointer_width = "64")] {
+                Capacity(Capacity::new(capacity).0 | HEADER_FLAG)
+            } else {
+                let _ = capacity;
+                CAPACITY_IS_ON_THE_HEAP
+            }
+        }
+    }
+
     /// Re-interprets a [`Capacity`] as a `usize`
     ///
     /// # SAFETY:
@@ -115,6 +152,22 @@ impl Capacity {
             }
         }
     }
+
+    /// Returns whether the buffer starts with a header, which holds the capacity and the custom
+    /// [`crate::Allocator`] the buffer was allocated with, if any
+    ///
+    /// On 32-bit archs this is the case whenever the capacity is stored on the heap, on 64-bit
+    /// archs only for buffers allocated by a custom allocator.
+    #[inline(always)]
+    pub(crate) fn has_header(self) -> bool {
+        cfg_if::cfg_if! {
+            if #[cfg(target_pointer_width = "64")] {
+                self.0 & HEADER_FLAG != 0
+            } else {
+                self.is_heap()
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -133,12 +186,18 @@ mod tests {
     #[test]
     fn test_max_value() {
         let available_bytes = (core::mem::size_of::<usize>() - 1) as u32;
+
+        // on 64-bit archs the top bit marks buffers with a header, so we don't need a sentinel
+        #[cfg(target_pointer_width = "64")]
+        let max_value = 2usize.pow(available_bytes * 8 - 1) - 1;
+        #[cfg(target_pointer_width = "32")]
         let max_value = 2usize.pow(available_bytes * 8) - 2;
 
         #[cfg(target_pointer_width = "64")]
-        assert_eq!(max_value, 72057594037927934);
+        assert_eq!(max_value, 36028797018963967);
         #[cfg(target_pointer_width = "32")]
         assert_eq!(max_value, 16777214);
+        assert_eq!(max_value, super::MAX_VALUE);
 
         let cap = Capacity::new(max_value);
         let after = unsafe { cap.as_usize() };
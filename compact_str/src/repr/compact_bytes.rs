@@ -0,0 +1,369 @@
+//! Byte oriented operations on a [`Repr`], used to implement [`crate::CompactBytes`].
+//!
+//! A [`Repr`] that stores a string can inline exactly [`MAX_SIZE`] bytes because the final byte
+//! of a full inline string is always part of a UTF-8 sequence, and thus never collides with one of
+//! our discriminants. Arbitrary bytes have no such guarantee, so when storing bytes we only ever
+//! inline up to [`MAX_INLINE_BYTES`], which leaves the final byte free for the length marker.
+//!
+//! Note: A [`Repr`] that contains arbitrary bytes must never be used with any of the `&str` based
+//! methods, e.g. [`Repr::as_str`], [`Repr::reserve`], or [`Clone::clone`].
+
+use alloc::vec::Vec;
+use core::{mem, ptr};
+
+use super::capacity::Capacity;
+use super::heap::{self, HeapBuffer};
+use super::inline::InlineBuffer;
+use super::static_str::StaticStr;
+use super::{Repr, EMPTY, MAX_SIZE};
+use crate::{ReserveError, UnwrapWithMsg};
+
+/// The maximum number of arbitrary bytes we can store inline.
+pub(crate) const MAX_INLINE_BYTES: usize = MAX_SIZE - 1;
+
+impl Repr {
+    /// Create a [`Repr`] from arbitrary bytes
+    #[inline]
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, ReserveError> {
+        let len = bytes.len();
+
+        if len <= MAX_INLINE_BYTES {
+            Ok(Repr::from_inline(inline_bytes(bytes)))
+        } else {
+            heap_with_capacity(bytes, len)
+        }
+    }
+
+    /// Create a [`Repr`] from a `&'static [u8]`, inlining the bytes if possible
+    #[inline]
+    pub(crate) const fn const_from_bytes(bytes: &'static [u8]) -> Self {
+        if bytes.len() <= MAX_INLINE_BYTES {
+            let inline = InlineBuffer::new_const_bytes(bytes);
+            Repr::from_inline(inline)
+        } else {
+            Repr::from_static(StaticStr::new_bytes(bytes))
+        }
+    }
+
+    /// Create a [`Repr`] that can store at least `capacity` arbitrary bytes
+    #[inline]
+    pub(crate) fn bytes_with_capacity(capacity: usize) -> Result<Self, ReserveError> {
+        if capacity <= MAX_INLINE_BYTES {
+            Ok(EMPTY)
+        } else {
+            HeapBuffer::with_capacity(capacity).map(Repr::from_heap)
+        }
+    }
+
+    /// Create a [`Repr`] from a [`Vec<u8>`], in `O(1)` time if the bytes can't be inlined
+    ///
+    /// Note: If the provided [`Vec<u8>`] is >16 MB and we're on a 32-bit arch, we'll copy the
+    /// bytes.
+    #[inline]
+    pub(crate) fn from_vec(v: Vec<u8>) -> Result<Self, ReserveError> {
+        let og_cap = v.capacity();
+        let cap = Capacity::new(og_cap);
+
+        if cap.is_heap() {
+            // We only hit this case if the provided Vec is > 16MB and we're on a 32-bit arch.
+            Repr::from_bytes(&v)
+        } else if v.len() <= MAX_INLINE_BYTES {
+            Ok(Repr::from_inline(inline_bytes(&v)))
+        } else {
+            let mut v = mem::ManuallyDrop::new(v);
+            let len = v.len();
+            let raw_ptr = v.as_mut_ptr();
+
+            let ptr = ptr::NonNull::new(raw_ptr).expect("vec with capacity has null ptr?");
+            let heap = HeapBuffer { ptr, len, cap };
+
+            Ok(Repr::from_heap(heap))
+        }
+    }
+
+    #[inline]
+    pub(crate) const fn as_static_bytes(&self) -> Option<&'static [u8]> {
+        if self.is_static_str() {
+            // SAFETY: A `Repr` is transmuted from `StaticStr`
+            let s: &StaticStr = unsafe { &*(self as *const Self as *const StaticStr) };
+            Some(s.get_bytes())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of arbitrary bytes we can store without reallocating
+    #[inline]
+    pub(crate) fn bytes_capacity(&self) -> usize {
+        if let Some(s) = self.as_static_bytes() {
+            s.len()
+        } else if self.is_heap_allocated() {
+            self.capacity()
+        } else {
+            MAX_INLINE_BYTES
+        }
+    }
+
+    /// Reserves at least `additional` bytes. If there is already enough capacity to store
+    /// `additional` bytes this is a no-op
+    pub(crate) fn reserve_bytes(&mut self, additional: usize) -> Result<(), ReserveError> {
+        let len = self.len();
+        let needed_capacity = len.checked_add(additional).ok_or(ReserveError(()))?;
+
+        if !self.is_static_str() && needed_capacity <= self.bytes_capacity() {
+            // we already have enough space, no-op
+            Ok(())
+        } else if needed_capacity <= MAX_INLINE_BYTES {
+            // Either we're a `&'static [u8]`, or a heap allocated buffer with a capacity less than
+            // MAX_INLINE_BYTES, which can happen if we were created from a `Vec<u8>`
+            *self = Repr::from_inline(inline_bytes(self.as_slice()));
+            Ok(())
+        } else if self.is_heap_allocated() {
+            // SAFETY: We checked above to see if we're heap allocated
+            let heap_buffer = unsafe { self.as_mut_heap() };
+
+            // To reduce allocations, we amortize our growth
+            let amortized_capacity = heap::amortized_growth(len, additional);
+            // Attempt to grow our capacity, allocating a new HeapBuffer on failure
+            if heap_buffer.realloc(amortized_capacity).is_err() {
+                *self = heap_with_capacity(self.as_slice(), amortized_capacity)?;
+            }
+            Ok(())
+        } else {
+            // We're not heap allocated, but need to be
+            let amortized_capacity = heap::amortized_growth(len, additional);
+            *self = heap_with_capacity(self.as_slice(), amortized_capacity)?;
+            Ok(())
+        }
+    }
+
+    /// Shrinks the capacity of the buffer to at least `min_capacity`, inlining if possible
+    pub(crate) fn shrink_bytes_to(&mut self, min_capacity: usize) {
+        // Note: We can't shrink the inline or static variants
+        if !self.is_heap_allocated() {
+            return;
+        }
+
+        let len = self.len();
+        let new_capacity = len.max(min_capacity);
+
+        if new_capacity <= MAX_INLINE_BYTES {
+            *self = Repr::from_inline(inline_bytes(self.as_slice()));
+            return;
+        }
+
+        // SAFETY: We just checked the discriminant to make sure we're heap allocated
+        let heap = unsafe { self.as_mut_heap() };
+
+        // Return if the buffer cannot be strunk.
+        if new_capacity >= heap.capacity() {
+            return;
+        }
+
+        // Try to shrink in-place.
+        if heap.realloc(new_capacity).is_ok() {
+            return;
+        }
+
+        // Otherwise try to allocate a new, smaller chunk.
+        // We can ignore the error. The buffer keeps its old capacity, but that's okay.
+        if let Ok(new_this) = heap_with_capacity(self.as_slice(), new_capacity) {
+            *self = new_this;
+        }
+    }
+
+    /// Appends `bytes` to the end of our buffer
+    #[inline]
+    pub(crate) fn push_bytes(&mut self, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+
+        let len = self.len();
+        let bytes_len = bytes.len();
+
+        if self.is_static_str() || len + bytes_len > self.bytes_capacity() {
+            self.reserve_bytes(bytes_len).unwrap_with_msg();
+        }
+
+        // SAFETY:
+        // * `reserve_bytes` guarantees at least `len + bytes_len` bytes of capacity.
+        // * `bytes` is valid for reads of `bytes_len` bytes.
+        // * The mutable borrow of `self` guarantees the source and destination do not overlap.
+        unsafe {
+            self.as_mut_bytes_ptr()
+                .add(len)
+                .copy_from_nonoverlapping(bytes.as_ptr(), bytes_len)
+        };
+
+        // SAFETY: We just wrote `bytes_len` additional bytes
+        unsafe { self.set_bytes_len(len + bytes_len) };
+    }
+
+    /// Returns a mutable raw pointer to the start of the underlying buffer, converting a
+    /// `&'static [u8]` into an owned buffer if necessary.
+    ///
+    /// The pointer is valid for writes up to [`Repr::bytes_capacity`].
+    #[inline]
+    pub(crate) fn as_mut_bytes_ptr(&mut self) -> *mut u8 {
+        #[cold]
+        fn own_static_bytes(this: &mut Repr) {
+            if let Some(s) = this.as_static_bytes() {
+                *this = Repr::from_bytes(s).unwrap_with_msg();
+            }
+        }
+
+        if self.is_static_str() {
+            own_static_bytes(self);
+        }
+        // Note: We're not a static variant, so this won't touch our contents
+        self.as_mut_ptr()
+    }
+
+    /// Sets the number of bytes our underlying buffer contains
+    ///
+    /// # Safety
+    /// * `len` bytes in the buffer must be initialized
+    /// * If the underlying buffer is stored inline, `len` must be <= MAX_INLINE_BYTES
+    #[inline]
+    pub(crate) unsafe fn set_bytes_len(&mut self, len: usize) {
+        if let Some(s) = self.as_static_variant_mut() {
+            s.set_bytes_len(len);
+        } else {
+            // SAFETY: Inline buffers never store more than MAX_INLINE_BYTES, so the final byte is
+            // always our length marker, and heap buffers don't care about their contents
+            self.set_len(len);
+        }
+    }
+
+    /// Creates a deep copy of a [`Repr`] that contains arbitrary bytes
+    #[inline]
+    pub(crate) fn clone_bytes(&self) -> Self {
+        if self.is_heap_allocated() {
+            Repr::from_bytes(self.as_slice()).unwrap_with_msg()
+        } else {
+            // SAFETY: We just checked that `self` can be copied because it is inline bytes or a
+            // reference to a `&'static [u8]`.
+            unsafe { core::ptr::read(self) }
+        }
+    }
+}
+
+/// Copy at most [`MAX_INLINE_BYTES`] into an [`InlineBuffer`]
+#[inline(always)]
+fn inline_bytes(bytes: &[u8]) -> InlineBuffer {
+    let len = bytes.len();
+    debug_assert!(len <= MAX_INLINE_BYTES);
+
+    let mut inline = InlineBuffer::empty();
+    inline.0[..len].copy_from_slice(bytes);
+    // SAFETY: `len` is less than MAX_SIZE, so the final byte will contain our length
+    unsafe { inline.set_len(len) };
+    inline
+}
+
+/// Copy `bytes` into a new [`HeapBuffer`] with a capacity of at least `capacity`
+#[cold]
+fn heap_with_capacity(bytes: &[u8], capacity: usize) -> Result<Repr, ReserveError> {
+    let len = bytes.len();
+    debug_assert!(len <= capacity);
+    let mut repr = Repr::from_heap(HeapBuffer::with_capacity(capacity)?);
+
+    // SAFETY: `repr` was just allocated with a capacity of at least `len` bytes, so it does not
+    // overlap with `bytes`
+    unsafe {
+        repr.as_mut_ptr()
+            .copy_from_nonoverlapping(bytes.as_ptr(), len)
+    };
+    // SAFETY: We just wrote `len` bytes into our heap buffer
+    unsafe { repr.set_len(len) };
+
+    Ok(repr)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use test_case::test_case;
+
+    use super::{Repr, MAX_INLINE_BYTES};
+
+    #[test_case(&[]; "empty")]
+    #[test_case(&[0xFF; MAX_INLINE_BYTES]; "max inline")]
+    #[test_case(&[0xFF; MAX_INLINE_BYTES + 1]; "min heap")]
+    #[test_case(&[0xD8; 64]; "heap discriminant")]
+    fn test_from_bytes(bytes: &'static [u8]) {
+        let repr = Repr::from_bytes(bytes).unwrap();
+        assert_eq!(repr.as_slice(), bytes);
+        assert_eq!(repr.len(), bytes.len());
+        assert_eq!(repr.is_heap_allocated(), bytes.len() > MAX_INLINE_BYTES);
+
+        let repr = Repr::const_from_bytes(bytes);
+        assert_eq!(repr.as_slice(), bytes);
+        assert!(!repr.is_heap_allocated());
+        assert_eq!(
+            repr.as_static_bytes().is_some(),
+            bytes.len() > MAX_INLINE_BYTES
+        );
+    }
+
+    #[test]
+    fn test_push_bytes_never_inlines_max_size() {
+        let mut repr = Repr::from_bytes(&[0xFF; MAX_INLINE_BYTES]).unwrap();
+        assert!(!repr.is_heap_allocated());
+
+        // The final byte would be a discriminant if stored inline
+        repr.push_bytes(&[0xD8]);
+        assert!(repr.is_heap_allocated());
+        assert_eq!(repr.len(), MAX_INLINE_BYTES + 1);
+        assert_eq!(repr.as_slice().last(), Some(&0xD8));
+
+        let clone = repr.clone_bytes();
+        assert!(clone.is_heap_allocated());
+        assert_eq!(clone.as_slice(), repr.as_slice());
+    }
+
+    #[test]
+    fn test_static_bytes_truncate_and_push() {
+        static BYTES: [u8; 32] = [0xFE; 32];
+        let mut repr = Repr::const_from_bytes(&BYTES);
+        assert_eq!(repr.bytes_capacity(), 32);
+
+        let len = MAX_INLINE_BYTES + 1;
+        unsafe { repr.set_bytes_len(len) };
+        assert!(repr.as_static_bytes().is_some());
+        assert_eq!(repr.len(), len);
+
+        repr.push_bytes(&[1, 2, 3]);
+        assert!(repr.as_static_bytes().is_none());
+        assert_eq!(&repr.as_slice()[len..], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_vec_roundtrip() {
+        let v: Vec<u8> = (0..=255).collect();
+        let ptr = v.as_ptr();
+        let repr = Repr::from_vec(v).unwrap();
+        assert!(repr.is_heap_allocated());
+        assert_eq!(repr.as_slice().as_ptr(), ptr);
+
+        let v = repr.into_vec();
+        assert_eq!(v.as_ptr(), ptr);
+        assert_eq!(v, (0..=255).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_shrink_bytes_to() {
+        let mut repr = Repr::bytes_with_capacity(100).unwrap();
+        repr.push_bytes(&[0xFF; MAX_INLINE_BYTES + 1]);
+        repr.shrink_bytes_to(0);
+        assert!(repr.is_heap_allocated());
+        assert!(repr.bytes_capacity() < 100);
+
+        unsafe { repr.set_bytes_len(MAX_INLINE_BYTES) };
+        repr.shrink_bytes_to(0);
+        assert!(!repr.is_heap_allocated());
+        assert_eq!(repr.as_slice(), &[0xFF; MAX_INLINE_BYTES]);
+    }
+}
@@ -0,0 +1,367 @@
+//! Formatting floats into a [`Repr`] according to a [`FloatFormat`].
+//!
+//! [`zmij`] gives us the shortest representation of a float that roundtrips, which we re-arrange
+//! into the requested notation. With a precision set this is only correct if rounding the exact
+//! value of the float gives the same digits as padding the shortest representation with zeros,
+//! otherwise we fall back to [`core::fmt`].
+
+use core::fmt::Write;
+
+use super::Repr;
+use crate::format::sealed::Float;
+use crate::{CompactString, Exponent, FloatFormat, NanInfStyle, UnwrapWithMsg};
+
+impl Repr {
+    /// Formats `value` according to `format`.
+    pub(crate) fn from_float_with<F: Float>(value: F, format: &FloatFormat) -> Repr {
+        if value.is_nan() {
+            return Repr::const_new(match format.nan_inf_style {
+                NanInfStyle::Rust | NanInfStyle::JavaScript => "NaN",
+                NanInfStyle::Lower => "nan",
+            });
+        }
+        if value.is_infinite() {
+            let inf = match format.nan_inf_style {
+                NanInfStyle::Rust | NanInfStyle::Lower => "-inf",
+                NanInfStyle::JavaScript => "-Infinity",
+            };
+            let inf = if value.is_sign_negative() {
+                inf
+            } else {
+                &inf[1..]
+            };
+            return Repr::const_new(inf);
+        }
+
+        let mut buf = zmij::Buffer::new();
+        let shortest = buf.format_finite(value);
+
+        let (exponent, precision) = match (format.exponent, format.precision) {
+            (Exponent::Auto, None) => return Repr::new_panic(shortest),
+            (Exponent::Auto, precision) => (Exponent::Never, precision),
+            (exponent, precision) => (exponent, precision),
+        };
+
+        // Subnormals have less precision than `F::DIGITS`, so we always format those exactly.
+        let max_digits = if value.is_subnormal() { 0 } else { F::DIGITS };
+
+        let decimal = Decimal::parse(shortest);
+        let repr = match exponent {
+            Exponent::Never => decimal.write_fixed(precision, max_digits),
+            Exponent::Lower => decimal.write_scientific(precision, max_digits, "e"),
+            Exponent::Upper => decimal.write_scientific(precision, max_digits, "E"),
+            Exponent::Auto => unreachable!(),
+        };
+        if let Some(repr) = repr {
+            return repr;
+        }
+
+        // The shortest representation doesn't have enough precision, format the exact value.
+        let mut compact = CompactString::const_new("");
+        let precision = precision.unwrap_or_default();
+        let result = match exponent {
+            Exponent::Lower => write!(compact, "{value:.precision$e}"),
+            Exponent::Upper => write!(compact, "{value:.precision$E}"),
+            _ => write!(compact, "{value:.precision$}"),
+        };
+        debug_assert!(result.is_ok(), "formatting a float never fails");
+        compact.0
+    }
+}
+
+/// A finite float in the form `d1.d2d3... * 10^exponent`.
+struct Decimal {
+    negative: bool,
+    /// The significant digits, without leading or trailing zeros, as ASCII. The first `len`
+    /// bytes are used, which is 0 if the value is zero.
+    buf: [u8; 32],
+    len: usize,
+    exponent: isize,
+}
+
+impl Decimal {
+    /// Parses the output of [`zmij`], e.g. `-123.45`, `0.0012` or `1.5e+16`.
+    fn parse(s: &str) -> Decimal {
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s),
+        };
+        let (mantissa, exp) = s.split_once('e').unwrap_or((s, ""));
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+        let (exp_negative, exp) = match exp.as_bytes() {
+            [b'-', rest @ ..] => (true, rest),
+            [b'+', rest @ ..] => (false, rest),
+            exp => (false, exp),
+        };
+        let mut exponent = exp.iter().fold(0, |acc, b| acc * 10 + (b - b'0') as isize);
+        if exp_negative {
+            exponent = -exponent;
+        }
+
+        let mut buf = [0; 32];
+        let mut len = 0;
+        // the index of the first significant digit in `int` followed by `frac`
+        let mut first = None;
+        for (i, &b) in int.as_bytes().iter().chain(frac.as_bytes()).enumerate() {
+            if first.is_none() && b == b'0' {
+                continue;
+            }
+            first.get_or_insert(i);
+            buf[len] = b;
+            len += 1;
+        }
+        while len > 0 && buf[len - 1] == b'0' {
+            len -= 1;
+        }
+
+        match first {
+            Some(first) => exponent += int.len() as isize - 1 - first as isize,
+            None => exponent = 0,
+        }
+
+        Decimal {
+            negative,
+            buf,
+            len,
+            exponent,
+        }
+    }
+
+    fn digits(&self) -> &str {
+        // SAFETY: We only ever copy ASCII digits into `buf`
+        unsafe { core::str::from_utf8_unchecked(&self.buf[..self.len]) }
+    }
+
+    /// Formats the value without an exponent, like `{}`, or `{:.precision$}`. Returns `None` if
+    /// the shortest representation doesn't have enough precision.
+    fn write_fixed(&self, precision: Option<usize>, max_digits: usize) -> Option<Repr> {
+        let digits = self.digits();
+
+        // `int` followed by `int_zeros` zeros, a `.`, `frac_zeros` zeros, then `frac`
+        let (int, int_zeros, frac_zeros, frac) = if digits.is_empty() {
+            ("0", 0, 0, "")
+        } else if self.exponent >= 0 {
+            let int_len = self.exponent as usize + 1;
+            if digits.len() <= int_len {
+                (digits, int_len - digits.len(), 0, "")
+            } else {
+                (&digits[..int_len], 0, 0, &digits[int_len..])
+            }
+        } else {
+            ("0", 0, (-self.exponent - 1) as usize, digits)
+        };
+        let frac_len = frac_zeros + frac.len();
+
+        if let Some(precision) = precision {
+            if frac_len > precision {
+                return None;
+            }
+            let significant = precision.checked_add_signed(self.exponent + 1);
+            if !digits.is_empty() && !matches!(significant, Some(n) if n <= max_digits) {
+                return None;
+            }
+        }
+        let padded_frac_len = precision.unwrap_or(frac_len);
+
+        let len =
+            (self.negative as usize + int.len() + int_zeros + 1).saturating_add(padded_frac_len);
+        let mut repr = Repr::with_capacity(len).unwrap_with_msg();
+        if self.negative {
+            repr.push_str("-");
+        }
+        repr.push_str(int);
+        push_zeros(&mut repr, int_zeros);
+        if padded_frac_len > 0 {
+            repr.push_str(".");
+            push_zeros(&mut repr, frac_zeros);
+            repr.push_str(frac);
+            push_zeros(&mut repr, padded_frac_len - frac_len);
+        }
+        Some(repr)
+    }
+
+    /// Formats the value in scientific notation, like `{:e}`, or `{:.precision$e}`. Returns
+    /// `None` if the shortest representation doesn't have enough precision.
+    fn write_scientific(
+        &self,
+        precision: Option<usize>,
+        max_digits: usize,
+        e: &str,
+    ) -> Option<Repr> {
+        let digits = match self.digits() {
+            "" => "0",
+            digits => digits,
+        };
+        let (first, rest) = digits.split_at(1);
+
+        if let Some(precision) = precision {
+            if rest.len() > precision {
+                return None;
+            }
+            if self.len != 0 && precision >= max_digits {
+                return None;
+            }
+        }
+        let padded_frac_len = precision.unwrap_or(rest.len());
+
+        let mut exp_buf = itoa::Buffer::new();
+        let exponent = exp_buf.format(self.exponent);
+
+        let len = (self.negative as usize + 3 + exponent.len()).saturating_add(padded_frac_len);
+        let mut repr = Repr::with_capacity(len).unwrap_with_msg();
+        if self.negative {
+            repr.push_str("-");
+        }
+        repr.push_str(first);
+        if padded_frac_len > 0 {
+            repr.push_str(".");
+            repr.push_str(rest);
+            push_zeros(&mut repr, padded_frac_len - rest.len());
+        }
+        repr.push_str(e);
+        repr.push_str(exponent);
+        Some(repr)
+    }
+}
+
+fn push_zeros(repr: &mut Repr, mut count: usize) {
+    const ZEROS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+    while count > 0 {
+        let n = count.min(ZEROS.len());
+        repr.push_str(&ZEROS[..n]);
+        count -= n;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use test_strategy::proptest;
+
+    use crate::repr::Repr;
+    use crate::{Exponent, FloatFormat, NanInfStyle};
+
+    fn assert_matches_std<F>(value: F, precision: Option<usize>)
+    where
+        F: crate::Float + PartialEq + core::fmt::Debug,
+    {
+        let format = |exponent| {
+            let mut format = FloatFormat::new().exponent(exponent);
+            if let Some(precision) = precision {
+                format = format.precision(precision);
+            }
+            Repr::from_float_with(value, &format)
+        };
+
+        let (fixed, lower, upper): (String, String, String) = match precision {
+            Some(p) => (
+                format!("{value:.p$}"),
+                format!("{value:.p$e}"),
+                format!("{value:.p$E}"),
+            ),
+            None => (
+                format!("{value}"),
+                format!("{value:e}"),
+                format!("{value:E}"),
+            ),
+        };
+        let check = |ours: Repr, std: String| {
+            if precision.is_none() && ours.as_str() != std {
+                // without a precision we use the shortest digits from `zmij`, like
+                // `ToCompactString`, and when two are equally close it can pick a different last
+                // digit than `core::fmt`
+                assert_eq!(ours.len(), std.len(), "{} != {std}", ours.as_str());
+                assert_eq!(ours.as_str().parse::<F>().ok(), Some(value));
+            } else {
+                assert_eq!(ours.as_str(), std);
+            }
+        };
+        check(format(Exponent::Never), fixed);
+        check(format(Exponent::Lower), lower);
+        check(format(Exponent::Upper), upper);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_f64_matches_std(value: f64, #[strategy(0..20usize)] precision: usize) {
+        assert_matches_std(value, None);
+        assert_matches_std(value, Some(precision));
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_f32_matches_std(value: f32, #[strategy(0..12usize)] precision: usize) {
+        assert_matches_std(value, None);
+        assert_matches_std(value, Some(precision));
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_short_decimals(
+        #[strategy(-100_000i64..100_000)] n: i64,
+        #[strategy(0..6i32)] scale: i32,
+        #[strategy(0..8usize)] precision: usize,
+    ) {
+        // values like `12.5` or `0.35` that are likely to hit rounding edge cases
+        let value = n as f64 / 10f64.powi(scale);
+        assert_matches_std(value, None);
+        assert_matches_std(value, Some(precision));
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        let values = [
+            0.0,
+            -0.0,
+            0.125,
+            0.35,
+            1.0,
+            1e16,
+            1e23,
+            1e-7,
+            123456.789,
+            f64::MIN_POSITIVE,
+            f64::from_bits(1),
+            -f64::from_bits(1),
+            f64::MAX,
+            f64::MIN,
+            f64::EPSILON,
+        ];
+        for value in values {
+            assert_matches_std(value, None);
+            for precision in [0, 1, 2, 3, 15, 16, 17, 30] {
+                assert_matches_std(value, Some(precision));
+            }
+        }
+    }
+
+    #[test]
+    fn test_nan_inf_style() {
+        let format = |value: f64, style| {
+            Repr::from_float_with(value, &FloatFormat::new().nan_inf_style(style))
+        };
+        let styles = [
+            (NanInfStyle::Rust, ["NaN", "inf", "-inf"]),
+            (NanInfStyle::Lower, ["nan", "inf", "-inf"]),
+            (NanInfStyle::JavaScript, ["NaN", "Infinity", "-Infinity"]),
+        ];
+        for (style, [nan, inf, neg_inf]) in styles {
+            assert_eq!(format(f64::NAN, style).as_str(), nan);
+            assert_eq!(format(f64::INFINITY, style).as_str(), inf);
+            assert_eq!(format(f64::NEG_INFINITY, style).as_str(), neg_inf);
+        }
+    }
+
+    #[test]
+    fn test_auto() {
+        let auto = FloatFormat::new();
+        assert_eq!(Repr::from_float_with(1e16, &auto).as_str(), "1e+16");
+        assert_eq!(Repr::from_float_with(1.5, &auto).as_str(), "1.5");
+        assert_eq!(
+            Repr::from_float_with(1e16, &auto.precision(1)).as_str(),
+            "10000000000000000.0"
+        );
+    }
+}
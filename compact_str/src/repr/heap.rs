@@ -1,8 +1,9 @@
 use core::alloc::Layout;
 use core::{cmp, mem, ptr};
 
-use super::capacity::Capacity;
+use super::capacity::{self, Capacity};
 use super::{Repr, MAX_SIZE};
+use crate::allocator::AllocatorRef;
 use crate::{ReserveError, UnwrapWithMsg};
 
 /// The minimum size we'll allocate on the heap is one usize larger than our max inline size
@@ -11,6 +12,19 @@ const MIN_HEAP_SIZE: usize = MAX_SIZE + mem::size_of::<usize>();
 const UNKNOWN: usize = 0;
 pub(crate) type StrBuffer = [u8; UNKNOWN];
 
+/// Returns an error if `capacity` is larger than a [`Capacity`] can hold.
+///
+/// On 32-bit archs a larger capacity is stored on the heap instead, but on 64-bit archs
+/// [`Capacity::new`] assumes it fits, so we have to check before we allocate.
+#[inline(always)]
+fn check_capacity(capacity: usize) -> Result<(), ReserveError> {
+    if cfg!(target_pointer_width = "64") && capacity > capacity::MAX_VALUE {
+        Err(ReserveError(()))
+    } else {
+        Ok(())
+    }
+}
+
 /// [`HeapBuffer`] grows at an amortized rates of 1.5x
 ///
 /// Note: this is different than [`std::string::String`], which grows at a rate of 2x. It's debated
@@ -113,6 +127,39 @@ impl HeapBuffer {
         Ok((ptr, cap))
     }
 
+    /// Create a [`HeapBuffer`] with the provided text, allocated by `allocator`.
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    pub(crate) fn new_in(text: &str, allocator: AllocatorRef) -> Result<Self, ReserveError> {
+        let len = text.len();
+        let mut heap = Self::with_capacity_in(len, allocator)?;
+
+        // SAFETY: `src` and `dest` are both valid for `len` bytes, and don't overlap because we
+        // just allocated `dest`
+        unsafe { super::copy_medium(text.as_ptr(), heap.ptr.as_ptr(), len) };
+        // SAFETY: We just copied `text`, which is valid UTF-8
+        unsafe { heap.set_len(len) };
+
+        Ok(heap)
+    }
+
+    /// Create an empty [`HeapBuffer`] with a specific capacity, allocated by `allocator`.
+    #[inline]
+    pub(crate) fn with_capacity_in(
+        capacity: usize,
+        allocator: AllocatorRef,
+    ) -> Result<Self, ReserveError> {
+        let capacity = capacity.max(MIN_HEAP_SIZE);
+        check_capacity(capacity)?;
+        // SAFETY: `capacity` is at least `MIN_HEAP_SIZE`, so it's not zero
+        let ptr = unsafe { heap_capacity::alloc(capacity, Some(allocator))? };
+        Ok(HeapBuffer {
+            ptr,
+            len: 0,
+            cap: Capacity::with_header(capacity),
+        })
+    }
+
     /// Create a [`HeapBuffer`] with `text` that has _at least_ `additional` bytes of capacity
     ///
     /// To prevent frequent re-allocations, this method will create a [`HeapBuffer`] with a capacity
@@ -159,14 +206,8 @@ impl HeapBuffer {
     pub(crate) fn capacity(&self) -> usize {
         #[cold]
         fn read_capacity_from_heap(this: &HeapBuffer) -> usize {
-            // re-adjust the pointer to include the capacity that's on the heap
-            let adj_ptr: *const u8 = this.ptr.as_ptr().wrapping_sub(mem::size_of::<usize>());
-            let mut buf = [0u8; mem::size_of::<usize>()];
-            // SAFETY: `src` and `dst` don't overlap, and are valid for usize number of bytes
-            unsafe {
-                ptr::copy_nonoverlapping(adj_ptr, buf.as_mut_ptr(), mem::size_of::<usize>());
-            }
-            usize::from_ne_bytes(buf)
+            // SAFETY: We only call this method if our capacity is on the heap
+            unsafe { heap_capacity::header(this.ptr).capacity }
         }
 
         if self.cap.is_heap() {
@@ -177,6 +218,17 @@ impl HeapBuffer {
         }
     }
 
+    /// Returns the custom [`crate::Allocator`] this [`HeapBuffer`] was allocated with, if any
+    #[inline]
+    pub(crate) fn allocator(&self) -> Option<&AllocatorRef> {
+        if self.cap.has_header() {
+            // SAFETY: We just checked that our buffer starts with a header
+            unsafe { heap_capacity::header(self.ptr).allocator.as_ref() }
+        } else {
+            None
+        }
+    }
+
     /// Try to grow the [`HeapBuffer`] by reallocating, returning an error if we fail
     pub(crate) fn realloc(&mut self, new_capacity: usize) -> Result<usize, ()> {
         // We can't reallocate to a size less than our length, or else we'd clip the string
@@ -191,11 +243,18 @@ impl HeapBuffer {
 
         // Always allocate at least MIN_HEAP_SIZE
         let new_capacity = cmp::max(new_capacity, MIN_HEAP_SIZE);
+        check_capacity(new_capacity).map_err(|_| ())?;
         // N.B. must be computed _after_ the MIN_HEAP_SIZE clamp so the stored capacity matches the
         // layout we actually (re)allocate with, otherwise dealloc/realloc get a mismatched layout.
-        let new_cap = Capacity::new(new_capacity);
+        //
+        // A custom allocator is stored in the header, so the buffer always keeps it.
+        let new_cap = if self.allocator().is_some() {
+            Capacity::with_header(new_capacity)
+        } else {
+            Capacity::new(new_capacity)
+        };
 
-        let (new_cap, new_ptr) = match (self.cap.is_heap(), new_cap.is_heap()) {
+        let (new_cap, new_ptr) = match (self.cap.has_header(), new_cap.has_header()) {
             // both current and new capacity can be stored inline
             (false, false) => {
                 // SAFETY: checked above that our capacity is valid
@@ -228,58 +287,15 @@ impl HeapBuffer {
                     None => return Err(()),
                 }
             }
-            // both current and new capacity need to be stored on the heap
+            // both the current and new buffer need a header
             (true, true) => {
-                let cur_layout = heap_capacity::layout(self.capacity());
-                let new_layout = heap_capacity::layout(new_capacity);
-                let new_size = new_layout.size();
-
-                // alloc::realloc requires that size > 0
-                debug_assert!(new_size > 0);
-
-                // It's possible `new_size` could overflow since heap_capacity::layout requires a
-                // few additional bytes
-                if new_size < new_capacity {
-                    return Err(());
-                }
-
-                // move our pointer back one WORD since our capacity is behind it
-                let raw_ptr = self.ptr.as_ptr();
-                let adj_ptr = raw_ptr.wrapping_sub(mem::size_of::<usize>());
-
-                // SAFETY:
-                // * We're using the same allocator that we used for `ptr`
-                // * The layout is the same because we checked that the capacity is on the heap
-                // * `new_size` will be > 0, we return early if the requested capacity is 0
-                // * Checked above if `new_size` overflowed when rounding to alignment
-                let cap_ptr = unsafe { alloc::alloc::realloc(adj_ptr, cur_layout, new_size) };
-                // Check if reallocation succeeded
-                if cap_ptr.is_null() {
-                    return Err(());
-                }
-
-                // Our allocation succeeded! Write the new capacity
-                //
-                // SAFETY:
-                // * `src` and `dst` are both valid for reads of `usize` number of bytes
-                // * `src` and `dst` don't overlap because we created `src`
-                unsafe {
-                    ptr::copy_nonoverlapping(
-                        new_capacity.to_ne_bytes().as_ptr(),
-                        cap_ptr,
-                        mem::size_of::<usize>(),
-                    )
-                };
-
-                // Finally, adjust our pointer backup so it points at the string content
-                let str_ptr = cap_ptr.wrapping_add(mem::size_of::<usize>());
-                // SAFETY: We checked above to make sure the pointer was non-null
-                let ptr = unsafe { ptr::NonNull::new_unchecked(str_ptr) };
-
+                // SAFETY: We checked above that our buffer starts with a header, and
+                // `new_capacity` is at least `MIN_HEAP_SIZE`
+                let ptr = unsafe { heap_capacity::realloc(self.ptr, new_capacity)? };
                 (new_cap, ptr)
             }
-            // capacity is currently inline or on the heap, but needs to move, can't realloc because
-            // we'd need to change the layout!
+            // the buffer needs to gain or lose its header, can't realloc because we'd need to
+            // change the layout!
             (false, true) | (true, false) => return Err(()),
         };
 
@@ -306,10 +322,15 @@ impl HeapBuffer {
     }
 }
 
-impl Clone for HeapBuffer {
-    fn clone(&self) -> Self {
-        // Create a new HeapBuffer with the same capacity as the original
-        let mut new = Self::with_capacity(self.capacity()).unwrap_with_msg();
+impl HeapBuffer {
+    /// Fallible version of [`Clone::clone()`], the clone has the same capacity and allocator
+    pub(crate) fn try_clone(&self) -> Result<Self, ReserveError> {
+        let mut new = match self.allocator() {
+            Some(allocator) => {
+                Self::with_capacity_in(self.capacity(), AllocatorRef::clone(allocator))
+            }
+            None => Self::with_capacity(self.capacity()),
+        }?;
 
         // SAFETY:
         // * `src` and `dst` don't overlap because we just created `dst`
@@ -319,7 +340,13 @@ impl Clone for HeapBuffer {
         // * We copied the text from self, which is valid UTF-8
         unsafe { new.set_len(self.len) };
 
-        new
+        Ok(new)
+    }
+}
+
+impl Clone for HeapBuffer {
+    fn clone(&self) -> Self {
+        self.try_clone().unwrap_with_msg()
     }
 }
 
@@ -338,6 +365,7 @@ impl Drop for HeapBuffer {
 pub(crate) fn allocate_ptr(capacity: usize) -> Result<(Capacity, ptr::NonNull<u8>), ReserveError> {
     // We allocate at least MIN_HEAP_SIZE bytes because we need to allocate at least one byte
     let capacity = capacity.max(MIN_HEAP_SIZE);
+    check_capacity(capacity)?;
     let cap = Capacity::new(capacity);
 
     // HeapBuffer doesn't support 0 sized allocations, we should always allocate at least
@@ -346,21 +374,8 @@ pub(crate) fn allocate_ptr(capacity: usize) -> Result<(Capacity, ptr::NonNull<u8
 
     #[cold]
     fn allocate_with_capacity_on_heap(capacity: usize) -> Result<ptr::NonNull<u8>, ReserveError> {
-        // write our capacity onto the heap
         // SAFETY: we know that the capacity is not zero
-        let ptr = unsafe { heap_capacity::alloc(capacity)? };
-        // SAFETY:
-        // * `src` and `dst` don't overlap and are both valid for `usize` bytes
-        unsafe {
-            ptr::copy_nonoverlapping(
-                capacity.to_ne_bytes().as_ptr(),
-                ptr.as_ptr(),
-                mem::size_of::<usize>(),
-            )
-        };
-        let raw_ptr = ptr.as_ptr().wrapping_add(core::mem::size_of::<usize>());
-        // SAFETY: We know `raw_ptr` is non-null because we just created it
-        Ok(unsafe { ptr::NonNull::new_unchecked(raw_ptr) })
+        unsafe { heap_capacity::alloc(capacity, None) }
     }
 
     let ptr = if cap.is_heap() {
@@ -372,31 +387,19 @@ pub(crate) fn allocate_ptr(capacity: usize) -> Result<(Capacity, ptr::NonNull<u8
     Ok((cap, ptr?))
 }
 
-/// Deallocates a buffer on the heap, handling when the capacity is also stored on the heap
+/// Deallocates a buffer on the heap, handling when it starts with a header
 #[inline]
 pub(crate) fn deallocate_ptr(ptr: ptr::NonNull<u8>, cap: Capacity) {
     #[cold]
-    fn deallocate_with_capacity_on_heap(ptr: ptr::NonNull<u8>) {
-        // re-adjust the pointer to include the capacity that's on the heap
-        let adj_ptr = ptr.as_ptr().wrapping_sub(mem::size_of::<usize>());
-        // read the capacity from the heap so we know how much to deallocate
-        let mut buf = [0u8; mem::size_of::<usize>()];
-        // SAFETY: `src` and `dst` don't overlap, and are valid for usize number of bytes
-        unsafe {
-            ptr::copy_nonoverlapping(adj_ptr, buf.as_mut_ptr(), mem::size_of::<usize>());
-        }
-        let capacity = usize::from_ne_bytes(buf);
-        // SAFETY: We know the pointer is not null since we got it as a NonNull
-        let ptr = unsafe { ptr::NonNull::new_unchecked(adj_ptr) };
-        // SAFETY: We checked above that our capacity is on the heap, and we readjusted the
-        // pointer to reference the capacity
-        unsafe { heap_capacity::dealloc(ptr, capacity) }
+    fn deallocate_with_header(ptr: ptr::NonNull<u8>) {
+        // SAFETY: We checked below that our buffer starts with a header
+        unsafe { heap_capacity::dealloc(ptr) }
     }
 
-    if cap.is_heap() {
-        deallocate_with_capacity_on_heap(ptr);
+    if cap.has_header() {
+        deallocate_with_header(ptr);
     } else {
-        // SAFETY: Our capacity is always inline on 64-bit archs
+        // SAFETY: Without a header our capacity is always stored inline
         unsafe { inline_capacity::dealloc(ptr, cap.as_usize()) }
     }
 }
@@ -416,30 +419,129 @@ pub(crate) unsafe fn do_alloc(layout: Layout) -> Result<ptr::NonNull<u8>, Reserv
     ptr::NonNull::new(raw_ptr).ok_or(ReserveError(()))
 }
 
-mod heap_capacity {
-    use core::{alloc, ptr};
+pub(super) mod heap_capacity {
+    use core::mem::ManuallyDrop;
+    use core::{alloc, mem, ptr};
 
     use super::{do_alloc, StrBuffer};
+    use crate::allocator::AllocatorRef;
     use crate::ReserveError;
 
-    /// SAFETY: `capacity` must not be zero
-    pub(crate) unsafe fn alloc(capacity: usize) -> Result<ptr::NonNull<u8>, ReserveError> {
-        do_alloc(layout(capacity))
+    /// Stored in front of the string when the capacity of a `HeapBuffer` is on the heap, or it was
+    /// allocated by a custom allocator, i.e. we have `ptr -> [header<Header> ; string<bytes>]`
+    #[repr(C)]
+    pub(crate) struct Header {
+        /// The allocator the buffer was allocated with, `None` for the global allocator
+        pub(crate) allocator: Option<AllocatorRef>,
+        pub(crate) capacity: usize,
     }
 
-    /// Deallocates a pointer which references a `HeapBuffer` whose capacity is on the heap
+    const HEADER_SIZE: usize = mem::size_of::<Header>();
+
+    /// Allocates a buffer with a [`Header`], returning a pointer to the start of the string
+    ///
+    /// # SAFETY:
+    /// * `capacity` must not be zero
+    pub(crate) unsafe fn alloc(
+        capacity: usize,
+        allocator: Option<AllocatorRef>,
+    ) -> Result<ptr::NonNull<u8>, ReserveError> {
+        let layout = layout(capacity);
+        let header_ptr = match &allocator {
+            Some(allocator) => allocator.allocate(layout)?,
+            None => do_alloc(layout)?,
+        };
+
+        header_ptr.as_ptr().cast::<Header>().write(Header {
+            allocator,
+            capacity,
+        });
+        Ok(ptr::NonNull::new_unchecked(
+            header_ptr.as_ptr().add(HEADER_SIZE),
+        ))
+    }
+
+    /// Returns the [`Header`] of a `HeapBuffer` that starts with one
     ///
     /// # Safety
-    /// * `ptr` must point to the start of a `HeapBuffer` whose capacity is on the heap. i.e. we
-    ///   must have `ptr -> [cap<usize> ; string<bytes>]`
-    pub(crate) unsafe fn dealloc(ptr: ptr::NonNull<u8>, capacity: usize) {
+    /// * `ptr` must point to the string of a `HeapBuffer` that starts with a header
+    #[inline]
+    pub(crate) unsafe fn header<'a>(ptr: ptr::NonNull<u8>) -> &'a Header {
+        &*ptr.as_ptr().sub(HEADER_SIZE).cast::<Header>()
+    }
+
+    /// Deallocates a pointer which references a `HeapBuffer` that starts with a header
+    ///
+    /// # Safety
+    /// * `ptr` must point to the string of a `HeapBuffer` that starts with a header. i.e. we
+    ///   must have `ptr - size_of::<Header>() -> [header<Header> ; string<bytes>]`
+    pub(crate) unsafe fn dealloc(ptr: ptr::NonNull<u8>) {
+        let header_ptr = ptr.as_ptr().sub(HEADER_SIZE);
+        // Move the header out of the buffer, so the allocator outlives the memory it frees
+        let Header {
+            allocator,
+            capacity,
+        } = ptr::read(header_ptr.cast::<Header>());
         let layout = layout(capacity);
-        ::alloc::alloc::dealloc(ptr.as_ptr(), layout);
+
+        match &allocator {
+            Some(allocator) => {
+                allocator.deallocate(ptr::NonNull::new_unchecked(header_ptr), layout)
+            }
+            None => ::alloc::alloc::dealloc(header_ptr, layout),
+        }
+    }
+
+    /// Reallocates a `HeapBuffer` that starts with a header, using the same allocator it was
+    /// allocated with. Returns a pointer to the start of the string
+    ///
+    /// # Safety
+    /// * `ptr` must point to the string of a `HeapBuffer` that starts with a header
+    /// * `new_capacity` must not be zero
+    pub(crate) unsafe fn realloc(
+        ptr: ptr::NonNull<u8>,
+        new_capacity: usize,
+    ) -> Result<ptr::NonNull<u8>, ()> {
+        let header_ptr = ptr::NonNull::new_unchecked(ptr.as_ptr().sub(HEADER_SIZE));
+        let capacity = header(ptr).capacity;
+        // The allocator can free the header while it moves the buffer, so we use a copy of the
+        // handle. The header keeps owning it, since it gets moved along with the buffer.
+        let allocator = ManuallyDrop::new(ptr::read(&header(ptr).allocator));
+
+        let cur_layout = layout(capacity);
+        let new_layout = layout(new_capacity);
+
+        let new_header_ptr = match &*allocator {
+            Some(allocator) if new_layout.size() >= cur_layout.size() => allocator
+                .grow(header_ptr, cur_layout, new_layout)
+                .map_err(|_| ())?,
+            Some(allocator) => allocator
+                .shrink(header_ptr, cur_layout, new_layout)
+                .map_err(|_| ())?,
+            None => {
+                // SAFETY:
+                // * We're using the same allocator that we used for `ptr`
+                // * The layout is the same because we read it from the header
+                // * `new_layout.size()` is > 0, since it includes the header
+                let raw =
+                    ::alloc::alloc::realloc(header_ptr.as_ptr(), cur_layout, new_layout.size());
+                ptr::NonNull::new(raw).ok_or(())?
+            }
+        };
+
+        // Our allocation succeeded! Write the new capacity, the allocator handle got moved along
+        // with the rest of the header
+        let header = new_header_ptr.as_ptr().cast::<Header>();
+        ptr::addr_of_mut!((*header).capacity).write(new_capacity);
+
+        Ok(ptr::NonNull::new_unchecked(
+            new_header_ptr.as_ptr().add(HEADER_SIZE),
+        ))
     }
 
     #[repr(C)]
     struct HeapBufferInnerHeapCapacity {
-        capacity: usize,
+        header: Header,
         buffer: StrBuffer,
     }
 
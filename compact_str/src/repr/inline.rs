@@ -160,6 +160,15 @@ impl InlineBuffer {
 
     #[inline]
     pub(crate) const fn new_const(text: &str) -> Self {
+        Self::new_const_bytes(text.as_bytes())
+    }
+
+    /// Construct a new [`InlineBuffer`] from arbitrary bytes in a `const` context.
+    ///
+    /// Note: If `bytes` is not valid UTF-8, the caller must make sure its length is less than
+    /// [`MAX_SIZE`], otherwise the final byte could be mistaken for a discriminant.
+    #[inline]
+    pub(crate) const fn new_const_bytes(text: &[u8]) -> Self {
         if text.len() > MAX_SIZE {
             panic!("Provided string has a length greater than our MAX_SIZE");
         }
@@ -173,7 +182,6 @@ impl InlineBuffer {
         // Note: for loops aren't allowed in `const fn`, hence the while.
         // Note: Iterating forward results in badly optimized code, because the compiler tries to
         //       unroll the loop.
-        let text = text.as_bytes();
         let mut i = len;
         while i > 0 {
             buffer[i - 1] = text[i - 1];
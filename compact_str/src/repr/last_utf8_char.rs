@@ -1,7 +1,7 @@
 use alloc::string::String;
 
-/// [`LastByte`] is an unsigned 8-bit integer data type that has a valid range of `[0, 217]`.
-/// Excluding `[218, 255]` allows the Rust compiler to use these values as niches.
+/// [`LastByte`] is an unsigned 8-bit integer data type that has a valid range of `[0, 218]`.
+/// Excluding `[219, 255]` allows the Rust compiler to use these values as niches.
 ///
 /// Specifically the compiler can use a value in this range to encode the `None` variant of
 /// `Option<LastByte>` allowing:
@@ -239,6 +239,7 @@ pub(crate) enum LastByte {
 
     Heap = 216,
     Static = 217,
+    Shared = 218,
 }
 
 static_assertions::assert_eq_size!(LastByte, Option<LastByte>, u8);
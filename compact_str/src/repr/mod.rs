@@ -9,26 +9,38 @@ mod bytes;
 mod smallvec;
 
 mod capacity;
+mod compact_bytes;
+mod float;
 mod heap;
 mod inline;
 mod iter;
 mod last_utf8_char;
+#[cfg(feature = "std")]
+mod net;
 mod num;
 #[allow(unexpected_cfgs)]
 #[cfg(kani)]
 mod proofs;
+mod repr_n;
+mod shared;
 mod static_str;
 mod traits;
 
 use alloc::string::String;
+use alloc::vec::Vec;
 
 use capacity::Capacity;
+pub(crate) use compact_bytes::MAX_INLINE_BYTES;
 use heap::HeapBuffer;
 use inline::InlineBuffer;
 use last_utf8_char::LastByte;
+pub(crate) use num::int_len;
+pub(crate) use repr_n::ReprN;
+use shared::SharedStr;
 use static_str::StaticStr;
 pub(crate) use traits::IntoRepr;
 
+use crate::allocator::AllocatorRef;
 use crate::{ReserveError, UnwrapWithMsg};
 
 /// The max size of a string we can fit inline
@@ -37,12 +49,37 @@ pub(crate) const MAX_SIZE: usize = core::mem::size_of::<String>();
 pub(crate) const HEAP_MASK: u8 = LastByte::Heap as u8;
 /// Used for `StaticStr` variant
 pub(crate) const STATIC_STR_MASK: u8 = LastByte::Static as u8;
+/// Used as a discriminant to identify a reference counted, shared string
+pub(crate) const SHARED_MASK: u8 = LastByte::Shared as u8;
 /// When our string is stored inline, we represent the length of the string in the last byte, offset
 /// by `LENGTH_MASK`
 pub(crate) const LENGTH_MASK: u8 = 0b11000000;
 
 const EMPTY: Repr = Repr::const_new("");
 
+/// The number of words in a [`Repr`].
+pub(crate) const WORDS: usize = MAX_SIZE / mem::size_of::<usize>();
+
+/// For every inline length, a mask that keeps the first `len` bytes and the last byte of an
+/// inline buffer, i.e. all of the bytes that matter.
+const INLINE_MASKS: [[usize; WORDS]; MAX_SIZE + 1] = {
+    let mut masks = [[0; WORDS]; MAX_SIZE + 1];
+    let mut len = 0;
+    while len <= MAX_SIZE {
+        let mut bytes = [0; MAX_SIZE];
+        let mut i = 0;
+        while i < MAX_SIZE {
+            if i < len || i == MAX_SIZE - 1 {
+                bytes[i] = 0xFF;
+            }
+            i += 1;
+        }
+        masks[len] = words_from_bytes(bytes);
+        len += 1;
+    }
+    masks
+};
+
 #[repr(C)]
 pub(crate) struct Repr(
     /// We have a pointer in the representation to properly carry provenance.
@@ -59,6 +96,7 @@ pub(crate) struct Repr(
     LastByte,
 );
 static_assertions::assert_eq_size!([u8; MAX_SIZE], Repr);
+static_assertions::assert_eq_size!([usize; WORDS], Repr);
 
 unsafe impl Send for Repr {}
 unsafe impl Sync for Repr {}
@@ -122,6 +160,16 @@ impl Repr {
         }
     }
 
+    /// Create a [`Repr`] that either inlines `text`, or references it like a `&'static str`.
+    ///
+    /// # Safety
+    /// * The returned [`Repr`] must not be used after `text` is no longer valid, and it must not
+    ///   hand out `text` as a `&'static str`.
+    #[inline]
+    pub(crate) const unsafe fn from_borrowed(text: &str) -> Self {
+        Self::const_new(&*(text as *const str))
+    }
+
     /// Create a [`Repr`] with the provided `capacity`
     #[inline]
     pub(crate) fn with_capacity(capacity: usize) -> Result<Self, ReserveError> {
@@ -132,6 +180,60 @@ impl Repr {
         }
     }
 
+    /// Create a [`Repr`] with the provided text, allocating a heap buffer with `allocator` if the
+    /// text doesn't fit inline
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    pub(crate) fn new_in(text: &str, allocator: AllocatorRef) -> Result<Self, ReserveError> {
+        if text.len() <= MAX_SIZE {
+            Repr::new(text)
+        } else {
+            HeapBuffer::new_in(text, allocator).map(Repr::from_heap)
+        }
+    }
+
+    /// Create a [`Repr`] with the provided `capacity`, allocating a heap buffer with `allocator`
+    /// if the capacity is larger than what fits inline
+    #[inline]
+    pub(crate) fn with_capacity_in(
+        capacity: usize,
+        allocator: AllocatorRef,
+    ) -> Result<Self, ReserveError> {
+        if capacity <= MAX_SIZE {
+            Ok(EMPTY)
+        } else {
+            HeapBuffer::with_capacity_in(capacity, allocator).map(Repr::from_heap)
+        }
+    }
+
+    /// Returns the custom [`crate::Allocator`] our heap buffer was allocated with, if any
+    #[inline]
+    pub(crate) fn allocator(&self) -> Option<&AllocatorRef> {
+        if self.is_heap_allocated() {
+            // SAFETY: We just checked the discriminant to make sure we're heap allocated
+            unsafe { self.as_heap() }.allocator()
+        } else {
+            None
+        }
+    }
+
+    /// Fallible version of [`Clone::clone()`]. Like `clone`, only heap allocated strings
+    /// allocate, and strings from a custom allocator are cloned into the same allocator.
+    #[inline]
+    pub(crate) fn try_clone(&self) -> Result<Self, ReserveError> {
+        if !self.is_heap_allocated() {
+            return Ok(self.clone());
+        }
+
+        // SAFETY: We just checked the discriminant to make sure we're heap allocated
+        let heap = unsafe { self.as_heap() };
+        if heap.allocator().is_some() {
+            heap.try_clone().map(Repr::from_heap)
+        } else {
+            Repr::new(self.as_str())
+        }
+    }
+
     /// Create a [`Repr`] from a slice of bytes that is UTF-8
     #[inline]
     pub(crate) fn from_utf8<B: AsRef<[u8]>>(buf: B) -> Result<Self, Utf8Error> {
@@ -213,23 +315,27 @@ impl Repr {
     /// Converts a [`Repr`] into a [`String`], in `O(1)` time, if possible
     #[inline]
     pub(crate) fn into_string(self) -> String {
+        // SAFETY: A `Repr` contains valid UTF-8
+        unsafe { String::from_utf8_unchecked(self.into_vec()) }
+    }
+
+    /// Converts a [`Repr`] into a [`Vec<u8>`], in `O(1)` time, if possible
+    #[inline]
+    pub(crate) fn into_vec(self) -> Vec<u8> {
         #[cold]
-        fn into_string_heap(this: HeapBuffer) -> String {
+        fn into_vec_heap(this: HeapBuffer) -> Vec<u8> {
             // SAFETY: We know pointer is valid for `length` bytes
             let slice = unsafe { core::slice::from_raw_parts(this.ptr.as_ptr(), this.len) };
-            // SAFETY: A `Repr` contains valid UTF-8
-            let s = unsafe { core::str::from_utf8_unchecked(slice) };
-
-            String::from(s)
+            slice.to_vec()
         }
 
         if self.is_heap_allocated() {
             // SAFETY: we just checked that the discriminant indicates we're a HeapBuffer
             let heap_buffer = unsafe { self.into_heap() };
 
-            if heap_buffer.cap.is_heap() {
-                // We don't expect capacity to be on the heap often, so we mark it as cold
-                into_string_heap(heap_buffer)
+            if heap_buffer.cap.has_header() {
+                // We don't expect the buffer to start with a header often, so we mark it as cold
+                into_vec_heap(heap_buffer)
             } else {
                 // Wrap the BoxString in a ManuallyDrop so the underlying buffer doesn't get freed
                 let this = mem::ManuallyDrop::new(heap_buffer);
@@ -242,11 +348,10 @@ impl Repr {
                 //   library uses, with a required alignment of exactly 1.
                 // * `length` is less than or equal to capacity, due to internal invaraints.
                 // * `capacity` is correctly maintained internally.
-                // * `BoxString` only ever contains valid UTF-8.
-                unsafe { String::from_raw_parts(this.ptr.as_ptr(), this.len, cap) }
+                unsafe { Vec::from_raw_parts(this.ptr.as_ptr(), this.len, cap) }
             }
         } else {
-            String::from(self.as_str())
+            self.as_slice().to_vec()
         }
     }
 
@@ -257,10 +362,10 @@ impl Repr {
         let len = self.len();
         let needed_capacity = len.checked_add(additional).ok_or(ReserveError(()))?;
 
-        if !self.is_static_str() && needed_capacity <= self.capacity() {
+        if !self.is_immutable() && needed_capacity <= self.capacity() {
             // we already have enough space, no-op
-            // If self.is_static_str() is true, then we would have to convert
-            // it to other variants since static_str variant cannot be modified.
+            // If self.is_immutable() is true, then we would have to convert
+            // it to other variants since static_str and shared variants cannot be modified.
             Ok(())
         } else if needed_capacity <= MAX_SIZE {
             // It's possible to have a `Repr` that is heap allocated with a capacity less than
@@ -285,6 +390,11 @@ impl Repr {
             let amortized_capacity = heap::amortized_growth(len, additional);
             // Attempt to grow our capacity, allocating a new HeapBuffer on failure
             if heap_buffer.realloc(amortized_capacity).is_err() {
+                // A custom allocator already had its chance to allocate, so don't fall back to
+                // the global allocator
+                if heap_buffer.allocator().is_some() {
+                    return Err(ReserveError(()));
+                }
                 // Create a new HeapBuffer
                 let heap = HeapBuffer::with_additional(self.as_str(), additional)?;
                 *self = Repr::from_heap(heap);
@@ -336,9 +446,13 @@ impl Repr {
             return;
         }
 
-        // Otherwise try to allocate a new, smaller chunk.
+        // Otherwise try to allocate a new, smaller chunk, with the same allocator.
         // We can ignore the error. The string keeps its old capacity, but that's okay.
-        if let Ok(mut new_this) = Repr::with_capacity(new_capacity) {
+        let new_this = match heap.allocator() {
+            Some(allocator) => Repr::with_capacity_in(new_capacity, AllocatorRef::clone(allocator)),
+            None => Repr::with_capacity(new_capacity),
+        };
+        if let Ok(mut new_this) = new_this {
             new_this.push_str(self.as_str());
             *self = new_this;
         }
@@ -346,19 +460,24 @@ impl Repr {
 
     #[inline]
     pub(crate) fn push_str(&mut self, s: &str) {
+        self.try_push_str(s).unwrap_with_msg()
+    }
+
+    #[inline]
+    pub(crate) fn try_push_str(&mut self, s: &str) -> Result<(), ReserveError> {
         // If `s` is empty, then there's no reason to reserve or push anything
         // at all.
         if s.is_empty() {
-            return;
+            return Ok(());
         }
 
         let len = self.len();
         let str_len = s.len();
 
         // Only call the (out-of-line) grow path when we actually need to; the common append
-        // already has room. A `&'static str` always has to be converted first.
-        if self.is_static_str() || len + str_len > self.capacity() {
-            self.reserve(str_len).unwrap_with_msg();
+        // already has room. A `&'static str` or shared string always has to be converted first.
+        if self.is_immutable() || len + str_len > self.capacity() {
+            self.reserve(str_len)?;
         }
 
         // Copy the string into the spare capacity without first creating a reference to the
@@ -379,6 +498,7 @@ impl Repr {
         // SAFETY: We appended `s` which is valid UTF-8, and if our size became greater than
         // MAX_SIZE, our call to reserve would make us heap allocated
         unsafe { self.set_len(len + str_len) };
+        Ok(())
     }
 
     #[inline]
@@ -416,6 +536,96 @@ impl Repr {
         unsafe { core::slice::from_raw_parts(pointer, length) }
     }
 
+    /// Returns the inline buffer as words, with the unused bytes zeroed, or `None` if the string
+    /// isn't stored inline.
+    ///
+    /// The last byte stores the length, so two inline strings are equal if and only if their
+    /// words are equal.
+    #[inline(always)]
+    pub(crate) fn inline_words(&self) -> Option<[usize; WORDS]> {
+        let last_byte = self.last_byte();
+        if last_byte >= HEAP_MASK {
+            return None;
+        }
+        let len = core::cmp::min(last_byte.wrapping_sub(LENGTH_MASK) as usize, MAX_SIZE);
+
+        // SAFETY: An inline `Repr` is an `InlineBuffer`, so all of its bytes are initialized, and a
+        // `Repr` is aligned to a `usize`
+        let words = unsafe { *(self as *const Self as *const [usize; WORDS]) };
+        let mask = &INLINE_MASKS[len];
+        Some(core::array::from_fn(|i| words[i] & mask[i]))
+    }
+
+    /// Returns the first 16 bytes of the string as a big endian integer, padded with zeros.
+    #[inline]
+    pub(crate) fn sort_key(&self) -> u128 {
+        // an inline string already has the prefix in its first two words
+        #[cfg(all(target_pointer_width = "64", target_endian = "little"))]
+        if let Some([first, second, _]) = self.inline_words() {
+            return ((first as u64).swap_bytes() as u128) << 64
+                | (second as u64).swap_bytes() as u128;
+        }
+
+        let bytes = self.as_slice();
+        let mut prefix = [0; 16];
+        match bytes.get(..16) {
+            Some(bytes) => prefix.copy_from_slice(bytes),
+            None => prefix[..bytes.len()].copy_from_slice(bytes),
+        }
+        u128::from_be_bytes(prefix)
+    }
+
+    /// Returns the words [`Repr::inline_words`] would return for `text`, which can be any bytes
+    /// that fit inline.
+    #[inline]
+    #[cfg(all(target_pointer_width = "64", target_endian = "little"))]
+    pub(crate) fn inline_words_of(text: &[u8]) -> [usize; WORDS] {
+        debug_assert!(text.len() <= MAX_SIZE);
+
+        // Like `InlineBuffer::new`, we build the words in registers, copying into a stack buffer
+        // and then loading whole words from it stalls on the overlapping stores.
+        let len = text.len();
+        let word = |start: usize| match text.get(start..) {
+            Some(rest) if !rest.is_empty() => load_le(&rest[..rest.len().min(8)]) as usize,
+            _ => 0,
+        };
+        let mut words = [word(0), word(8), word(16)];
+        if len < MAX_SIZE {
+            words[2] |= ((len as u8 | LENGTH_MASK) as usize) << 56;
+        }
+        words
+    }
+
+    /// Returns the words [`Repr::inline_words`] would return for `text`, which can be any bytes
+    /// that fit inline.
+    #[inline]
+    #[cfg(not(all(target_pointer_width = "64", target_endian = "little")))]
+    pub(crate) fn inline_words_of(text: &[u8]) -> [usize; WORDS] {
+        debug_assert!(text.len() <= MAX_SIZE);
+
+        let mut bytes = [0; MAX_SIZE];
+        bytes[..text.len()].copy_from_slice(text);
+        if text.len() < MAX_SIZE {
+            bytes[MAX_SIZE - 1] = text.len() as u8 | LENGTH_MASK;
+        }
+        words_from_bytes(bytes)
+    }
+
+    /// Returns the string, followed by the unused bytes of the inline buffer if it's stored
+    /// inline.
+    ///
+    /// The bytes after the string are arbitrary, but unlike reading past the end of a `&[u8]`
+    /// we're allowed to load them, so short strings can be read a whole word at a time.
+    #[inline]
+    pub(crate) fn as_padded_slice(&self) -> &[u8] {
+        if self.last_byte() < HEAP_MASK {
+            // SAFETY: An inline `Repr` is an `InlineBuffer`, so all of its bytes are initialized
+            unsafe { &*(self as *const Self as *const [u8; MAX_SIZE]) }
+        } else {
+            self.as_slice()
+        }
+    }
+
     #[inline]
     pub(crate) fn as_str(&self) -> &str {
         // SAFETY: A `Repr` contains valid UTF-8
@@ -474,8 +684,8 @@ impl Repr {
             heap_buffer.capacity()
         }
 
-        if let Some(s) = self.as_static_str() {
-            s.len()
+        if self.is_immutable() {
+            self.len()
         } else if self.is_heap_allocated() {
             heap_capacity(self)
         } else {
@@ -495,6 +705,20 @@ impl Repr {
         last_byte == STATIC_STR_MASK
     }
 
+    #[inline(always)]
+    pub(crate) const fn is_shared(&self) -> bool {
+        let last_byte = self.last_byte();
+        last_byte == SHARED_MASK
+    }
+
+    /// Returns `true` if we're a `&'static str` or a shared string, neither of which can be
+    /// modified in place.
+    #[inline(always)]
+    const fn is_immutable(&self) -> bool {
+        // Note: `SHARED_MASK` is the largest valid discriminant
+        self.last_byte() >= STATIC_STR_MASK
+    }
+
     #[inline]
     pub(crate) const fn as_static_str(&self) -> Option<&'static str> {
         if self.is_static_str() {
@@ -517,22 +741,29 @@ impl Repr {
         }
     }
 
+    /// Copies a `&'static str` or shared string into a buffer we own, so it can be mutated.
+    #[inline]
+    pub(crate) fn try_make_mut(&mut self) -> Result<(), ReserveError> {
+        #[cold]
+        fn copy_immutable(this: &mut Repr) -> Result<(), ReserveError> {
+            *this = Repr::new(this.as_str())?;
+            Ok(())
+        }
+
+        if self.is_immutable() {
+            copy_immutable(self)
+        } else {
+            Ok(())
+        }
+    }
+
     /// Returns a mutable raw pointer to the start of the underlying buffer.
     ///
     /// The pointer is valid for writes up to [`Repr::capacity`], but only the first [`Repr::len`]
     /// bytes are guaranteed to be initialized.
     #[inline]
     pub(crate) fn as_mut_ptr(&mut self) -> *mut u8 {
-        #[cold]
-        fn inline_static_str(this: &mut Repr) {
-            if let Some(s) = this.as_static_str() {
-                *this = Repr::new(s).unwrap_with_msg();
-            }
-        }
-
-        if self.is_static_str() {
-            inline_static_str(self);
-        }
+        self.try_make_mut().unwrap_with_msg();
 
         if self.is_heap_allocated() {
             // SAFETY: We just checked the discriminant to make sure we're heap allocated.
@@ -549,8 +780,18 @@ impl Repr {
     /// * If the underlying buffer is stored inline, `len` must be <= MAX_SIZE
     #[inline]
     pub(crate) unsafe fn set_len(&mut self, len: usize) {
+        #[cold]
+        unsafe fn copy_shared(this: &mut Repr, len: usize) {
+            // SAFETY: The caller guarantees that `len` bytes is valid UTF-8
+            let s = core::str::from_utf8_unchecked(this.as_slice().get_unchecked(..len));
+            *this = Repr::new(s).unwrap_with_msg();
+        }
+
         if let Some(s) = self.as_static_variant_mut() {
             s.set_len(len);
+        } else if self.is_shared() {
+            // We can't modify the length of a shared string in place, so we copy it
+            copy_shared(self, len);
         } else if self.is_heap_allocated() {
             // SAFETY: We just checked the discriminant to make sure we're heap allocated
             let heap_buffer = self.as_mut_heap();
@@ -567,9 +808,9 @@ impl Repr {
     /// Zero out the memory backing this [`Repr`].
     #[cfg(feature = "zeroize")]
     pub(crate) fn zeroize(&mut self) {
-        // We can't zero out static memory so we just replace ourselves with
+        // We can't zero out static or shared memory so we just replace ourselves with
         // the EMPTY variant.
-        if self.is_static_str() {
+        if self.is_immutable() {
             *self = EMPTY;
             return;
         }
@@ -698,6 +939,38 @@ impl Repr {
         unsafe { core::mem::transmute(heap) }
     }
 
+    /// Reinterprets a [`SharedStr`] into a [`Repr`]
+    ///
+    /// Note: This is safe because [`SharedStr`] and [`Repr`] are the same size.
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline(always)]
+    const fn from_shared(shared: SharedStr) -> Self {
+        // SAFETY: A `SharedStr` and `Repr` have the same size
+        unsafe { core::mem::transmute(shared) }
+    }
+
+    /// Reinterprets a [`Repr`] as a [`SharedStr`]
+    ///
+    /// # SAFETY
+    /// * The caller must guarantee that the provided [`Repr`] is actually a [`SharedStr`] by
+    ///   checking the discriminant.
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline(always)]
+    const unsafe fn into_shared_str(self) -> SharedStr {
+        core::mem::transmute(self)
+    }
+
+    /// Reinterprets a [`Repr`] as a [`SharedStr`]
+    ///
+    /// # SAFETY
+    /// * The caller must guarantee that the provided [`Repr`] is actually a [`SharedStr`] by
+    ///   checking the discriminant.
+    #[inline(always)]
+    unsafe fn as_shared(&self) -> &SharedStr {
+        // SAFETY: A `SharedStr` and `Repr` have the same size
+        &*(self as *const _ as *const SharedStr)
+    }
+
     /// Reinterprets a [`Repr`] as a [`HeapBuffer`]
     ///
     /// # SAFETY
@@ -784,10 +1057,22 @@ impl Clone for Repr {
         // registers; an out-of-line helper returning the 24-byte `Repr` here would route the hot
         // copy arm below through a scatter-copied stack temporary on x86 (store-forwarding stall).
         if self.is_heap_allocated() {
+            // SAFETY: We just checked the discriminant to make sure we're heap allocated
+            let heap = unsafe { self.as_heap() };
+            // Strings from a custom allocator are cloned into the same allocator
+            if heap.allocator().is_some() {
+                return Repr::from_heap(heap.clone());
+            }
             Repr::new_panic(self.as_str())
         } else {
-            // SAFETY: We just checked that `self` can be copied because it is an inline string or
-            // a reference to a `&'static str`.
+            if self.is_shared() {
+                // SAFETY: We just checked the discriminant to make sure we're a shared string
+                let shared = unsafe { self.as_shared() };
+                // SAFETY: The pointer and length come from a live `SharedStr`
+                unsafe { shared::increment_strong_count(shared.ptr, shared.len) };
+            }
+            // SAFETY: We just checked that `self` can be copied because it is an inline string, a
+            // reference to a `&'static str`, or a shared string whose reference count we bumped.
             unsafe { core::ptr::read(self) }
         }
     }
@@ -801,14 +1086,13 @@ impl Clone for Repr {
         }
 
         // There are only two cases we need to care about: If the string is allocated on the heap
-        // or not. If it is, then the data must be cloned proberly, otherwise we can simply copy
-        // the `Repr`.
-        if source.is_heap_allocated() {
+        // or not. If it is, then the data must be cloned proberly, otherwise cloning the `Repr` is
+        // cheap. Strings from a custom allocator are cloned into the same allocator, so we don't
+        // reuse our own buffer for them.
+        if source.is_heap_allocated() && source.allocator().is_none() {
             clone_from_heap(self, source)
         } else {
-            // SAFETY: We just checked that `source` can be copied because it is an inline string or
-            // a reference to a `&'static str`.
-            *self = unsafe { core::ptr::read(source) }
+            *self = source.clone()
         }
     }
 }
@@ -825,18 +1109,38 @@ impl Drop for Repr {
             // dropping a by-value `CompactString` must materialize the full 24 bytes on the
             // caller's stack just to hand `&mut Repr` to the outlined function.
             outlined_drop(heap.ptr, heap.cap)
+        } else if self.is_shared() {
+            // SAFETY: We just checked the discriminant to make sure we're a shared string
+            let shared = unsafe { self.as_shared() };
+            outlined_drop_shared(shared.ptr, shared.len)
         }
 
         #[cold]
         fn outlined_drop(ptr: ptr::NonNull<u8>, cap: Capacity) {
             heap::deallocate_ptr(ptr, cap);
         }
+
+        #[cold]
+        fn outlined_drop_shared(ptr: ptr::NonNull<u8>, len: usize) {
+            // SAFETY: The pointer and length come from a `SharedStr` that is being dropped
+            unsafe { shared::decrement_strong_count(ptr, len) };
+        }
     }
 }
 
 impl Extend<char> for Repr {
     #[inline]
     fn extend<T: IntoIterator<Item = char>>(&mut self, iter: T) {
+        self.try_extend_chars(iter).unwrap_with_msg()
+    }
+}
+
+impl Repr {
+    #[inline]
+    pub(crate) fn try_extend_chars<T: IntoIterator<Item = char>>(
+        &mut self,
+        iter: T,
+    ) -> Result<(), ReserveError> {
         let mut iter = iter.into_iter();
 
         let (lower_bound, _) = iter.size_hint();
@@ -852,12 +1156,13 @@ impl Extend<char> for Repr {
             // Pull a char before resolving the buffer so an empty iterator does no work.
             let mut next = iter.next();
             if next.is_none() {
-                return;
+                return Ok(());
             }
 
+            // Convert a `&'static str` or shared string into an owned buffer.
+            self.try_make_mut()?;
             let cap = self.capacity();
             let mut cur = self.len();
-            // Also converts a `&'static str` into an owned inline buffer.
             let ptr = self.as_mut_ptr();
             let mut buf = [0u8; 4];
 
@@ -868,7 +1173,7 @@ impl Extend<char> for Repr {
                 if cur + n > cap {
                     // SAFETY: `cur` bytes of valid UTF-8 have been written into the buffer.
                     unsafe { self.set_len(cur) };
-                    self.push_str(encoded);
+                    self.try_push_str(encoded)?;
                     continue 'refetch;
                 }
 
@@ -884,7 +1189,7 @@ impl Extend<char> for Repr {
 
             // SAFETY: `cur` bytes of valid UTF-8 have been written into the buffer.
             unsafe { self.set_len(cur) };
-            return;
+            return Ok(());
         }
     }
 }
@@ -946,6 +1251,49 @@ fn ensure_read(value: usize) -> usize {
     value
 }
 
+/// Loads up to 8 bytes into a little endian `u64`, without copying them onto the stack.
+#[inline(always)]
+#[cfg(all(target_pointer_width = "64", target_endian = "little"))]
+fn load_le(bytes: &[u8]) -> u64 {
+    debug_assert!(bytes.len() <= 8);
+
+    let len = bytes.len();
+    if len >= 4 {
+        // two overlapping loads
+        let lo = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64;
+        let tail = &bytes[len - 4..];
+        let hi = u32::from_le_bytes([tail[0], tail[1], tail[2], tail[3]]) as u64;
+        lo | (hi << (8 * (len - 4)))
+    } else if len > 0 {
+        // the first, middle and last byte, which covers 1, 2 and 3 bytes
+        let first = bytes[0] as u64;
+        let middle = (bytes[len / 2] as u64) << (8 * (len / 2));
+        let last = (bytes[len - 1] as u64) << (8 * (len - 1));
+        first | middle | last
+    } else {
+        0
+    }
+}
+
+/// Splits the bytes of an inline buffer into words, in memory order.
+const fn words_from_bytes(bytes: [u8; MAX_SIZE]) -> [usize; WORDS] {
+    const WORD: usize = mem::size_of::<usize>();
+
+    let mut words = [0; WORDS];
+    let mut i = 0;
+    while i < WORDS {
+        let mut word = [0; WORD];
+        let mut j = 0;
+        while j < WORD {
+            word[j] = bytes[i * WORD + j];
+            j += 1;
+        }
+        words[i] = usize::from_ne_bytes(word);
+        i += 1;
+    }
+    words
+}
+
 /// Polyfill for [`core::hint::assert_unchecked`] (stabilized in Rust 1.81; our MSRV is 1.71). The
 /// two are equivalent — `assert_unchecked` is defined as this — so the release codegen is identical.
 ///
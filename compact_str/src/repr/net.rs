@@ -0,0 +1,250 @@
+//! Formatting IP and socket addresses into a [`Repr`], without going through [`core::fmt`].
+//!
+//! All IPv4 addresses and most IPv6 addresses fit inline. The output matches the `Display`
+//! implementations from `std`, including the compressed `::` and IPv4-mapped forms of IPv6
+//! addresses.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use super::traits::IntoRepr;
+use super::Repr;
+use crate::ToCompactStringError;
+
+/// The longest formatted address, `[ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff%4294967295]:65535`
+const MAX_LEN: usize = 58;
+
+/// A buffer on the stack that's large enough for any formatted address.
+struct AddrBuffer {
+    buf: [u8; MAX_LEN],
+    len: usize,
+}
+
+impl AddrBuffer {
+    #[inline]
+    fn new() -> Self {
+        AddrBuffer {
+            buf: [0; MAX_LEN],
+            len: 0,
+        }
+    }
+
+    #[inline]
+    fn push(&mut self, byte: u8) {
+        self.buf[self.len] = byte;
+        self.len += 1;
+    }
+
+    #[inline]
+    fn push_str(&mut self, s: &str) {
+        self.buf[self.len..self.len + s.len()].copy_from_slice(s.as_bytes());
+        self.len += s.len();
+    }
+
+    #[inline]
+    fn push_dec<I: itoa::Integer>(&mut self, n: I) {
+        self.push_str(itoa::Buffer::new().format(n));
+    }
+
+    /// Pushes `n` as lowercase hex, without leading zeros.
+    #[inline]
+    fn push_hex(&mut self, n: u16) {
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+        let digits = ((16 - n.leading_zeros() + 3) / 4).max(1);
+        for i in (0..digits).rev() {
+            self.push(HEX[(n >> (i * 4)) as usize & 0xf]);
+        }
+    }
+
+    fn push_ipv4(&mut self, ip: &Ipv4Addr) {
+        let [a, b, c, d] = ip.octets();
+        self.push_dec(a);
+        self.push(b'.');
+        self.push_dec(b);
+        self.push(b'.');
+        self.push_dec(c);
+        self.push(b'.');
+        self.push_dec(d);
+    }
+
+    /// Pushes `segments` separated by colons.
+    fn push_segments(&mut self, segments: &[u16]) {
+        if let Some((first, rest)) = segments.split_first() {
+            self.push_hex(*first);
+            for segment in rest {
+                self.push(b':');
+                self.push_hex(*segment);
+            }
+        }
+    }
+
+    fn push_ipv6(&mut self, ip: &Ipv6Addr) {
+        if let Some(ipv4) = ip.to_ipv4_mapped() {
+            self.push_str("::ffff:");
+            return self.push_ipv4(&ipv4);
+        }
+
+        // find the first longest run of zero segments, which gets replaced by `::`
+        let segments = ip.segments();
+        let (mut start, mut len) = (0, 0);
+        let mut current = 0;
+        for (i, segment) in segments.iter().enumerate() {
+            if *segment == 0 {
+                current += 1;
+                if current > len {
+                    start = i + 1 - current;
+                    len = current;
+                }
+            } else {
+                current = 0;
+            }
+        }
+
+        // like `std`, a single zero segment isn't compressed
+        if len > 1 {
+            self.push_segments(&segments[..start]);
+            self.push_str("::");
+            self.push_segments(&segments[start + len..]);
+        } else {
+            self.push_segments(&segments);
+        }
+    }
+
+    #[inline]
+    fn into_repr(self) -> Result<Repr, ToCompactStringError> {
+        // SAFETY: We only ever push ASCII
+        let s = unsafe { core::str::from_utf8_unchecked(&self.buf[..self.len]) };
+        Ok(Repr::new(s)?)
+    }
+}
+
+impl IntoRepr for Ipv4Addr {
+    #[inline]
+    fn into_repr(self) -> Result<Repr, ToCompactStringError> {
+        let mut buf = AddrBuffer::new();
+        buf.push_ipv4(&self);
+        buf.into_repr()
+    }
+}
+
+impl IntoRepr for Ipv6Addr {
+    #[inline]
+    fn into_repr(self) -> Result<Repr, ToCompactStringError> {
+        let mut buf = AddrBuffer::new();
+        buf.push_ipv6(&self);
+        buf.into_repr()
+    }
+}
+
+impl IntoRepr for IpAddr {
+    #[inline]
+    fn into_repr(self) -> Result<Repr, ToCompactStringError> {
+        match self {
+            IpAddr::V4(ip) => ip.into_repr(),
+            IpAddr::V6(ip) => ip.into_repr(),
+        }
+    }
+}
+
+impl IntoRepr for SocketAddrV4 {
+    #[inline]
+    fn into_repr(self) -> Result<Repr, ToCompactStringError> {
+        let mut buf = AddrBuffer::new();
+        buf.push_ipv4(self.ip());
+        buf.push(b':');
+        buf.push_dec(self.port());
+        buf.into_repr()
+    }
+}
+
+impl IntoRepr for SocketAddrV6 {
+    #[inline]
+    fn into_repr(self) -> Result<Repr, ToCompactStringError> {
+        let mut buf = AddrBuffer::new();
+        buf.push(b'[');
+        buf.push_ipv6(self.ip());
+        if self.scope_id() != 0 {
+            buf.push(b'%');
+            buf.push_dec(self.scope_id());
+        }
+        buf.push_str("]:");
+        buf.push_dec(self.port());
+        buf.into_repr()
+    }
+}
+
+impl IntoRepr for SocketAddr {
+    #[inline]
+    fn into_repr(self) -> Result<Repr, ToCompactStringError> {
+        match self {
+            SocketAddr::V4(addr) => addr.into_repr(),
+            SocketAddr::V6(addr) => addr.into_repr(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+    use std::string::ToString;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use crate::repr::{IntoRepr, MAX_SIZE};
+
+    fn assert_matches_std<T: IntoRepr + core::fmt::Display + Copy>(addr: T) {
+        let repr = addr.into_repr().unwrap();
+        let std = addr.to_string();
+        assert_eq!(repr.as_str(), std);
+        assert_eq!(repr.is_heap_allocated(), std.len() > MAX_SIZE);
+    }
+
+    /// Generates IPv6 segments, with lots of zeros to exercise the `::` compression.
+    fn segments() -> impl Strategy<Value = [u16; 8]> {
+        let segment = prop_oneof![Just(0u16), Just(1), Just(0xffff), any::<u16>()];
+        proptest::array::uniform8(segment)
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_ipv4(octets: [u8; 4], port: u16) {
+        let ip = Ipv4Addr::from(octets);
+        assert_matches_std(ip);
+        assert_matches_std(IpAddr::V4(ip));
+        assert_matches_std(SocketAddr::V4(SocketAddrV4::new(ip, port)));
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_ipv6(
+        #[strategy(segments())] segments: [u16; 8],
+        port: u16,
+        #[strategy(prop_oneof![Just(0u32), any::<u32>()])] scope_id: u32,
+    ) {
+        let ip = Ipv6Addr::from(segments);
+        assert_matches_std(ip);
+        assert_matches_std(IpAddr::V6(ip));
+        assert_matches_std(SocketAddr::V6(SocketAddrV6::new(ip, port, 0, scope_id)));
+    }
+
+    #[test]
+    fn test_ipv6_edge_cases() {
+        let addrs = [
+            Ipv6Addr::UNSPECIFIED,
+            Ipv6Addr::LOCALHOST,
+            Ipv6Addr::new(0, 0, 0, 0, 0, 0xffff, 0xc0a8, 1),
+            Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0xc0a8, 1),
+            Ipv6Addr::new(1, 0, 0, 1, 0, 0, 0, 1),
+            Ipv6Addr::new(1, 0, 1, 0, 1, 0, 1, 0),
+            Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0),
+            Ipv6Addr::from([0xffff; 8]),
+        ];
+        for ip in addrs {
+            assert_matches_std(ip);
+        }
+
+        let longest = SocketAddrV6::new(Ipv6Addr::from([0xffff; 8]), 65535, 0, u32::MAX);
+        assert_eq!(longest.to_string().len(), super::MAX_LEN);
+        assert_matches_std(longest);
+    }
+}
@@ -7,7 +7,7 @@ use core::{mem, num, ptr};
 
 use super::traits::IntoRepr;
 use super::{InlineBuffer, Repr, LENGTH_MASK, MAX_SIZE};
-use crate::ToCompactStringError;
+use crate::{IntFormat, ReserveError, ToCompactStringError};
 
 const DEC_DIGITS_LUT: &[u8] = b"\
       0001020304050607080910111213141516171819\
@@ -134,6 +134,154 @@ impl_IntoRepr!(usize, u64);
 #[cfg(target_pointer_width = "64")]
 impl_IntoRepr!(isize, u64);
 
+impl Repr {
+    /// Formats `n`, with a leading `-` if `negative`, into an inline [`Repr`]. Unlike
+    /// [`IntoRepr`], this can be evaluated in a `const` context.
+    ///
+    /// # Panics
+    /// * If the formatted number doesn't fit inline, which can only happen for 64-bit integers on
+    ///   32-bit targets.
+    pub(crate) const fn const_from_u64(n: u64, negative: bool) -> Self {
+        let len = u64_num_chars(n) + negative as usize;
+        assert!(len <= MAX_SIZE, "formatted number does not fit inline");
+
+        let mut buffer = [0u8; MAX_SIZE];
+        let mut n = n;
+        let mut curr = len;
+
+        while n >= 100 {
+            let d = (n % 100) as usize * 2;
+            n /= 100;
+            curr -= 2;
+            buffer[curr] = DEC_DIGITS_LUT[d];
+            buffer[curr + 1] = DEC_DIGITS_LUT[d + 1];
+        }
+        if n < 10 {
+            curr -= 1;
+            buffer[curr] = n as u8 + b'0';
+        } else {
+            let d = n as usize * 2;
+            curr -= 2;
+            buffer[curr] = DEC_DIGITS_LUT[d];
+            buffer[curr + 1] = DEC_DIGITS_LUT[d + 1];
+        }
+        if negative {
+            buffer[0] = b'-';
+        }
+
+        // A string of exactly `MAX_SIZE` digits doesn't need a length marker, its last byte is an
+        // ASCII digit.
+        if len < MAX_SIZE {
+            buffer[MAX_SIZE - 1] = len as u8 | LENGTH_MASK;
+        }
+        Repr::from_inline(InlineBuffer(buffer))
+    }
+}
+
+const LOWER_DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+const UPPER_DIGITS: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+impl Repr {
+    /// Formats an integer with the magnitude `n`, and a leading `-` if `negative`, according to
+    /// `format`.
+    pub(crate) fn from_int_with(
+        n: u128,
+        negative: bool,
+        format: &IntFormat,
+    ) -> Result<Self, ReserveError> {
+        let radix = format.radix;
+        let num_digits = n
+            .checked_ilog(radix as u128)
+            .map_or(1, |log| log as usize + 1);
+        let num_chars = num_digits + negative as usize;
+        let len = num_chars.max(format.width);
+
+        let mut repr = Repr::with_capacity(len)?;
+        let buf_ptr = repr.as_mut_ptr();
+        let digits = if format.uppercase {
+            UPPER_DIGITS
+        } else {
+            LOWER_DIGITS
+        };
+
+        // SAFETY: `repr` has a capacity of at least `len` bytes, and we write exactly `len` ASCII
+        // bytes before setting the length.
+        unsafe {
+            // Zeros go between the sign and the digits, spaces before the sign.
+            let padding = len - num_chars;
+            let (sign_pos, pad_pos) = if format.fill == b'0' {
+                (0, negative as usize)
+            } else {
+                (padding, 0)
+            };
+            ptr::write_bytes(buf_ptr.add(pad_pos), format.fill, padding);
+            if negative {
+                *buf_ptr.add(sign_pos) = b'-';
+            }
+
+            let written = write_digits(buf_ptr.add(len), n, radix, digits);
+            debug_assert_eq!(written, num_digits);
+
+            repr.set_len(len);
+        }
+
+        Ok(repr)
+    }
+
+    /// Appends the integer with the magnitude `n`, and a leading `-` if `negative`, in base 10.
+    pub(crate) fn push_int(&mut self, n: u128, negative: bool) -> Result<(), ReserveError> {
+        let num_chars = int_len(n, negative);
+        self.reserve(num_chars)?;
+
+        let len = self.len();
+        // SAFETY: We reserved `num_chars` bytes past `len`, and write exactly that many ASCII
+        // bytes before setting the length.
+        unsafe {
+            let buf_ptr = self.as_mut_ptr().add(len);
+            if negative {
+                *buf_ptr = b'-';
+            }
+            write_digits(buf_ptr.add(num_chars), n, 10, LOWER_DIGITS);
+            self.set_len(len + num_chars);
+        }
+        Ok(())
+    }
+}
+
+/// Returns the length of the integer with the magnitude `n` in base 10, including a leading `-`
+/// if `negative`.
+#[inline]
+pub(crate) fn int_len(n: u128, negative: bool) -> usize {
+    n.checked_ilog10().map_or(1, |log| log as usize + 1) + negative as usize
+}
+
+/// Writes the digits of `n` in base `radix` backwards, ending just before `end`, and returns
+/// how many were written.
+///
+/// # Safety
+/// There must be enough space before `end` for all of the digits.
+#[inline(always)]
+unsafe fn write_digits(end: *mut u8, n: u128, radix: u32, digits: &[u8; 36]) -> usize {
+    // Write the digits from the back, doing 64-bit math for as long as we can.
+    let mut curr = end;
+    let mut n = n;
+    while n > u64::MAX as u128 {
+        curr = curr.sub(1);
+        *curr = digits[(n % radix as u128) as usize];
+        n /= radix as u128;
+    }
+    let mut n = n as u64;
+    loop {
+        curr = curr.sub(1);
+        *curr = digits[(n % radix as u64) as usize];
+        n /= radix as u64;
+        if n == 0 {
+            break;
+        }
+    }
+    end.offset_from(curr) as usize
+}
+
 /// For 128-bit integer types we use the [`itoa`] crate because writing into a buffer, and then
 /// copying the amount of characters we've written, is faster than determining the number of
 /// characters and then writing.
@@ -328,9 +476,7 @@ impl NumChars for i32 {
 impl NumChars for u64 {
     #[inline(always)]
     fn num_chars(val: u64) -> usize {
-        // `checked_ilog10` is `None` only for `0`, which has one digit. Cheaper than a 20-arm
-        // match for 64-bit values, and exact (unlike `f64::log10`).
-        val.checked_ilog10().map_or(1, |log| log as usize + 1)
+        u64_num_chars(val)
     }
 }
 
@@ -338,10 +484,18 @@ impl NumChars for i64 {
     #[inline(always)]
     fn num_chars(val: i64) -> usize {
         // Digits of the magnitude plus one for the sign. `unsigned_abs` avoids `-i64::MIN`.
-        val.unsigned_abs()
-            .checked_ilog10()
-            .map_or(1, |log| log as usize + 1)
-            + (val < 0) as usize
+        u64_num_chars(val.unsigned_abs()) + (val < 0) as usize
+    }
+}
+
+/// Returns the number of digits in `val`, usable in a `const` context.
+#[inline(always)]
+const fn u64_num_chars(val: u64) -> usize {
+    // `checked_ilog10` is `None` only for `0`, which has one digit. Cheaper than a 20-arm
+    // match for 64-bit values, and exact (unlike `f64::log10`).
+    match val.checked_ilog10() {
+        Some(log) => log as usize + 1,
+        None => 1,
     }
 }
 
@@ -377,7 +531,12 @@ impl NumChars for isize {
 mod tests {
     use alloc::string::ToString;
 
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
     use super::IntoRepr;
+    use crate::repr::Repr;
+    use crate::IntFormat;
 
     #[test]
     fn test_from_u8_sanity() {
@@ -505,4 +664,68 @@ mod tests {
             assert_eq!(repr.as_str(), x.to_string());
         }
     }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_const_from_u64(#[strategy(0..u32::MAX as u64 * 2)] x: u64, negative: bool) {
+        let repr = Repr::const_from_u64(x, negative);
+        let expected = if negative {
+            format!("-{x}")
+        } else {
+            x.to_string()
+        };
+        prop_assert_eq!(repr.as_str(), expected);
+        prop_assert!(!repr.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_const_from_u64_sanity() {
+        const ZERO: Repr = Repr::const_from_u64(0, false);
+        assert_eq!(ZERO.as_str(), "0");
+
+        for x in [1, 9, 10, 99, 100, 999_999, 1_000_000, u32::MAX as u64] {
+            assert_eq!(Repr::const_from_u64(x, false).as_str(), x.to_string());
+            assert_eq!(Repr::const_from_u64(x, true).as_str(), format!("-{x}"));
+        }
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_const_from_u64_max() {
+        assert_eq!(
+            Repr::const_from_u64(u64::MAX, false).as_str(),
+            u64::MAX.to_string()
+        );
+        assert_eq!(
+            Repr::const_from_u64(i64::MIN.unsigned_abs(), true).as_str(),
+            i64::MIN.to_string()
+        );
+    }
+
+    #[test]
+    fn test_from_int_with() {
+        let hex = IntFormat::new().radix(16);
+
+        let repr = |n: i128, format: IntFormat| {
+            Repr::from_int_with(n.unsigned_abs(), n < 0, &format).unwrap()
+        };
+        assert_eq!(repr(0, hex).as_str(), "0");
+        assert_eq!(repr(255, hex).as_str(), "ff");
+        assert_eq!(repr(-255, hex.uppercase(true)).as_str(), "-FF");
+        assert_eq!(repr(-255, hex.zero_pad(6)).as_str(), "-000ff");
+        assert_eq!(repr(-255, hex.space_pad(6)).as_str(), "   -ff");
+        assert_eq!(repr(12345, hex.zero_pad(2)).as_str(), "3039");
+        assert_eq!(
+            repr(i128::MIN, IntFormat::new()).as_str(),
+            i128::MIN.to_string()
+        );
+        assert_eq!(
+            repr(i128::MAX, IntFormat::new().radix(2)).as_str(),
+            format!("{:b}", i128::MAX)
+        );
+
+        let padded = repr(7, IntFormat::new().zero_pad(100));
+        assert!(padded.is_heap_allocated());
+        assert_eq!(padded.as_str(), format!("{:0100}", 7));
+    }
 }
@@ -0,0 +1,784 @@
+//! A [`Repr`](super::Repr) with a configurable inline capacity, which backs
+//! [`CompactStringN`](crate::CompactStringN).
+//!
+//! A [`ReprN<N>`] is `N` bytes large, and its last byte is a [`LastByte`] that uses the same
+//! discriminants as a `Repr`, so `Option<ReprN<N>>` is `N` bytes large too. Heap allocated strings
+//! are a [`HeapBuffer`], and `&'static str`s a [`StaticStr`], stored in our last words. Only a
+//! `ReprN` that's smaller than a `HeapBuffer`, i.e. two words on 64-bit archs, doesn't have room
+//! for them, and uses a [`ThinStr`] instead.
+//!
+//! An inline string that fills all `N` bytes ends in a UTF-8 byte, which is always less than
+//! [`LENGTH_MASK`]. Any shorter string stores its number of spare bytes, `N - 1 - len`, in the
+//! last byte, offset by [`LENGTH_MASK`]. There are only 24 of those values, so a string with
+//! [`LONG_SPARE`] or more spare bytes stores its length in the second to last byte instead.
+
+use alloc::string::String;
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::ptr::{self, NonNull};
+use core::{slice, str};
+
+use super::capacity::Capacity;
+use super::heap::{self, heap_capacity, HeapBuffer};
+use super::last_utf8_char::LastByte;
+use super::static_str::StaticStr;
+use super::{HEAP_MASK, LENGTH_MASK, MAX_SIZE, STATIC_STR_MASK};
+use crate::compact_string_n::sealed;
+use crate::{InlineSize, ReserveError, SupportedInlineSize, UnwrapWithMsg};
+
+/// An inline string with at least this many spare bytes stores its length in the second to last
+/// byte, and `LastByte::L23` in the last one
+const LONG_SPARE: usize = (LastByte::L23 as u8 - LENGTH_MASK) as usize;
+
+const USIZE_SIZE: usize = mem::size_of::<usize>();
+
+#[repr(C)]
+pub(crate) struct ReprN<const N: usize>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    /// Aligns us like a pointer, so our last words can hold a [`HeapBuffer`] or [`StaticStr`]
+    _align: [usize; 0],
+    /// `N - 1` bytes, which are all initialized for an inline string
+    buffer: <InlineSize<N> as sealed::SupportedInlineSize>::Buffer,
+    /// Allows the compiler to see a niche value, just like the last byte of a `Repr`
+    last_byte: LastByte,
+}
+
+/// A [`StaticStr`] in the last words of a [`ReprN`]
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct WideStatic<const N: usize>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    _align: [usize; 0],
+    padding: <InlineSize<N> as sealed::SupportedInlineSize>::Padding,
+    static_str: StaticStr,
+}
+
+/// Builds a [`ReprN`] in a `const` context
+#[repr(C)]
+union Raw<const N: usize>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    bytes: [u8; N],
+    wide_static: WideStatic<N>,
+    thin_static: ThinStr,
+    repr: ManuallyDrop<ReprN<N>>,
+}
+
+/// A heap allocated string, or a `&'static str`, in a [`ReprN`] that's too small for a
+/// [`HeapBuffer`] or [`StaticStr`].
+///
+/// The last byte of `len` is our discriminant, so the length is limited to 7 bytes, and a heap
+/// buffer always stores its capacity in a header, like a `HeapBuffer` whose capacity is on the
+/// heap.
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct ThinStr {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+impl ThinStr {
+    #[inline]
+    const fn new(ptr: NonNull<u8>, len: usize, discriminant: u8) -> Self {
+        let mut bytes = len.to_le_bytes();
+        bytes[USIZE_SIZE - 1] = discriminant;
+        ThinStr {
+            ptr,
+            len: usize::from_ne_bytes(bytes),
+        }
+    }
+
+    #[inline]
+    const fn from_static(text: &'static str) -> Self {
+        // SAFETY: A `&'static str` has a non-null pointer
+        let ptr = unsafe { NonNull::new_unchecked(text.as_ptr() as *mut u8) };
+        Self::new(ptr, text.len(), STATIC_STR_MASK)
+    }
+
+    #[inline]
+    const fn len(&self) -> usize {
+        let mut bytes = self.len.to_ne_bytes();
+        bytes[USIZE_SIZE - 1] = 0;
+        usize::from_le_bytes(bytes)
+    }
+}
+
+impl<const N: usize> ReprN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    /// Whether we're too small for a [`HeapBuffer`] or [`StaticStr`], and use a [`ThinStr`]
+    const THIN: bool = N < MAX_SIZE;
+    /// The offset of the [`HeapBuffer`], [`StaticStr`] or [`ThinStr`] in our last words
+    const TAIL: usize = if Self::THIN {
+        N - mem::size_of::<ThinStr>()
+    } else {
+        N - MAX_SIZE
+    };
+
+    #[inline]
+    pub(crate) const fn empty() -> Self {
+        Self::const_inline("")
+    }
+
+    #[inline]
+    pub(crate) fn new(text: &str) -> Result<Self, ReserveError> {
+        if text.len() <= N {
+            Ok(Self::inline(text))
+        } else if Self::THIN {
+            Self::new_thin(text, text.len())
+        } else {
+            Ok(Self::from_heap(HeapBuffer::new(text)?))
+        }
+    }
+
+    #[inline]
+    pub(crate) const fn const_new(text: &'static str) -> Self {
+        if text.len() <= N {
+            return Self::const_inline(text);
+        }
+
+        let raw = if Self::THIN {
+            Raw {
+                thin_static: ThinStr::from_static(text),
+            }
+        } else {
+            Raw {
+                wide_static: WideStatic {
+                    _align: [],
+                    padding: <InlineSize<N> as sealed::SupportedInlineSize>::PADDING,
+                    static_str: StaticStr::new(text),
+                },
+            }
+        };
+        // SAFETY: We wrote a `ThinStr` or `StaticStr` into our last words, whose last byte is
+        // `STATIC_STR_MASK`, and the rest of the buffer may be uninitialized
+        ManuallyDrop::into_inner(unsafe { raw.repr })
+    }
+
+    #[inline]
+    pub(crate) fn with_capacity(capacity: usize) -> Result<Self, ReserveError> {
+        if capacity <= N {
+            Ok(Self::empty())
+        } else if Self::THIN {
+            Self::new_thin("", capacity)
+        } else {
+            Ok(Self::from_heap(HeapBuffer::with_capacity(capacity)?))
+        }
+    }
+
+    /// Create a [`ReprN`] from a [`String`]. We'll inline strings that fit if `should_inline` is
+    /// `true`, every other string re-uses the allocation of the `String`, unless we're `THIN`, or
+    /// on a 32-bit arch and the `String` is >16 MB.
+    #[inline]
+    pub(crate) fn from_string(s: String, should_inline: bool) -> Result<Self, ReserveError> {
+        let cap = Capacity::new(s.capacity());
+
+        if should_inline && s.len() <= N {
+            Ok(Self::inline(&s))
+        } else if Self::THIN || cap.is_heap() || s.capacity() == 0 {
+            Self::new(&s)
+        } else {
+            let mut s = ManuallyDrop::new(s.into_bytes());
+            let len = s.len();
+            let ptr = NonNull::new(s.as_mut_ptr()).expect("string with capacity has null ptr?");
+            Ok(Self::from_heap(HeapBuffer { ptr, len, cap }))
+        }
+    }
+
+    /// Converts a [`ReprN`] into a [`String`], in `O(1)` time, if possible
+    #[inline]
+    pub(crate) fn into_string(self) -> String {
+        if !Self::THIN && self.is_heap_allocated() {
+            // SAFETY: We just checked that we're heap allocated
+            let cap = unsafe { self.as_heap() }.cap;
+            if !cap.has_header() {
+                let this = ManuallyDrop::new(self);
+                // SAFETY: We just checked that we're heap allocated
+                let heap = unsafe { this.as_heap() };
+                // SAFETY:
+                // * The buffer was allocated by the global allocator, with an alignment of 1,
+                //   and without a header
+                // * `len` bytes are valid UTF-8, and `len <= cap`
+                // * `this` never drops the buffer, so the `String` is its only owner
+                return unsafe {
+                    String::from_raw_parts(heap.ptr.as_ptr(), heap.len, cap.as_usize())
+                };
+            }
+        }
+        String::from(self.as_str())
+    }
+
+    #[inline]
+    pub(crate) fn reserve(&mut self, additional: usize) -> Result<(), ReserveError> {
+        let len = self.len();
+        let needed_capacity = len.checked_add(additional).ok_or(ReserveError(()))?;
+
+        if !self.is_static_str() && needed_capacity <= self.capacity() {
+            Ok(())
+        } else if needed_capacity <= N {
+            // We're a `&'static str` that fits inline
+            *self = Self::inline(self.as_str());
+            Ok(())
+        } else if !self.is_heap_allocated() {
+            *self = Self::with_additional(self.as_str(), additional)?;
+            Ok(())
+        } else {
+            // To reduce allocations, we amortize our growth
+            let amortized_capacity = heap::amortized_growth(len, additional);
+            if self.realloc(amortized_capacity).is_err() {
+                *self = Self::with_additional(self.as_str(), additional)?;
+            }
+            Ok(())
+        }
+    }
+
+    pub(crate) fn shrink_to(&mut self, min_capacity: usize) {
+        // Inline strings and `&'static str`s can't be shrunk
+        if !self.is_heap_allocated() {
+            return;
+        }
+
+        let new_capacity = self.len().max(min_capacity);
+        if new_capacity <= N {
+            *self = Self::inline(self.as_str());
+            return;
+        }
+        if new_capacity >= self.capacity() {
+            return;
+        }
+
+        // Try to shrink in-place, otherwise try to allocate a new, smaller buffer. We can ignore
+        // the error, the string keeps its old capacity, but that's okay.
+        if self.realloc(new_capacity).is_err() {
+            if let Ok(mut new_this) = Self::with_capacity(new_capacity) {
+                new_this.push_str(self.as_str());
+                *self = new_this;
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn push_str(&mut self, s: &str) {
+        self.try_push_str(s).unwrap_with_msg()
+    }
+
+    #[inline]
+    pub(crate) fn try_push_str(&mut self, s: &str) -> Result<(), ReserveError> {
+        if s.is_empty() {
+            return Ok(());
+        }
+
+        let len = self.len();
+        let str_len = s.len();
+        if self.is_static_str() || len + str_len > self.capacity() {
+            self.reserve(str_len)?;
+        }
+
+        // SAFETY: `reserve` guarantees at least `len + str_len` bytes of capacity, and the
+        // mutable borrow of `self` guarantees `s` doesn't overlap with our buffer
+        unsafe {
+            self.as_mut_ptr()
+                .add(len)
+                .copy_from_nonoverlapping(s.as_ptr(), str_len);
+            self.set_len(len + str_len);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn pop(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next_back()?;
+
+        // SAFETY: We know this is is a valid length which falls on a char boundary
+        unsafe { self.set_len(self.len() - ch.len_utf8()) };
+
+        Some(ch)
+    }
+
+    /// Returns the string content, and only the string content, as a slice of bytes.
+    #[inline]
+    pub(crate) fn as_slice(&self) -> &[u8] {
+        let last_byte = self.last_byte();
+        // SAFETY: We check the discriminant before reading a variant, and the first `len` bytes
+        // of every variant are initialized
+        unsafe {
+            if last_byte < HEAP_MASK {
+                slice::from_raw_parts(self.as_ptr(), self.inline_len())
+            } else if Self::THIN {
+                let thin = self.as_thin();
+                slice::from_raw_parts(thin.ptr.as_ptr(), thin.len())
+            } else if last_byte == HEAP_MASK {
+                let heap = self.as_heap();
+                slice::from_raw_parts(heap.ptr.as_ptr(), heap.len)
+            } else {
+                self.as_wide_static().get_bytes()
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn as_str(&self) -> &str {
+        // SAFETY: A `ReprN` contains valid UTF-8
+        unsafe { str::from_utf8_unchecked(self.as_slice()) }
+    }
+
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        let last_byte = self.last_byte();
+        // SAFETY: We check the discriminant before reading a variant
+        unsafe {
+            if last_byte < HEAP_MASK {
+                self.inline_len()
+            } else if Self::THIN {
+                self.as_thin().len()
+            } else if last_byte == HEAP_MASK {
+                self.as_heap().len
+            } else {
+                self.as_wide_static().get_bytes().len()
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn capacity(&self) -> usize {
+        let last_byte = self.last_byte();
+        if last_byte < HEAP_MASK {
+            N
+        } else if last_byte == STATIC_STR_MASK {
+            self.len()
+        } else if Self::THIN {
+            // SAFETY: We're heap allocated, and a thin heap buffer always starts with a header
+            unsafe { heap_capacity::header(self.as_thin().ptr).capacity }
+        } else {
+            // SAFETY: We just checked that we're heap allocated
+            unsafe { self.as_heap() }.capacity()
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_heap_allocated(&self) -> bool {
+        self.last_byte() == HEAP_MASK
+    }
+
+    #[inline(always)]
+    const fn is_static_str(&self) -> bool {
+        self.last_byte() == STATIC_STR_MASK
+    }
+
+    #[inline]
+    pub(crate) const fn as_static_str(&self) -> Option<&'static str> {
+        if !self.is_static_str() {
+            return None;
+        }
+
+        // SAFETY: We just checked that we're a `&'static str`, which is either stored as a
+        // `ThinStr` or a `StaticStr`
+        let bytes = unsafe {
+            let tail = (self as *const Self as *const u8).add(Self::TAIL);
+            if Self::THIN {
+                let thin = &*tail.cast::<ThinStr>();
+                slice::from_raw_parts(thin.ptr.as_ptr() as *const u8, thin.len())
+            } else {
+                (*tail.cast::<StaticStr>()).get_bytes()
+            }
+        };
+        // SAFETY: We were created from a `&'static str`
+        Some(unsafe { str::from_utf8_unchecked(bytes) })
+    }
+
+    /// Copies a `&'static str` into a buffer we own, so it can be mutated.
+    #[inline]
+    pub(crate) fn try_make_mut(&mut self) -> Result<(), ReserveError> {
+        #[cold]
+        fn copy_static<const N: usize>(this: &mut ReprN<N>) -> Result<(), ReserveError>
+        where
+            InlineSize<N>: SupportedInlineSize,
+        {
+            *this = ReprN::new(this.as_str())?;
+            Ok(())
+        }
+
+        if self.is_static_str() {
+            copy_static(self)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns a mutable raw pointer to the start of the underlying buffer.
+    ///
+    /// The pointer is valid for writes up to [`ReprN::capacity`], but only the first
+    /// [`ReprN::len`] bytes are guaranteed to be initialized.
+    #[inline]
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.try_make_mut().unwrap_with_msg();
+
+        if !self.is_heap_allocated() {
+            self as *mut Self as *mut u8
+        } else if Self::THIN {
+            // SAFETY: We just checked that we're heap allocated
+            unsafe { self.as_thin().ptr.as_ptr() }
+        } else {
+            // SAFETY: We just checked that we're heap allocated
+            unsafe { self.as_heap().ptr.as_ptr() }
+        }
+    }
+
+    /// Sets the length of the string that our underlying buffer contains
+    ///
+    /// # Safety
+    /// * `len` bytes in the buffer must be valid UTF-8
+    /// * `len` must be <= our capacity
+    #[inline]
+    pub(crate) unsafe fn set_len(&mut self, len: usize) {
+        let last_byte = self.last_byte();
+        if last_byte < HEAP_MASK {
+            self.set_inline_len(len);
+        } else if let Some(text) = self.as_static_str() {
+            // SAFETY: The caller guarantees that `len` is a char boundary of our text
+            *self = Self::const_new(text.get_unchecked(..len));
+        } else if Self::THIN {
+            let thin = self.as_thin();
+            // the buffer stays the same, so we must not drop it
+            ptr::write(
+                self,
+                Self::from_thin(ThinStr::new(thin.ptr, len, HEAP_MASK)),
+            );
+        } else {
+            self.as_mut_heap().set_len(len);
+        }
+    }
+
+    #[inline(always)]
+    const fn last_byte(&self) -> u8 {
+        self.last_byte as u8
+    }
+
+    #[inline(always)]
+    const fn as_ptr(&self) -> *const u8 {
+        self as *const Self as *const u8
+    }
+
+    /// An inline string that's `len` bytes long, where `len <= N`
+    #[inline]
+    fn inline(text: &str) -> Self {
+        let mut bytes = [0; N];
+        bytes[..text.len()].copy_from_slice(text.as_bytes());
+        Self::from_inline_bytes(bytes, text.len())
+    }
+
+    /// `const` version of [`ReprN::inline`]
+    #[inline]
+    const fn const_inline(text: &str) -> Self {
+        let mut bytes = [0; N];
+        let mut i = 0;
+        while i < text.len() {
+            bytes[i] = text.as_bytes()[i];
+            i += 1;
+        }
+        Self::from_inline_bytes(bytes, text.len())
+    }
+
+    /// Encodes `len` into the last bytes of an inline string
+    #[inline(always)]
+    const fn from_inline_bytes(mut bytes: [u8; N], len: usize) -> Self {
+        if len < N {
+            let spare = N - 1 - len;
+            if spare < LONG_SPARE {
+                bytes[N - 1] = LENGTH_MASK + spare as u8;
+            } else {
+                bytes[N - 2] = len as u8;
+                bytes[N - 1] = LENGTH_MASK + LONG_SPARE as u8;
+            }
+        }
+        // SAFETY: Every byte of our buffer may have any value, and the last byte is either the
+        // last byte of a UTF-8 string, which is less than `LENGTH_MASK`, or an inline length
+        ManuallyDrop::into_inner(unsafe { Raw { bytes }.repr })
+    }
+
+    /// # Safety
+    /// * We must be an inline string
+    #[inline(always)]
+    unsafe fn inline_len(&self) -> usize {
+        let last_byte = self.last_byte();
+        if last_byte < LENGTH_MASK {
+            return N;
+        }
+        let spare = (last_byte - LENGTH_MASK) as usize;
+        if spare < LONG_SPARE {
+            N - 1 - spare
+        } else {
+            *self.as_ptr().add(N - 2) as usize
+        }
+    }
+
+    /// # Safety
+    /// * We must be an inline string
+    /// * `len` must be <= `N`, and the first `len` bytes of our buffer must be valid UTF-8
+    #[inline(always)]
+    unsafe fn set_inline_len(&mut self, len: usize) {
+        debug_assert!(len <= N);
+        if len < N {
+            let ptr = self as *mut Self as *mut u8;
+            let spare = N - 1 - len;
+            if spare < LONG_SPARE {
+                ptr.add(N - 1).write(LENGTH_MASK + spare as u8);
+            } else {
+                ptr.add(N - 2).write(len as u8);
+                ptr.add(N - 1).write(LENGTH_MASK + LONG_SPARE as u8);
+            }
+        }
+    }
+
+    /// Copies `text` into a new heap allocated string, with at least `additional` bytes of spare
+    /// capacity
+    #[inline]
+    fn with_additional(text: &str, additional: usize) -> Result<Self, ReserveError> {
+        if Self::THIN {
+            Self::new_thin(text, heap::amortized_growth(text.len(), additional))
+        } else {
+            Ok(Self::from_heap(HeapBuffer::with_additional(
+                text, additional,
+            )?))
+        }
+    }
+
+    /// Copies `text` into a new [`ThinStr`] heap buffer, with `capacity` bytes of capacity
+    #[cold]
+    fn new_thin(text: &str, capacity: usize) -> Result<Self, ReserveError> {
+        debug_assert!(Self::THIN && text.len() <= capacity && capacity > N);
+        // SAFETY: `capacity` is larger than `N`, so it's not zero
+        let ptr = unsafe { heap_capacity::alloc(capacity, None)? };
+        // SAFETY: We just allocated `ptr` with room for `text`
+        unsafe {
+            ptr.as_ptr()
+                .copy_from_nonoverlapping(text.as_ptr(), text.len())
+        };
+        Ok(Self::from_thin(ThinStr::new(ptr, text.len(), HEAP_MASK)))
+    }
+
+    /// Try to grow or shrink our heap buffer in place, we must be heap allocated
+    fn realloc(&mut self, new_capacity: usize) -> Result<(), ()> {
+        debug_assert!(self.is_heap_allocated() && new_capacity >= self.len());
+        if Self::THIN {
+            // SAFETY: We're heap allocated, and a thin heap buffer always starts with a header
+            let thin = unsafe { self.as_thin() };
+            // SAFETY: `new_capacity` is at least our length, which doesn't fit inline, so it's
+            // not zero
+            let ptr = unsafe { heap_capacity::realloc(thin.ptr, new_capacity)? };
+            // SAFETY: The old buffer was reallocated into `ptr`, so we must not drop it
+            unsafe {
+                ptr::write(
+                    self,
+                    Self::from_thin(ThinStr::new(ptr, thin.len(), HEAP_MASK)),
+                )
+            };
+            Ok(())
+        } else {
+            // SAFETY: We're heap allocated
+            unsafe { self.as_mut_heap() }
+                .realloc(new_capacity)
+                .map(|_| ())
+        }
+    }
+
+    #[inline(always)]
+    fn from_heap(heap: HeapBuffer) -> Self {
+        debug_assert!(!Self::THIN);
+        // SAFETY: We're not `THIN`, so the `HeapBuffer` fits in our last words, and its last
+        // byte, `HEAP_MASK`, becomes ours. The rest of our buffer may be uninitialized.
+        unsafe { Self::from_tail(heap) }
+    }
+
+    #[inline(always)]
+    fn from_thin(thin: ThinStr) -> Self {
+        debug_assert!(Self::THIN);
+        // SAFETY: A `ThinStr` fits in the last two words of any `ReprN`, and its last byte is a
+        // valid discriminant
+        unsafe { Self::from_tail(thin) }
+    }
+
+    /// # Safety
+    /// * `tail` must fit at [`ReprN::TAIL`], and end in a valid last byte
+    #[inline(always)]
+    unsafe fn from_tail<T>(tail: T) -> Self {
+        let mut this = MaybeUninit::<Self>::uninit();
+        this.as_mut_ptr()
+            .cast::<u8>()
+            .add(Self::TAIL)
+            .cast::<T>()
+            .write(tail);
+        this.assume_init()
+    }
+
+    /// # Safety
+    /// * We must be heap allocated, and not `THIN`
+    #[inline(always)]
+    unsafe fn as_heap(&self) -> &HeapBuffer {
+        &*self.as_ptr().add(Self::TAIL).cast::<HeapBuffer>()
+    }
+
+    /// # Safety
+    /// * We must be heap allocated, and not `THIN`
+    #[inline(always)]
+    unsafe fn as_mut_heap(&mut self) -> &mut HeapBuffer {
+        &mut *(self as *mut Self as *mut u8)
+            .add(Self::TAIL)
+            .cast::<HeapBuffer>()
+    }
+
+    /// # Safety
+    /// * We must be a `&'static str`, and not `THIN`
+    #[inline(always)]
+    unsafe fn as_wide_static(&self) -> &StaticStr {
+        &*self.as_ptr().add(Self::TAIL).cast::<StaticStr>()
+    }
+
+    /// # Safety
+    /// * We must be heap allocated or a `&'static str`, and `THIN`
+    #[inline(always)]
+    unsafe fn as_thin(&self) -> ThinStr {
+        *self.as_ptr().add(Self::TAIL).cast::<ThinStr>()
+    }
+}
+
+impl<const N: usize> Clone for ReprN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        #[cold]
+        fn clone_heap<const N: usize>(this: &ReprN<N>) -> ReprN<N>
+        where
+            InlineSize<N>: SupportedInlineSize,
+        {
+            if ReprN::<N>::THIN {
+                ReprN::new_thin(this.as_str(), this.capacity()).unwrap_with_msg()
+            } else {
+                // SAFETY: We're heap allocated
+                ReprN::from_heap(unsafe { this.as_heap() }.clone())
+            }
+        }
+
+        if self.is_heap_allocated() {
+            clone_heap(self)
+        } else {
+            // SAFETY: Inline strings and `&'static str`s don't own any memory
+            unsafe { ptr::read(self) }
+        }
+    }
+}
+
+impl<const N: usize> Drop for ReprN<N>
+where
+    InlineSize<N>: SupportedInlineSize,
+{
+    #[inline]
+    fn drop(&mut self) {
+        #[cold]
+        fn drop_heap<const N: usize>(this: &mut ReprN<N>)
+        where
+            InlineSize<N>: SupportedInlineSize,
+        {
+            if ReprN::<N>::THIN {
+                // SAFETY: A thin heap buffer always starts with a header
+                unsafe { heap_capacity::dealloc(this.as_thin().ptr) }
+            } else {
+                // SAFETY: We're heap allocated, and never read the `HeapBuffer` again
+                unsafe { ptr::drop_in_place(this.as_mut_heap()) }
+            }
+        }
+
+        if self.is_heap_allocated() {
+            drop_heap(self)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use super::{ReprN, LONG_SPARE};
+
+    fn check_every_length<const N: usize>()
+    where
+        crate::InlineSize<N>: crate::SupportedInlineSize,
+    {
+        let text = "a".repeat(N + 1);
+        for len in 0..=N + 1 {
+            let repr = ReprN::<N>::new(&text[..len]).unwrap();
+            assert_eq!(repr.len(), len);
+            assert_eq!(repr.as_str(), &text[..len]);
+            assert_eq!(repr.is_heap_allocated(), len > N);
+
+            let repr = ReprN::<N>::from_string(String::from(&text[..len]), true).unwrap();
+            assert_eq!(repr.as_str(), &text[..len]);
+            assert_eq!(repr.into_string(), &text[..len]);
+
+            let repr = ReprN::<N>::from_string(String::from(&text[..len]), false).unwrap();
+            assert_eq!(repr.as_str(), &text[..len]);
+            assert_eq!(
+                repr.is_heap_allocated(),
+                len > 0 && !ReprN::<N>::THIN || len > N
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_length() {
+        check_every_length::<16>();
+        check_every_length::<24>();
+        check_every_length::<32>();
+        check_every_length::<40>();
+        check_every_length::<48>();
+        check_every_length::<56>();
+        check_every_length::<64>();
+    }
+
+    #[test]
+    fn test_long_spare_lengths() {
+        // the length moves between the last and the second to last byte
+        assert_eq!(64 - 1 - LONG_SPARE, 40);
+        let mut repr = ReprN::<64>::empty();
+        for _ in 0..64 {
+            repr.push_str("é");
+            let len = repr.len();
+            assert_eq!(repr.as_str(), "é".repeat(len / 2));
+            assert_eq!(repr.is_heap_allocated(), len > 64);
+        }
+        while repr.pop().is_some() {
+            assert_eq!(repr.as_str(), "é".repeat(repr.len() / 2));
+        }
+    }
+
+    #[test]
+    fn test_static() {
+        const LONG: ReprN<16> = ReprN::const_new("a &'static str that doesn't fit");
+        const SHORT: ReprN<16> = ReprN::const_new("fits inline");
+        assert_eq!(
+            LONG.as_static_str(),
+            Some("a &'static str that doesn't fit")
+        );
+        assert_eq!(LONG.capacity(), LONG.len());
+        assert_eq!(SHORT.as_static_str(), None);
+        assert_eq!(SHORT.as_str(), "fits inline");
+
+        let mut repr = ReprN::<24>::const_new("a &'static str that doesn't fit");
+        // SAFETY: `8` is a char boundary
+        unsafe { repr.set_len(8) };
+        assert_eq!(repr.as_str(), "a &'stat");
+        assert!(!repr.is_heap_allocated());
+
+        let mut repr = ReprN::<32>::const_new("a &'static str that doesn't fit inline");
+        repr.push_str("!");
+        assert_eq!(repr.as_str(), "a &'static str that doesn't fit inline!");
+        assert!(repr.is_heap_allocated());
+    }
+}
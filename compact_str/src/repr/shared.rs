@@ -0,0 +1,129 @@
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+use core::ptr;
+
+use super::static_str::DISCRIMINANT_SIZE;
+#[cfg(target_has_atomic = "ptr")]
+use super::MAX_SIZE;
+use super::{Repr, SHARED_MASK};
+
+#[cfg(target_has_atomic = "ptr")]
+impl Repr {
+    /// Create a [`Repr`] from an `Arc<str>`, in `O(1)` time. Strings that fit inline are copied,
+    /// longer strings share the `Arc`'s buffer.
+    #[inline]
+    pub(crate) fn from_arc(text: Arc<str>) -> Self {
+        if text.len() <= MAX_SIZE {
+            Repr::new_panic(&text)
+        } else {
+            Repr::from_shared(SharedStr::new(text))
+        }
+    }
+
+    /// Converts a [`Repr`] into an `Arc<str>`, in `O(1)` time if we're a shared string
+    #[inline]
+    pub(crate) fn into_arc(self) -> Arc<str> {
+        if self.is_shared() {
+            // SAFETY: We just checked the discriminant to make sure we're a shared string
+            let shared = unsafe { self.into_shared_str() };
+            shared.into_arc()
+        } else {
+            Arc::from(self.as_str())
+        }
+    }
+
+    /// Moves a heap allocated string into a shared buffer, so clones of it are `O(1)`. All other
+    /// variants are already cheap to clone, and are returned as-is.
+    #[inline]
+    pub(crate) fn into_shared(self) -> Self {
+        if self.is_heap_allocated() {
+            Repr::from_arc(Arc::from(self.as_str()))
+        } else {
+            self
+        }
+    }
+}
+
+/// A reference counted string, stored as the data pointer and length of an `Arc<str>`.
+///
+/// Cloning a [`SharedStr`] only increments the reference count, and since the buffer is shared
+/// it is never modified in place; any mutation first copies the string into an owned buffer.
+///
+/// Note: A [`SharedStr`] can only be created on platforms that support atomic pointers, but the
+/// type itself always exists so [`Repr`] doesn't need to `cfg` each of its call sites.
+#[repr(C)]
+pub(crate) struct SharedStr {
+    pub(super) ptr: ptr::NonNull<u8>,
+    pub(super) len: usize,
+    #[allow(unused)]
+    discriminant: [u8; DISCRIMINANT_SIZE],
+}
+static_assertions::assert_eq_size!(SharedStr, Repr);
+static_assertions::assert_eq_align!(SharedStr, Repr);
+
+impl SharedStr {
+    /// Takes ownership of one strong reference of the provided `Arc<str>`.
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    pub(super) fn new(text: Arc<str>) -> Self {
+        let len = text.len();
+        let raw = Arc::into_raw(text) as *const u8;
+
+        let mut discriminant = [0; DISCRIMINANT_SIZE];
+        discriminant[DISCRIMINANT_SIZE - 1] = SHARED_MASK;
+
+        SharedStr {
+            // SAFETY: `Arc::into_raw` always returns a non-null pointer
+            ptr: unsafe { ptr::NonNull::new_unchecked(raw as *mut u8) },
+            len,
+            discriminant,
+        }
+    }
+
+    /// Converts the [`SharedStr`] back into the `Arc<str>` it was created from.
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    pub(super) fn into_arc(self) -> Arc<str> {
+        // SAFETY: `ptr` and `len` came from `Arc::into_raw`, and we owned one strong reference
+        unsafe { Arc::from_raw(raw_str(self.ptr, self.len)) }
+    }
+}
+
+/// Increments the strong count of the `Arc<str>` referenced by `ptr` and `len`.
+///
+/// # Safety
+/// * `ptr` and `len` must come from a live [`SharedStr`]
+#[inline]
+pub(super) unsafe fn increment_strong_count(ptr: ptr::NonNull<u8>, len: usize) {
+    cfg_if::cfg_if! {
+        if #[cfg(target_has_atomic = "ptr")] {
+            Arc::increment_strong_count(raw_str(ptr, len));
+        } else {
+            let _ = (ptr, len);
+            unreachable!("a `SharedStr` cannot be created without atomics");
+        }
+    }
+}
+
+/// Decrements the strong count of the `Arc<str>` referenced by `ptr` and `len`, freeing it if this
+/// was the last reference.
+///
+/// # Safety
+/// * `ptr` and `len` must come from a live [`SharedStr`], which must not be used afterwards
+#[inline]
+pub(super) unsafe fn decrement_strong_count(ptr: ptr::NonNull<u8>, len: usize) {
+    cfg_if::cfg_if! {
+        if #[cfg(target_has_atomic = "ptr")] {
+            Arc::decrement_strong_count(raw_str(ptr, len));
+        } else {
+            let _ = (ptr, len);
+            unreachable!("a `SharedStr` cannot be created without atomics");
+        }
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+#[inline(always)]
+fn raw_str(ptr: ptr::NonNull<u8>, len: usize) -> *const str {
+    ptr::slice_from_raw_parts(ptr.as_ptr() as *const u8, len) as *const str
+}
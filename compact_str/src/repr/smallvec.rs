@@ -8,8 +8,8 @@ impl Repr {
     /// Note: both for the inlined case and the heap case, the buffers are re-used
     #[inline]
     pub(crate) fn into_bytes(self) -> SmallVec<[u8; MAX_SIZE]> {
-        if let Some(s) = self.as_static_str() {
-            SmallVec::from(s.as_bytes())
+        if self.is_immutable() {
+            SmallVec::from(self.as_slice())
         } else if self.is_heap_allocated() {
             let string = self.into_string();
             let bytes = string.into_bytes();
@@ -21,21 +21,35 @@ static_assertions::assert_eq_size!(&'static str, (*const u8, usize));
 impl StaticStr {
     #[inline]
     pub(crate) const fn new(text: &'static str) -> Self {
+        Self::new_bytes(text.as_bytes())
+    }
+
+    /// Create a [`StaticStr`] that references arbitrary bytes.
+    ///
+    /// Note: The returned value must only be read back via [`StaticStr::get_bytes`] unless `bytes`
+    /// is valid UTF-8.
+    #[inline]
+    pub(crate) const fn new_bytes(bytes: &'static [u8]) -> Self {
         let mut discriminant = [0; DISCRIMINANT_SIZE];
         discriminant[DISCRIMINANT_SIZE - 1] = STATIC_STR_MASK;
 
         Self {
-            // SAFETY: `&'static str` must have a non-null, properly aligned
+            // SAFETY: `&'static [u8]` must have a non-null, properly aligned
             // address
-            ptr: unsafe { ptr::NonNull::new_unchecked(text.as_ptr() as *mut _) },
-            len: text.len(),
+            ptr: unsafe { ptr::NonNull::new_unchecked(bytes.as_ptr() as *mut _) },
+            len: bytes.len(),
             discriminant,
         }
     }
 
     pub(super) const fn get_text(&self) -> &'static str {
         // SAFETY: `StaticStr` invariants requires it to be a valid str
-        unsafe { str::from_utf8_unchecked(slice::from_raw_parts(self.ptr.as_ptr(), self.len)) }
+        unsafe { str::from_utf8_unchecked(self.get_bytes()) }
+    }
+
+    pub(super) const fn get_bytes(&self) -> &'static [u8] {
+        // SAFETY: `ptr` and `len` were created from a `&'static [u8]`
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
     }
 
     /// # Safety
@@ -44,4 +58,10 @@ impl StaticStr {
     pub(super) unsafe fn set_len(&mut self, len: usize) {
         *self = Self::new(&self.get_text()[..len]);
     }
+
+    /// # Safety
+    /// * `len` must be <= `self.get_bytes().len()`
+    pub(super) unsafe fn set_bytes_len(&mut self, len: usize) {
+        *self = Self::new_bytes(self.get_bytes().get_unchecked(..len));
+    }
 }
@@ -0,0 +1,135 @@
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::CompactString;
+
+/// Sorts a slice of [`CompactString`]s, faster than [`slice::sort`] for large slices.
+///
+/// Comparing two heap allocated strings means following two pointers, which is slow once the
+/// strings don't fit in the cache anymore. Instead we first sort by [`CompactString::sort_key`],
+/// which we read once per string, and only compare the strings themselves when their first 16
+/// bytes are equal. For inline strings reading the key doesn't follow any pointers at all.
+///
+/// The sort is unstable, but equal strings are indistinguishable anyway. It allocates a buffer
+/// of 40 bytes per string on 64-bit targets.
+///
+/// # Examples
+/// ```
+/// use compact_str::{sort_compact_strings, CompactString};
+///
+/// let mut strings = ["pear", "apple", "a very long string that is heap allocated", "fig"]
+///     .map(CompactString::new);
+/// sort_compact_strings(&mut strings);
+/// assert_eq!(
+///     strings,
+///     ["a very long string that is heap allocated", "apple", "fig", "pear"]
+/// );
+/// ```
+pub fn sort_compact_strings(strings: &mut [CompactString]) {
+    if strings.len() <= 1 {
+        return;
+    }
+
+    // move the strings next to their keys, so the tie breaks don't have to look them up. The key
+    // is split into two words, since a `u128` would be 16-byte aligned and pad every entry to 48
+    // bytes, they still compare in the same order.
+    let mut keyed: Vec<([u64; 2], CompactString)> = strings
+        .iter_mut()
+        .map(|string| {
+            let key = string.sort_key();
+            ([(key >> 64) as u64, key as u64], mem::take(string))
+        })
+        .collect();
+
+    // the keys are only integers, so this doesn't touch the heap
+    keyed.sort_unstable_by_key(|(key, _)| *key);
+
+    // then sort the strings that share a key, they can only differ after the first 16 bytes
+    let mut start = 0;
+    while start < keyed.len() {
+        let key = keyed[start].0;
+        let end = keyed[start..]
+            .iter()
+            .position(|(other, _)| *other != key)
+            .map_or(keyed.len(), |len| start + len);
+        if end - start > 1 {
+            keyed[start..end].sort_unstable_by(|(_, a), (_, b)| a.cmp(b));
+        }
+        start = end;
+    }
+
+    for (string, (_, sorted)) in strings.iter_mut().zip(keyed) {
+        *string = sorted;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use super::sort_compact_strings;
+    use crate::CompactString;
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_sort_compact_strings(
+        #[strategy(proptest::collection::vec("(https://)?[ab\u{0}é]{0,40}", 0..64))] strings: Vec<
+            String,
+        >,
+    ) {
+        let mut compacts: Vec<CompactString> = strings.iter().map(CompactString::new).collect();
+        sort_compact_strings(&mut compacts);
+
+        let mut expected = strings;
+        expected.sort();
+        prop_assert_eq!(compacts, expected);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_sort_key_order(
+        #[strategy("[ab\u{0}é]{0,20}")] a: String,
+        #[strategy("[ab\u{0}é]{0,20}")] b: String,
+    ) {
+        let (a, b) = (CompactString::new(a), CompactString::new(b));
+        if a < b {
+            prop_assert!(a.sort_key() <= b.sort_key());
+            prop_assert!(a.prefix_u64() <= b.prefix_u64());
+        }
+
+        // the key doesn't depend on where the string is stored
+        let mut heap = CompactString::with_capacity(64);
+        heap.push_str(&a);
+        prop_assert_eq!(heap.sort_key(), a.sort_key());
+    }
+
+    #[test]
+    fn test_sort_key() {
+        let compact = CompactString::new("0123456789abcdefXYZ");
+        assert_eq!(
+            compact.sort_key(),
+            u128::from_be_bytes(*b"0123456789abcdef")
+        );
+        assert_eq!(compact.prefix_u64(), u64::from_be_bytes(*b"01234567"));
+        assert_eq!(CompactString::default().sort_key(), 0);
+
+        // stale bytes from truncating don't leak into the key
+        let mut truncated = CompactString::new("abcdefgh");
+        truncated.truncate(2);
+        assert_eq!(
+            truncated.prefix_u64(),
+            u64::from_be_bytes(*b"ab\0\0\0\0\0\0")
+        );
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_keyed_size() {
+        // the buffer size that's documented on `sort_compact_strings`
+        assert_eq!(core::mem::size_of::<([u64; 2], CompactString)>(), 40);
+    }
+}
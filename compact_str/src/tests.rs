@@ -10,7 +10,10 @@ use proptest::prelude::*;
 use test_case::test_case;
 use test_strategy::proptest;
 
-use crate::{format_compact, CompactString, ToCompactString};
+use crate::{
+    format_compact, format_compact_exact, try_format_compact_exact, CompactString,
+    CompactStringExt, IntFormat, ToCompactString, ToCompactStringError,
+};
 
 #[cfg(target_pointer_width = "64")]
 const MAX_SIZE: usize = 24;
@@ -260,6 +263,78 @@ fn proptest_extend_chars_allocated_properly(
     assert_allocated_properly(&compact);
 }
 
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn proptest_trim_in_place(#[strategy(rand_unicode())] s: String, pad: u8) {
+    let padded = format!("{0}{s}{0}", " ".repeat(pad as usize % 8));
+
+    let check = |trim: fn(&mut CompactString), expected: &str| -> Result<(), TestCaseError> {
+        let mut compact = CompactString::new(&padded);
+        let was_heap = compact.is_heap_allocated();
+        let capacity = compact.capacity();
+
+        trim(&mut compact);
+        prop_assert_eq!(&compact, expected);
+        if was_heap && expected.len() <= MAX_SIZE {
+            // heap allocated strings that now fit are moved inline
+            prop_assert!(!compact.is_heap_allocated());
+        } else {
+            prop_assert_eq!(compact.is_heap_allocated(), was_heap);
+            prop_assert_eq!(compact.capacity(), capacity);
+        }
+        Ok(())
+    };
+
+    check(CompactString::trim_in_place, padded.trim())?;
+    check(CompactString::trim_start_in_place, padded.trim_start())?;
+    check(CompactString::trim_end_in_place, padded.trim_end())?;
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn proptest_strip_in_place(
+    #[strategy(rand_unicode())] s: String,
+    #[strategy(rand_unicode())] affix: String,
+) {
+    let text = format!("{affix}{s}{affix}");
+
+    let mut compact = CompactString::new(&text);
+    let capacity = compact.capacity();
+    prop_assert!(compact.strip_prefix_in_place(&affix));
+    prop_assert_eq!(&compact, text.strip_prefix(affix.as_str()).unwrap());
+    prop_assert!(compact.strip_suffix_in_place(&affix));
+    prop_assert_eq!(&compact, &s);
+    if s.len() <= MAX_SIZE {
+        prop_assert!(!compact.is_heap_allocated());
+    } else {
+        prop_assert_eq!(compact.capacity(), capacity);
+    }
+
+    let mut compact = CompactString::new(&s);
+    let stripped = compact.strip_prefix_in_place(&affix);
+    prop_assert_eq!(stripped, s.starts_with(affix.as_str()));
+    prop_assert_eq!(&compact, s.strip_prefix(affix.as_str()).unwrap_or(&s));
+}
+
+#[test]
+fn test_trim_in_place_static_and_shared() {
+    use alloc::sync::Arc;
+
+    let mut s = CompactString::const_new("   a static string that is long enough   ");
+    s.trim_in_place();
+    assert_eq!(s, "a static string that is long enough");
+    assert!(s.as_static_str().is_some());
+
+    let text = "   a shared string that is long enough   ";
+    let shared = CompactString::from(Arc::<str>::from(text));
+    let mut s = shared.clone();
+    assert!(s.strip_prefix_in_place("   a shared "));
+    s.trim_end_in_place();
+    assert_eq!(s, "string that is long enough");
+    assert!(!s.is_shared());
+    assert_eq!(shared, text);
+}
+
 #[proptest]
 #[cfg_attr(miri, ignore)]
 fn proptest_truncate(#[strategy(rand_unicode())] mut control: String, val: u8) {
@@ -729,6 +804,27 @@ fn test_eq_operator() {
     test_impl!(y, x);
 }
 
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn proptest_eq_inline(
+    #[strategy(rand_unicode())] a: String,
+    #[strategy(rand_unicode())] b: String,
+    #[strategy(0..32usize)] truncate: usize,
+) {
+    // truncating leaves stale bytes behind in the inline buffer, which must be ignored
+    let mut truncated = CompactString::new(&a);
+    let truncate = (0..=truncate.min(a.len()))
+        .rev()
+        .find(|&i| a.is_char_boundary(i))
+        .unwrap_or_default();
+    truncated.truncate(truncate);
+    let prefix = CompactString::new(&a[..truncate]);
+
+    prop_assert_eq!(&truncated, &prefix);
+    prop_assert_eq!(CompactString::new(&a) == CompactString::new(&b), a == b);
+    prop_assert_eq!(truncated == CompactString::new(&b), a[..truncate] == b);
+}
+
 #[test]
 fn test_u8_to_compact_string() {
     let vals = [u8::MIN, 1, 42, u8::MAX - 2, u8::MAX - 1, u8::MAX];
@@ -970,12 +1066,130 @@ fn test_bool_to_compact_string() {
     assert!(!c.is_heap_allocated());
 }
 
+#[test]
+fn test_str_pointers_to_compact_string() {
+    use alloc::rc::Rc;
+    use alloc::sync::Arc;
+
+    for text in ["", "hello", "a string that is too long to be inlined"] {
+        let boxed: Box<str> = text.into();
+        let rc: Rc<str> = text.into();
+        let arc: Arc<str> = text.into();
+
+        for compact in [
+            boxed.to_compact_string(),
+            rc.to_compact_string(),
+            arc.to_compact_string(),
+        ] {
+            assert_eq!(compact, text);
+            assert_eq!(compact.is_heap_allocated(), text.len() > MAX_SIZE);
+        }
+    }
+}
+
+#[test]
+fn test_cow_to_compact_string() {
+    for text in ["", "hello", "a string that is too long to be inlined"] {
+        let owned = String::from(text);
+        let borrowed: Cow<'_, str> = Cow::Borrowed(owned.as_str());
+        let static_str: Cow<'static, str> = Cow::Borrowed(text);
+        let owned: Cow<'_, str> = Cow::Owned(owned.clone());
+
+        for compact in [
+            borrowed.to_compact_string(),
+            static_str.to_compact_string(),
+            owned.to_compact_string(),
+        ] {
+            assert_eq!(compact, text);
+            assert_eq!(compact.is_heap_allocated(), text.len() > MAX_SIZE);
+            assert_eq!(compact.as_static_str(), None);
+        }
+    }
+}
+
+#[test]
+fn test_net_to_compact_string() {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    let ipv4 = IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1));
+    assert_eq!(ipv4.to_compact_string(), "192.168.0.1");
+    assert!(!ipv4.to_compact_string().is_heap_allocated());
+
+    let ipv6 = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+    assert_eq!(ipv6.to_compact_string(), "2001:db8::1");
+
+    let socket = SocketAddr::new(ipv6.into(), 8080);
+    assert_eq!(socket.to_compact_string(), "[2001:db8::1]:8080");
+    assert_eq!(socket.to_compact_string(), socket.to_string());
+}
+
 macro_rules! assert_int_MAX_to_compact_string {
     ($int: ty) => {
         assert_eq!(&*<$int>::MAX.to_string(), &*<$int>::MAX.to_compact_string());
     };
 }
 
+#[test]
+fn test_const_from_int() {
+    macro_rules! assert_const_from_int {
+        ($int:ty, $f:ident) => {
+            for x in [<$int>::MIN, 0, 1, 42, <$int>::MAX - 1, <$int>::MAX] {
+                let compact = CompactString::$f(x);
+                assert_eq!(compact, x.to_string());
+                assert!(!compact.is_heap_allocated());
+            }
+        };
+    }
+
+    assert_const_from_int!(u8, const_from_u8);
+    assert_const_from_int!(i8, const_from_i8);
+    assert_const_from_int!(u16, const_from_u16);
+    assert_const_from_int!(i16, const_from_i16);
+    assert_const_from_int!(u32, const_from_u32);
+    assert_const_from_int!(i32, const_from_i32);
+
+    #[cfg(target_pointer_width = "64")]
+    {
+        assert_const_from_int!(u64, const_from_u64);
+        assert_const_from_int!(i64, const_from_i64);
+        assert_const_from_int!(usize, const_from_usize);
+        assert_const_from_int!(isize, const_from_isize);
+    }
+
+    const LABELS: [CompactString; 3] = [
+        CompactString::const_from_u64(7),
+        CompactString::const_from_i64(-1_000_000),
+        CompactString::const_from_u32(u32::MAX),
+    ];
+    assert_eq!(LABELS, ["7", "-1000000", "4294967295"]);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn proptest_from_int_with(x: i64, y: u128, #[strategy(0..40usize)] width: usize) {
+    prop_assert_eq!(CompactString::from_hex_lower(y), format!("{y:x}"));
+    prop_assert_eq!(CompactString::from_hex_upper(y), format!("{y:X}"));
+    prop_assert_eq!(CompactString::from_int_radix(y, 8), format!("{y:o}"));
+    prop_assert_eq!(CompactString::from_int_radix(y, 2), format!("{y:b}"));
+
+    let sign = if x < 0 { "-" } else { "" };
+    let magnitude = x.unsigned_abs();
+    prop_assert_eq!(
+        CompactString::from_int_radix(x, 16),
+        format!("{sign}{magnitude:x}")
+    );
+
+    let zero_padded = CompactString::from_int_with(x, IntFormat::new().zero_pad(width));
+    prop_assert_eq!(&zero_padded, &format!("{x:0width$}"));
+    prop_assert_eq!(
+        zero_padded.is_heap_allocated(),
+        zero_padded.len() > MAX_SIZE
+    );
+
+    let space_padded = CompactString::from_int_with(x, IntFormat::new().space_pad(width));
+    prop_assert_eq!(space_padded, format!("{x:width$}"));
+}
+
 #[test]
 fn test_to_compact_string() {
     // Test specialisation for bool, char and String
@@ -1048,6 +1262,72 @@ fn test_to_compact_string() {
     );
 }
 
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn proptest_format_compact_exact(#[strategy(rand_unicode())] s: String, int: i64, float: f64) {
+    let exact = format_compact_exact!("{s}: {int} {float:.3}");
+    let expected = format!("{s}: {int} {float:.3}");
+    prop_assert_eq!(&exact, &expected);
+    if exact.is_heap_allocated() {
+        // heap buffers have a minimum size
+        let min = MAX_SIZE + core::mem::size_of::<usize>();
+        prop_assert_eq!(exact.capacity(), expected.len().max(min));
+    } else {
+        prop_assert!(expected.len() <= MAX_SIZE);
+    }
+}
+
+#[test]
+fn test_format_compact_exact() {
+    let long = "a string that is too long to be inlined";
+
+    // the constant case doesn't format, or allocate
+    let constant = format_compact_exact!("a string that is too long to be inlined");
+    assert_eq!(constant.as_static_str(), Some(long));
+    assert_eq!(format_compact_exact!(""), "");
+
+    let inline = format_compact_exact!("{}-{}", 1, 2);
+    assert_eq!(inline, "1-2");
+    assert!(!inline.is_heap_allocated());
+
+    let heap = format_compact_exact!("{long}{}{:>5}", '!', 7);
+    assert_eq!(heap, format!("{long}!    7"));
+    assert_eq!(heap.capacity(), heap.len());
+
+    assert_eq!(try_format_compact_exact!("{long}").unwrap(), long);
+}
+
+#[test]
+fn test_format_compact_exact_errors() {
+    struct Failing;
+    impl core::fmt::Display for Failing {
+        fn fmt(&self, _: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            Err(core::fmt::Error)
+        }
+    }
+    assert!(matches!(
+        try_format_compact_exact!("{}", Failing),
+        Err(ToCompactStringError::Fmt(_))
+    ));
+
+    // a `Display` implementation that writes more the second time around
+    struct Growing(core::cell::Cell<usize>);
+    impl core::fmt::Display for Growing {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            self.0.set(self.0.get() + 1);
+            for _ in 0..self.0.get() {
+                f.write_str("0123456789abcdef")?;
+            }
+            Ok(())
+        }
+    }
+    let growing = Growing(core::cell::Cell::new(0));
+    assert_eq!(
+        format_compact_exact!("{growing}"),
+        "0123456789abcdef0123456789abcdef"
+    );
+}
+
 #[test]
 fn test_into_string_large_string_with_excess_capacity() {
     let mut string = String::with_capacity(128);
@@ -2066,15 +2346,37 @@ fn test_from_string_buffer_inlines_on_clone() {
     assert!(!b.is_heap_allocated());
 }
 
-// With debug assertions enabled the invocation will panic if you try to allocate more memory than
-// the system even has.
-#[cfg(not(debug_assertions))]
 #[cfg(target_pointer_width = "64")]
 #[test]
 #[should_panic = "Cannot allocate memory to hold CompactString"]
 fn test_alloc_excessively_long_string() {
-    // 2**56 - 2 bytes, the maximum number `Capacity` can hold
-    std::hint::black_box(CompactString::with_capacity((1 << 56) - 2));
+    // 2**55 bytes, one more than the maximum number `Capacity` can hold
+    std::hint::black_box(CompactString::with_capacity(1 << 55));
+}
+
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn test_capacity_larger_than_max_value() {
+    use alloc::sync::Arc;
+
+    // 2**55 bytes, one more than the maximum number `Capacity` can hold
+    let too_large = 1 << 55;
+    assert!(CompactString::try_with_capacity(too_large).is_err());
+    assert!(CompactString::try_with_capacity((1 << 56) - 2).is_err());
+
+    let allocator = Arc::new(TrackingAllocator::new(usize::MAX));
+    assert!(CompactString::try_with_capacity_in(too_large, allocator.clone()).is_err());
+
+    let mut s = CompactString::new("a string that is long enough to be heap allocated");
+    assert!(s.try_reserve(too_large).is_err());
+    assert_eq!(s, "a string that is long enough to be heap allocated");
+
+    let mut s = CompactString::new_in(
+        "a string that is long enough to be heap allocated",
+        allocator,
+    );
+    assert!(s.try_reserve(too_large).is_err());
+    assert_eq!(s, "a string that is long enough to be heap allocated");
 }
 
 // This feature was enabled by <https://github.com/rust-lang/rust/pull/94075> which was first
@@ -2277,3 +2579,397 @@ fn proptest_drain_matches_std(#[strategy(rand_unicode())] control: String, lo: u
     std_string.drain(start..end);
     prop_assert_eq!(compact.as_str(), std_string.as_str());
 }
+
+#[test]
+fn test_shared_clone_is_refcounted() {
+    use alloc::sync::Arc;
+
+    let arc: Arc<str> = Arc::from("this string is long enough to not be inlined");
+    let shared = CompactString::from(Arc::clone(&arc));
+    assert!(shared.is_shared());
+    assert!(shared.is_heap_allocated());
+    assert_eq!(shared.as_ptr(), arc.as_ptr());
+    assert_eq!(Arc::strong_count(&arc), 2);
+
+    let clone = shared.clone();
+    assert_eq!(clone.as_ptr(), arc.as_ptr());
+    assert_eq!(Arc::strong_count(&arc), 3);
+
+    drop(shared);
+    assert_eq!(Arc::strong_count(&arc), 2);
+
+    // converting back into an `Arc<str>` hands over our reference
+    let back: Arc<str> = clone.into();
+    assert!(Arc::ptr_eq(&back, &arc));
+    assert_eq!(Arc::strong_count(&arc), 2);
+}
+
+#[test]
+fn test_shared_short_arc_is_inlined() {
+    use alloc::sync::Arc;
+
+    let arc: Arc<str> = Arc::from("short");
+    let compact = CompactString::from(Arc::clone(&arc));
+    assert!(!compact.is_shared());
+    assert!(!compact.is_heap_allocated());
+    assert_eq!(Arc::strong_count(&arc), 1);
+}
+
+#[test]
+fn test_shared_copy_on_write() {
+    let og = CompactString::from("this string is long enough to not be inlined").into_shared();
+    assert!(og.is_shared());
+
+    let mut pushed = og.clone();
+    pushed.push_str(" and more");
+    assert!(!pushed.is_shared());
+    assert_eq!(
+        pushed,
+        "this string is long enough to not be inlined and more"
+    );
+
+    let mut truncated = og.clone();
+    truncated.truncate(30);
+    assert!(!truncated.is_shared());
+    assert_eq!(truncated, "this string is long enough to ");
+
+    let mut upper = og.clone();
+    upper.make_ascii_uppercase();
+    assert_eq!(upper, "THIS STRING IS LONG ENOUGH TO NOT BE INLINED");
+
+    let mut cleared = og.clone();
+    cleared.clear();
+    assert!(cleared.is_empty());
+    assert!(!cleared.is_heap_allocated());
+
+    let mut clone_from = CompactString::new("a heap allocated string that gets overwritten");
+    clone_from.clone_from(&og);
+    assert!(clone_from.is_shared());
+    assert_eq!(clone_from.as_ptr(), og.as_ptr());
+
+    assert_eq!(og, "this string is long enough to not be inlined");
+    assert_eq!(og.capacity(), og.len());
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn proptest_shared_roundtrip(#[strategy(rand_unicode())] s: String) {
+    let shared = CompactString::new(&s).into_shared();
+    prop_assert_eq!(shared.is_shared(), s.len() > MAX_SIZE);
+    prop_assert_eq!(&shared, &s);
+
+    let clone = shared.clone();
+    prop_assert_eq!(&clone, &s);
+    prop_assert_eq!(clone.into_string(), s.clone());
+
+    let mut shared = shared;
+    shared.push('a');
+    prop_assert!(!shared.is_shared());
+    prop_assert_eq!(shared, s + "a");
+}
+
+#[test]
+fn test_shared_option_niche() {
+    assert_eq!(
+        core::mem::size_of::<Option<CompactString>>(),
+        core::mem::size_of::<CompactString>()
+    );
+    let shared =
+        Some(CompactString::from("this string is long enough to not be inlined").into_shared());
+    assert!(matches!(shared, Some(ref s) if s.is_shared()));
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn proptest_replace_matches_std(
+    #[strategy("[aβ]{0,40}")] s: String,
+    #[strategy("[aβ]{0,3}")] from: String,
+    #[strategy("[aβc]{0,4}")] to: String,
+    #[strategy(0..5usize)] count: usize,
+) {
+    let compact = CompactString::new(&s);
+
+    let replaced = compact.replace(&from, &to);
+    prop_assert_eq!(&replaced, &s.replace(&from, &to));
+    assert_allocated_properly(&replaced);
+
+    let replaced = compact.replacen(&from, &to, count);
+    prop_assert_eq!(&replaced, &s.replacen(&from, &to, count));
+    assert_allocated_properly(&replaced);
+
+    let mut in_place = compact.clone();
+    let capacity = in_place.capacity();
+    in_place.replace_in_place(&from, &to);
+    prop_assert_eq!(&in_place, &s.replace(&from, &to));
+    if !from.is_empty() && to.len() <= from.len() {
+        prop_assert_eq!(in_place.capacity(), capacity);
+    }
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn proptest_replace_char_matches_std(#[strategy(rand_unicode())] s: String, from: char, to: char) {
+    // make sure we have at least one match
+    let s = format!("{s}{from}");
+    let compact = CompactString::new(&s);
+
+    let mut buf = [0; 4];
+    let expected = s.replace(from, to.encode_utf8(&mut buf));
+    prop_assert_eq!(compact.replace_char(from, to), expected);
+}
+
+#[test]
+fn test_replace_static_and_inline() {
+    let s = CompactString::const_new("a static string that's long enough to not be inlined");
+
+    // replacing shrinks the result enough to be inlined
+    let replaced = s.replace("a static string that's long enough to not be ", "");
+    assert_eq!(replaced, "inlined");
+    assert!(!replaced.is_heap_allocated());
+
+    // no matches keeps the static string
+    let mut in_place = s.clone();
+    in_place.replace_in_place("missing", "");
+    assert!(in_place.as_static_str().is_some());
+
+    in_place.replace_in_place("string", "str");
+    assert!(in_place.as_static_str().is_none());
+    assert_eq!(
+        in_place,
+        "a static str that's long enough to not be inlined"
+    );
+}
+
+/// An [`crate::Allocator`] that tracks how many bytes it has outstanding, and fails to allocate
+/// once `limit` would be exceeded.
+struct TrackingAllocator {
+    outstanding: core::sync::atomic::AtomicUsize,
+    limit: usize,
+}
+
+impl TrackingAllocator {
+    const fn new(limit: usize) -> Self {
+        TrackingAllocator {
+            outstanding: core::sync::atomic::AtomicUsize::new(0),
+            limit,
+        }
+    }
+
+    fn outstanding(&self) -> usize {
+        self.outstanding.load(core::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+unsafe impl crate::Allocator for TrackingAllocator {
+    fn allocate(
+        &self,
+        layout: core::alloc::Layout,
+    ) -> Result<core::ptr::NonNull<u8>, crate::AllocError> {
+        use core::sync::atomic::Ordering;
+
+        let outstanding = self.outstanding.fetch_add(layout.size(), Ordering::SeqCst);
+        if outstanding + layout.size() > self.limit {
+            self.outstanding.fetch_sub(layout.size(), Ordering::SeqCst);
+            return Err(crate::AllocError);
+        }
+        // SAFETY: Our heap buffers never have a zero sized layout
+        core::ptr::NonNull::new(unsafe { alloc::alloc::alloc(layout) }).ok_or(crate::AllocError)
+    }
+
+    unsafe fn deallocate(&self, ptr: core::ptr::NonNull<u8>, layout: core::alloc::Layout) {
+        self.outstanding
+            .fetch_sub(layout.size(), core::sync::atomic::Ordering::SeqCst);
+        alloc::alloc::dealloc(ptr.as_ptr(), layout)
+    }
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn proptest_allocator_roundtrip(
+    #[strategy(rand_unicode())] s: String,
+    #[strategy(rand_unicode())] extra: String,
+) {
+    use alloc::sync::Arc;
+
+    let allocator = Arc::new(TrackingAllocator::new(usize::MAX));
+
+    let mut compact = CompactString::new_in(&s, allocator.clone());
+    prop_assert_eq!(&compact, &s);
+    prop_assert_eq!(compact.allocator().is_some(), s.len() > MAX_SIZE);
+
+    // clones are allocated by the same allocator
+    let clone = compact.clone();
+    prop_assert_eq!(&clone, &s);
+    prop_assert_eq!(clone.allocator().is_some(), s.len() > MAX_SIZE);
+    let mut clone_into = CompactString::new("a string on the global heap, not inlined");
+    clone_into.clone_from(&compact);
+    prop_assert_eq!(&clone_into, &s);
+    prop_assert_eq!(clone_into.allocator().is_some(), s.len() > MAX_SIZE);
+    drop((clone, clone_into));
+
+    compact.push_str(&extra);
+    compact.reserve(100);
+    prop_assert_eq!(&compact, &format!("{s}{extra}"));
+    prop_assert!(compact.capacity() >= compact.len() + 100);
+
+    // strings that started inline grow into the global allocator
+    prop_assert_eq!(compact.allocator().is_some(), s.len() > MAX_SIZE);
+
+    compact.shrink_to_fit();
+    prop_assert_eq!(
+        compact.allocator().is_some(),
+        s.len() > MAX_SIZE && compact.len() > MAX_SIZE
+    );
+
+    prop_assert_eq!(compact.clone().into_string(), format!("{s}{extra}"));
+    drop(compact);
+    prop_assert_eq!(allocator.outstanding(), 0);
+    // every string released its handle to the allocator
+    prop_assert_eq!(Arc::strong_count(&allocator), 1);
+}
+
+#[test]
+fn test_allocator_errors() {
+    use alloc::sync::Arc;
+
+    let allocator = Arc::new(TrackingAllocator::new(256));
+    let long = "a string that is long enough to be heap allocated";
+
+    assert!(CompactString::try_new_in("short", allocator.clone()).is_ok());
+    assert!(CompactString::try_with_capacity_in(1024, allocator.clone()).is_err());
+
+    let mut s = CompactString::try_new_in(long, allocator.clone()).unwrap();
+    assert!(s.allocator().is_some());
+    assert!(s.try_reserve(1024).is_err());
+    assert_eq!(s, long);
+    assert!(s.try_reserve(64).is_ok());
+    assert!(s.allocator().is_some());
+
+    drop(s);
+    assert_eq!(allocator.outstanding(), 0);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn proptest_try_variants(
+    #[strategy(rand_unicode())] s: String,
+    #[strategy(rand_unicode())] other: String,
+    #[strategy(0..4usize)] n: usize,
+) {
+    let compact = CompactString::new(&s);
+
+    let mut a = compact.clone();
+    let mut b = compact.clone();
+    a.push_str(&other);
+    b.try_push_str(&other).unwrap();
+    prop_assert_eq!(&a, &b);
+
+    a.insert_str(0, &other);
+    b.try_insert_str(0, &other).unwrap();
+    prop_assert_eq!(&a, &b);
+
+    a.insert(0, 'ä');
+    b.try_insert(0, 'ä').unwrap();
+    a.push('🦀');
+    b.try_push('🦀').unwrap();
+    prop_assert_eq!(&a, &b);
+
+    let end = a.len() - '🦀'.len_utf8();
+    a.replace_range(2..end, &s);
+    b.try_replace_range(2..end, &s).unwrap();
+    prop_assert_eq!(&a, &b);
+
+    a.extend(other.chars());
+    b.try_extend_chars(other.chars()).unwrap();
+    a.extend([s.as_str(), other.as_str()]);
+    b.try_extend([&s, &other]).unwrap();
+    prop_assert_eq!(&a, &b);
+
+    prop_assert_eq!(compact.repeat(n), compact.try_repeat(n).unwrap());
+    prop_assert_eq!(compact.to_lowercase(), compact.try_to_lowercase().unwrap());
+    prop_assert_eq!(compact.to_uppercase(), compact.try_to_uppercase().unwrap());
+    prop_assert_eq!(&compact, &compact.try_clone().unwrap());
+    prop_assert_eq!(
+        &compact,
+        &CompactString::try_from_utf8(s.as_bytes()).unwrap()
+    );
+    prop_assert_eq!(
+        [&s, &other].concat_compact(),
+        [&s, &other].try_concat_compact().unwrap()
+    );
+    prop_assert_eq!(
+        [&s, &other].join_compact("-"),
+        [&s, &other].try_join_compact("-").unwrap()
+    );
+}
+
+#[test]
+fn test_try_variants_errors() {
+    use alloc::sync::Arc;
+
+    let allocator = Arc::new(TrackingAllocator::new(256));
+    let long = "a string that is long enough to be heap allocated";
+    let huge = "a".repeat(1024);
+
+    let mut s = CompactString::try_new_in(long, allocator.clone()).unwrap();
+    assert!(s.try_push_str(&huge).is_err());
+    assert!(s.try_insert_str(0, &huge).is_err());
+    assert!(s.try_replace_range(..1, &huge).is_err());
+    assert!(s.try_extend([&huge]).is_err());
+    assert_eq!(s, long);
+
+    // the error is reported after every item that did fit was appended
+    assert!(s.try_extend_chars(huge.chars()).is_err());
+    assert!(s.starts_with(long) && s.len() < long.len() + huge.len());
+    drop(s);
+
+    let mut s = CompactString::try_new_in(long, allocator.clone()).unwrap();
+    assert!(s.try_extend(["1", huge.as_str(), "2"]).is_err());
+    assert_eq!(s, format!("{long}1"));
+    drop(s);
+    assert_eq!(allocator.outstanding(), 0);
+
+    assert!(CompactString::new("abc").try_repeat(usize::MAX).is_err());
+    assert!(matches!(
+        CompactString::try_from_utf8([255, 255]),
+        Err(ToCompactStringError::Utf8(_))
+    ));
+}
+
+#[test]
+fn test_try_variants_static() {
+    let mut s = CompactString::const_new("a string that is long enough to be heap allocated");
+    s.try_replace_range(0..1, "A").unwrap();
+    assert_eq!(s, "A string that is long enough to be heap allocated");
+    assert!(s.as_static_str().is_none());
+
+    let mut s = CompactString::const_new("hello world");
+    s.try_extend_chars("!?".chars()).unwrap();
+    assert_eq!(s, "hello world!?");
+}
+
+#[test]
+fn test_try_clone_shared_and_allocator() {
+    use alloc::sync::Arc;
+
+    let shared = CompactString::from("this string is long enough to not be inlined").into_shared();
+    let clone = shared.try_clone().unwrap();
+    assert!(clone.is_shared());
+    assert_eq!(clone.as_ptr(), shared.as_ptr());
+
+    let allocator = Arc::new(TrackingAllocator::new(usize::MAX));
+    let compact = CompactString::new_in(
+        "a string that is long enough to be heap allocated",
+        allocator.clone(),
+    );
+    let clone = compact.try_clone().unwrap();
+    assert_eq!(clone, compact);
+    assert_ne!(clone.as_ptr(), compact.as_ptr());
+    assert!(Arc::ptr_eq(
+        clone.allocator().unwrap(),
+        compact.allocator().unwrap()
+    ));
+
+    drop((compact, clone));
+    assert_eq!(allocator.outstanding(), 0);
+}
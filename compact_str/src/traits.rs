@@ -1,11 +1,17 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
 use alloc::string::String;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
 use core::fmt::{self, Write};
-use core::num;
+use core::iter::FusedIterator;
+use core::{num, str};
 
 use castaway::{match_type, LifetimeFree};
 
 use super::repr::{IntoRepr, Repr};
-use crate::{CompactString, ToCompactStringError, UnwrapWithMsg};
+use crate::{CompactString, ReserveError, ToCompactStringError, UnwrapWithMsg};
 
 /// A trait for converting a value to a `CompactString`.
 ///
@@ -82,9 +88,20 @@ impl ToCompactString for str {
 /// * `NonZeroU*`, `NonZeroI*`
 /// * `bool`
 /// * `char`
-/// * `String`, `CompactString`
+/// * `String`, `CompactString`, `Cow<str>`, `Box<str>`, `Rc<str>`
+/// * `Arc<str>`, on targets with atomic pointers
+/// * `IpAddr`, `Ipv4Addr`, `Ipv6Addr`, `SocketAddr`, `SocketAddrV4`, `SocketAddrV6`, with the
+///   `std` feature
+/// * `uuid::Uuid`, with the `uuid` feature
 /// * `f32`, `f64`
 ///     * For floats we use [`zmij`] crate which sometimes provides different formatting than [`std`]
+///
+/// Every other type goes through its [`fmt::Display`] implementation, including these, which
+/// can't be specialized:
+/// * `std::path::Display`, returned by `Path::display()`, doesn't give access to the path it
+///   wraps, so there's nothing to copy directly.
+/// * [`core::time::Duration`] only implements [`fmt::Debug`], so it doesn't implement
+///   [`ToCompactString`] at all.
 impl<T: fmt::Display> ToCompactString for T {
     #[inline]
     fn try_to_compact_string(&self) -> Result<CompactString, ToCompactStringError> {
@@ -119,17 +136,167 @@ impl<T: fmt::Display> ToCompactString for T {
             &num::NonZeroIsize as s => s.into_repr()?,
             &num::NonZeroU128 as s => s.into_repr()?,
             &num::NonZeroI128 as s => s.into_repr()?,
-            s => {
-                let mut c = CompactString::const_new("");
-                write!(c, "{}", s)?;
-                return Ok(c);
-            }
+            s => match_type!(Specialize::new(s), {
+                &Specialize<Box<str>> as s => Repr::new(&s.0)?,
+                &Specialize<Rc<str>> as s => Repr::new(&s.0)?,
+                &Specialize<Cow<'static, str>> as s => Repr::new(&s.0)?,
+                s => specialize_gated(s)?,
+            }),
         });
 
         Ok(CompactString(repr))
     }
 }
 
+/// Specializes the types that are only available on some targets, or from `std` and optional
+/// dependencies, and falls back to the [`fmt::Display`] implementation.
+#[inline(always)]
+fn specialize_gated<T: fmt::Display>(value: &Specialize<T>) -> Result<Repr, ToCompactStringError> {
+    #[cfg(target_has_atomic = "ptr")]
+    let value = match_type!(value, {
+        &Specialize<Arc<str>> as s => return Ok(Repr::new(&s.0)?),
+        s => s,
+    });
+    #[cfg(feature = "std")]
+    let value = match_type!(value, {
+        &Specialize<std::net::IpAddr> as s => return s.0.into_repr(),
+        &Specialize<std::net::Ipv4Addr> as s => return s.0.into_repr(),
+        &Specialize<std::net::Ipv6Addr> as s => return s.0.into_repr(),
+        &Specialize<std::net::SocketAddr> as s => return s.0.into_repr(),
+        &Specialize<std::net::SocketAddrV4> as s => return s.0.into_repr(),
+        &Specialize<std::net::SocketAddrV6> as s => return s.0.into_repr(),
+        s => s,
+    });
+    #[cfg(feature = "uuid")]
+    let value = match_type!(value, {
+        &Specialize<uuid::Uuid> as s => return s.0.into_repr(),
+        s => s,
+    });
+    Ok(write_display(&value.0)?.0)
+}
+
+/// Formats `value` with its [`fmt::Display`] implementation.
+fn write_display<T: fmt::Display + ?Sized>(
+    value: &T,
+) -> Result<CompactString, ToCompactStringError> {
+    let mut writer = TryWriter {
+        string: CompactString::const_new(""),
+        error: None,
+    };
+    if write!(writer, "{}", value).is_err() {
+        return Err(match writer.error {
+            Some(err) => err.into(),
+            None => fmt::Error.into(),
+        });
+    }
+    Ok(writer.string)
+}
+
+/// A wrapper that lets us specialize on foreign types that [`castaway`] can't tell are
+/// [`LifetimeFree`], since we can't implement the trait for them directly.
+#[repr(transparent)]
+struct Specialize<T: ?Sized>(T);
+
+impl<T: ?Sized> Specialize<T> {
+    #[inline(always)]
+    fn new(value: &T) -> &Specialize<T> {
+        // SAFETY: `Specialize` is `repr(transparent)`
+        unsafe { &*(value as *const T as *const Specialize<T>) }
+    }
+}
+
+// SAFETY: None of these types contain any lifetimes
+unsafe impl LifetimeFree for Specialize<Box<str>> {}
+unsafe impl LifetimeFree for Specialize<Rc<str>> {}
+#[cfg(target_has_atomic = "ptr")]
+unsafe impl LifetimeFree for Specialize<Arc<str>> {}
+// SAFETY: `castaway` ignores lifetimes when it compares types, so this matches a `Cow<'a, str>` of
+// any lifetime, which would be unsound if the `'static` lifetime escaped. We only ever copy the
+// text out of the reference we get back, while the original `Cow` is still borrowed.
+unsafe impl LifetimeFree for Specialize<Cow<'static, str>> {}
+#[cfg(feature = "std")]
+mod std_impls {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+    use super::{LifetimeFree, Specialize};
+
+    // SAFETY: None of these types contain any lifetimes
+    unsafe impl LifetimeFree for Specialize<IpAddr> {}
+    unsafe impl LifetimeFree for Specialize<Ipv4Addr> {}
+    unsafe impl LifetimeFree for Specialize<Ipv6Addr> {}
+    unsafe impl LifetimeFree for Specialize<SocketAddr> {}
+    unsafe impl LifetimeFree for Specialize<SocketAddrV4> {}
+    unsafe impl LifetimeFree for Specialize<SocketAddrV6> {}
+}
+
+// SAFETY: `Uuid` doesn't contain any lifetimes
+#[cfg(feature = "uuid")]
+unsafe impl LifetimeFree for Specialize<uuid::Uuid> {}
+
+/// A [`fmt::Write`] adapter that reports allocation failures instead of panicking.
+struct TryWriter {
+    string: CompactString,
+    error: Option<ReserveError>,
+}
+
+impl Write for TryWriter {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.string.try_push_str(s).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+/// A [`fmt::Write`] implementation that only counts how many bytes get written.
+struct LenWriter(usize);
+
+impl Write for LenWriter {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 = self.0.saturating_add(s.len());
+        Ok(())
+    }
+}
+
+/// Formats `args` into a [`CompactString`] of exactly the right capacity, for
+/// [`format_compact_exact!`](crate::format_compact_exact).
+#[doc(hidden)]
+#[inline]
+#[track_caller]
+pub fn format_exact(args: fmt::Arguments<'_>) -> CompactString {
+    try_format_exact(args).unwrap_with_msg()
+}
+
+/// Fallible version of [`format_exact`], for
+/// [`try_format_compact_exact!`](crate::try_format_compact_exact).
+#[doc(hidden)]
+pub fn try_format_exact(args: fmt::Arguments<'_>) -> Result<CompactString, ToCompactStringError> {
+    if let Some(s) = args.as_str() {
+        // no arguments to format, so there's nothing to measure, and `const_new` doesn't copy
+        // strings that are too long to be inlined
+        return Ok(CompactString::const_new(s));
+    }
+
+    let mut len = LenWriter(0);
+    fmt::write(&mut len, args)?;
+
+    let mut writer = TryWriter {
+        string: CompactString::try_with_capacity(len.0)?,
+        error: None,
+    };
+    // if the `Display` implementations write something different the second time around, the
+    // string still grows as needed
+    if fmt::write(&mut writer, args).is_err() {
+        return Err(match writer.error {
+            Some(err) => err.into(),
+            None => fmt::Error.into(),
+        });
+    }
+    Ok(writer.string)
+}
+
 /// A trait that provides convenience methods for creating a [`CompactString`] from a collection of
 /// items. It is implemented for all types that can be converted into an iterator, and that iterator
 /// yields types that can be converted into a `str`.
@@ -178,6 +345,26 @@ pub trait CompactStringExt {
     /// assert_eq!(compact, "apples, oranges, bananas");
     /// ```
     fn join_compact<S: AsRef<str>>(self, separator: S) -> CompactString;
+
+    /// Fallible version of [`CompactStringExt::concat_compact()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return a [`ReserveError`].
+    fn try_concat_compact(self) -> Result<CompactString, ReserveError>;
+
+    /// Fallible version of [`CompactStringExt::join_compact()`]
+    ///
+    /// This method won't panic if the system is out-of-memory, but return a [`ReserveError`].
+    ///
+    /// # Example
+    /// ```
+    /// use compact_str::CompactStringExt;
+    ///
+    /// let fruits = vec!["apples", "oranges", "bananas"];
+    /// let compact = fruits.try_join_compact(", ").expect("out of memory");
+    ///
+    /// assert_eq!(compact, "apples, oranges, bananas");
+    /// ```
+    fn try_join_compact<S: AsRef<str>>(self, separator: S) -> Result<CompactString, ReserveError>;
 }
 
 impl<I, C> CompactStringExt for C
@@ -186,30 +373,194 @@ where
     C: IntoIterator<Item = I>,
 {
     fn concat_compact(self) -> CompactString {
-        self.into_iter()
-            .fold(CompactString::const_new(""), |mut s, item| {
-                s.push_str(item.as_ref());
-                s
-            })
+        self.try_concat_compact().unwrap_with_msg()
     }
 
     fn join_compact<S: AsRef<str>>(self, separator: S) -> CompactString {
+        self.try_join_compact(separator).unwrap_with_msg()
+    }
+
+    fn try_concat_compact(self) -> Result<CompactString, ReserveError> {
+        let mut compact_string = CompactString::const_new("");
+        compact_string.try_extend(self)?;
+        Ok(compact_string)
+    }
+
+    fn try_join_compact<S: AsRef<str>>(self, separator: S) -> Result<CompactString, ReserveError> {
         let mut compact_string = CompactString::const_new("");
 
         let mut iter = self.into_iter().peekable();
         let sep = separator.as_ref();
 
         while let Some(item) = iter.next() {
-            compact_string.push_str(item.as_ref());
+            compact_string.try_push_str(item.as_ref())?;
             if iter.peek().is_some() {
-                compact_string.push_str(sep);
+                compact_string.try_push_str(sep)?;
             }
         }
 
-        compact_string
+        Ok(compact_string)
     }
 }
 
+/// A trait that provides methods on [`str`](prim@str) which return [`CompactString`]s directly, instead of
+/// going through [`ToCompactString`] or allocating an intermediate [`String`].
+///
+/// This complements [`CompactStringExt`], and since [`CompactString`] derefs to a [`str`](prim@str), all of
+/// these methods are available on [`CompactString`] too.
+///
+/// ```
+/// use compact_str::CompactStrExt;
+///
+/// let input = "  Hello World  ";
+///
+/// assert_eq!(input.trim_compact(), "Hello World");
+/// assert_eq!(input.to_ascii_lowercase_compact(), "  hello world  ");
+///
+/// let words: Vec<_> = input.split_whitespace_compact().collect();
+/// assert_eq!(words, ["Hello", "World"]);
+/// ```
+pub trait CompactStrExt {
+    /// Returns a [`CompactString`] with leading and trailing whitespace removed, see
+    /// [`str::trim`].
+    fn trim_compact(&self) -> CompactString;
+
+    /// Returns a [`CompactString`] with leading whitespace removed, see [`str::trim_start`].
+    fn trim_start_compact(&self) -> CompactString;
+
+    /// Returns a [`CompactString`] with trailing whitespace removed, see [`str::trim_end`].
+    fn trim_end_compact(&self) -> CompactString;
+
+    /// Returns an iterator of [`CompactString`]s, separated by `separator`, see [`str::split`].
+    ///
+    /// # Example
+    /// ```
+    /// use compact_str::CompactStrExt;
+    ///
+    /// let parts: Vec<_> = "a,b,,c".split_compact(",").collect();
+    /// assert_eq!(parts, ["a", "b", "", "c"]);
+    /// ```
+    fn split_compact<'a>(&'a self, separator: &'a str) -> CompactStrIter<str::Split<'a, &'a str>>;
+
+    /// Returns an iterator of [`CompactString`]s, separated by any amount of whitespace, see
+    /// [`str::split_whitespace`].
+    fn split_whitespace_compact(&self) -> CompactStrIter<str::SplitWhitespace<'_>>;
+
+    /// Returns an iterator over the lines of a string as [`CompactString`]s, see [`str::lines`].
+    ///
+    /// # Example
+    /// ```
+    /// use compact_str::CompactStrExt;
+    ///
+    /// let lines: Vec<_> = "foo\r\nbar\n\nbaz\n".lines_compact().collect();
+    /// assert_eq!(lines, ["foo", "bar", "", "baz"]);
+    /// ```
+    fn lines_compact(&self) -> CompactStrIter<str::Lines<'_>>;
+
+    /// Returns a [`CompactString`] where each character is mapped to its ASCII lower case
+    /// equivalent, see [`str::to_ascii_lowercase`].
+    fn to_ascii_lowercase_compact(&self) -> CompactString;
+
+    /// Returns a [`CompactString`] where each character is mapped to its ASCII upper case
+    /// equivalent, see [`str::to_ascii_uppercase`].
+    fn to_ascii_uppercase_compact(&self) -> CompactString;
+
+    /// Returns the lowercase equivalent of this string as a [`CompactString`], see
+    /// [`str::to_lowercase`].
+    fn to_lowercase_compact(&self) -> CompactString;
+
+    /// Returns the uppercase equivalent of this string as a [`CompactString`], see
+    /// [`str::to_uppercase`].
+    fn to_uppercase_compact(&self) -> CompactString;
+}
+
+impl CompactStrExt for str {
+    #[inline]
+    fn trim_compact(&self) -> CompactString {
+        CompactString(Repr::new_panic(self.trim()))
+    }
+
+    #[inline]
+    fn trim_start_compact(&self) -> CompactString {
+        CompactString(Repr::new_panic(self.trim_start()))
+    }
+
+    #[inline]
+    fn trim_end_compact(&self) -> CompactString {
+        CompactString(Repr::new_panic(self.trim_end()))
+    }
+
+    #[inline]
+    fn split_compact<'a>(&'a self, separator: &'a str) -> CompactStrIter<str::Split<'a, &'a str>> {
+        CompactStrIter(self.split(separator))
+    }
+
+    #[inline]
+    fn split_whitespace_compact(&self) -> CompactStrIter<str::SplitWhitespace<'_>> {
+        CompactStrIter(self.split_whitespace())
+    }
+
+    #[inline]
+    fn lines_compact(&self) -> CompactStrIter<str::Lines<'_>> {
+        CompactStrIter(self.lines())
+    }
+
+    #[inline]
+    fn to_ascii_lowercase_compact(&self) -> CompactString {
+        let mut s = CompactString(Repr::new_panic(self));
+        s.make_ascii_lowercase();
+        s
+    }
+
+    #[inline]
+    fn to_ascii_uppercase_compact(&self) -> CompactString {
+        let mut s = CompactString(Repr::new_panic(self));
+        s.make_ascii_uppercase();
+        s
+    }
+
+    #[inline]
+    fn to_lowercase_compact(&self) -> CompactString {
+        CompactString::from_str_to_lowercase(self)
+    }
+
+    #[inline]
+    fn to_uppercase_compact(&self) -> CompactString {
+        CompactString::from_str_to_uppercase(self)
+    }
+}
+
+/// An iterator that converts each `&str` of the inner iterator into a [`CompactString`].
+///
+/// Returned from the iterator methods of [`CompactStrExt`].
+#[derive(Clone, Debug)]
+pub struct CompactStrIter<I>(I);
+
+impl<'a, I: Iterator<Item = &'a str>> Iterator for CompactStrIter<I> {
+    type Item = CompactString;
+
+    #[inline]
+    fn next(&mut self) -> Option<CompactString> {
+        self.0.next().map(|s| CompactString(Repr::new_panic(s)))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, I: DoubleEndedIterator<Item = &'a str>> DoubleEndedIterator for CompactStrIter<I> {
+    #[inline]
+    fn next_back(&mut self) -> Option<CompactString> {
+        self.0
+            .next_back()
+            .map(|s| CompactString(Repr::new_panic(s)))
+    }
+}
+
+impl<'a, I: FusedIterator<Item = &'a str>> FusedIterator for CompactStrIter<I> {}
+
 #[cfg(test)]
 mod tests {
     use alloc::string::{String, ToString};
@@ -219,7 +570,7 @@ mod tests {
     use proptest::prelude::*;
     use test_strategy::proptest;
 
-    use super::{CompactStringExt, ToCompactString};
+    use super::{CompactStrExt, CompactStringExt, ToCompactString};
     use crate::CompactString;
 
     #[test]
@@ -483,4 +834,38 @@ mod tests {
         let compact = val.to_compact_string();
         prop_assert_eq!(compact.as_str(), val.to_string());
     }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_compact_str_ext(#[strategy("[a \\n\\rÄ,]{0,60}")] s: String) {
+        prop_assert_eq!(s.trim_compact(), s.trim());
+        prop_assert_eq!(s.trim_start_compact(), s.trim_start());
+        prop_assert_eq!(s.trim_end_compact(), s.trim_end());
+        prop_assert_eq!(s.to_ascii_lowercase_compact(), s.to_ascii_lowercase());
+        prop_assert_eq!(s.to_ascii_uppercase_compact(), s.to_ascii_uppercase());
+        prop_assert_eq!(s.to_lowercase_compact(), s.to_lowercase());
+        prop_assert_eq!(s.to_uppercase_compact(), s.to_uppercase());
+
+        let split: Vec<CompactString> = s.split_compact(",").collect();
+        prop_assert_eq!(split, s.split(',').collect::<Vec<_>>());
+        let split: Vec<CompactString> = s.split_whitespace_compact().rev().collect();
+        prop_assert_eq!(split, s.split_whitespace().rev().collect::<Vec<_>>());
+        let lines: Vec<CompactString> = s.lines_compact().collect();
+        prop_assert_eq!(lines, s.lines().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_compact_str_ext_on_compact_string() {
+        let compact = CompactString::new("  a string that is long enough to be heap allocated  ");
+        let trimmed = compact.trim_compact();
+        assert_eq!(trimmed, "a string that is long enough to be heap allocated");
+        assert!(trimmed.is_heap_allocated());
+
+        let words: Vec<_> = compact
+            .split_compact(" ")
+            .filter(|w| !w.is_empty())
+            .collect();
+        assert_eq!(words.len(), 10);
+        assert!(words.iter().all(|w| !w.is_heap_allocated()));
+    }
 }
@@ -1,5 +1,9 @@
 //! Adapted from
 //! <https://doc.rust-lang.org/nightly/src/core/unicode/unicode_data.rs.html>
+//!
+//! `case_ignorable` and `cased` are copied from `core`. The tables from `combining_mark` onwards
+//! are for Unicode 17.0.0, and are generated from the UCD files by `scripts/unicode_data.py`, which
+//! explains how to run it.
 
 #[inline(always)]
 fn skip_search<const SOR: usize, const OFFSETS: usize>(
@@ -137,3 +141,248 @@ pub(crate) mod cased {
         )
     }
 }
+
+#[rustfmt::skip]
+pub(crate) mod combining_mark {
+    static SHORT_OFFSET_RUNS: [u32; 31] = [
+        768, 2098307, 10487185, 14685021, 278927124, 283123920, 396373231, 408956970, 421570159,
+        429959174, 446757662, 509672992, 513868285, 518064653, 530648356, 551620267, 560011321,
+        681646397, 685841471, 711007554, 715202369, 727802159, 731998960, 736194544, 744602782,
+        748802405, 753000448, 778166958, 807527660, 815917264, 842072064,
+    ];
+    static OFFSETS: [u8; 405] = [
+        0, 79, 1, 32, 0, 5, 0, 45, 1, 1, 1, 2, 1, 2, 1, 1, 72, 11, 48, 21, 16, 1, 101, 7, 2, 6, 2,
+        2, 1, 4, 35, 1, 30, 27, 160, 9, 9, 1, 24, 4, 1, 9, 1, 3, 1, 5, 43, 3, 59, 9, 42, 24, 1, 29,
+        60, 1, 16, 1, 3, 4, 103, 1, 16, 1, 48, 1, 61, 1, 16, 1, 110, 1, 16, 1, 110, 1, 16, 1, 127,
+        1, 110, 1, 16, 1, 7, 2, 101, 1, 16, 1, 109, 2, 16, 1, 124, 1, 109, 3, 13, 4, 108, 3, 13, 4,
+        76, 2, 27, 1, 1, 1, 1, 1, 55, 2, 1, 1, 5, 4, 2, 1, 1, 3, 1, 2, 62, 1, 112, 1, 1, 2, 82, 1,
+        0, 3, 0, 2, 30, 1, 157, 1, 10, 1, 203, 1, 143, 3, 219, 2, 71, 1, 20, 8, 2, 1, 48, 14, 1,
+        31, 2, 12, 72, 1, 15, 1, 38, 9, 54, 2, 58, 1, 11, 2, 67, 1, 152, 3, 1, 13, 1, 7, 4, 1, 6,
+        1, 3, 2, 198, 64, 0, 13, 4, 1, 3, 12, 0, 3, 141, 1, 96, 32, 0, 6, 105, 2, 0, 1, 4, 10, 32,
+        2, 80, 2, 0, 1, 37, 1, 151, 1, 27, 18, 57, 3, 37, 1, 95, 1, 12, 1, 239, 1, 1, 3, 2, 2, 5,
+        2, 1, 1, 52, 1, 246, 1, 0, 1, 0, 16, 0, 1, 226, 1, 149, 5, 0, 1, 1, 1, 40, 3, 4, 1, 165, 2,
+        0, 4, 65, 5, 0, 2, 77, 2, 1, 3, 70, 11, 49, 4, 192, 1, 41, 1, 14, 1, 57, 2, 69, 3, 48, 2,
+        62, 1, 76, 1, 9, 1, 106, 2, 178, 2, 80, 2, 16, 1, 24, 7, 3, 5, 89, 3, 113, 1, 3, 1, 23, 1,
+        99, 2, 251, 2, 126, 1, 118, 2, 115, 1, 0, 2, 0, 2, 4, 1, 156, 1, 83, 1, 18, 1, 81, 1, 0, 1,
+        0, 1, 1, 2, 81, 1, 0, 2, 0, 1, 0, 5, 59, 7, 0, 2, 0, 1, 0, 5, 3, 6, 8, 8, 2, 7, 30, 4, 148,
+        3, 0, 7, 1, 17, 2, 7, 1, 2, 1, 5, 100, 1, 160, 7, 0, 1, 61, 4, 0, 4, 254, 2, 243, 1, 2, 1,
+        7, 2, 5, 1, 0, 7, 109, 7, 0,
+    ];
+    #[inline(always)]
+    pub(crate) fn lookup(c: char) -> bool {
+        super::skip_search(
+            c as u32,
+            &SHORT_OFFSET_RUNS,
+            &OFFSETS,
+        )
+    }
+}
+
+#[rustfmt::skip]
+pub(crate) mod combining_above {
+    static SHORT_OFFSET_RUNS: [u32; 27] = [
+        768, 2098307, 35653010, 39849858, 228594525, 236984285, 241178938, 245374160, 295706832,
+        341847279, 371238511, 379627744, 396405424, 400621088, 421593974, 429984271, 434179364,
+        446762667, 455151872, 476124006, 480340784, 492949893, 497147904, 509731502, 539092207,
+        547481924, 573636608,
+    ];
+    static OFFSETS: [u8; 275] = [
+        0, 21, 40, 8, 1, 1, 3, 3, 3, 3, 4, 1, 3, 1, 7, 13, 0, 5, 0, 4, 1, 3, 2, 6, 6, 2, 1, 2, 2,
+        1, 20, 1, 75, 8, 59, 2, 2, 5, 1, 2, 119, 7, 2, 4, 1, 1, 2, 2, 2, 2, 67, 1, 1, 2, 1, 2, 3,
+        1, 2, 1, 1, 3, 1, 1, 1, 1, 1, 1, 1, 2, 160, 7, 1, 1, 34, 4, 1, 9, 1, 3, 1, 5, 105, 2, 3, 4,
+        42, 5, 5, 14, 2, 2, 1, 2, 1, 3, 6, 3, 1, 2, 2, 5, 81, 1, 1, 2, 169, 1, 0, 2, 2, 2, 0, 3, 0,
+        1, 0, 1, 220, 1, 93, 8, 51, 5, 6, 2, 4, 2, 2, 5, 1, 18, 3, 6, 1, 4, 128, 1, 1, 7, 0, 3, 7,
+        2, 4, 1, 19, 1, 3, 2, 198, 2, 1, 7, 1, 2, 4, 37, 5, 1, 2, 1, 0, 2, 2, 4, 3, 2, 4, 1, 5, 1,
+        1, 1, 6, 1, 0, 3, 238, 32, 0, 1, 4, 10, 32, 2, 80, 2, 0, 18, 0, 1, 1, 2, 3, 2, 5, 2, 1, 1,
+        0, 7, 7, 2, 0, 5, 0, 1, 40, 1, 172, 1, 0, 4, 65, 5, 0, 2, 155, 3, 1, 1, 53, 1, 1, 1, 0, 3,
+        0, 7, 3, 5, 233, 1, 0, 7, 0, 5, 32, 4, 148, 3, 0, 7, 1, 17, 2, 7, 1, 2, 1, 5, 100, 1, 160,
+        7, 0, 1, 61, 4, 0, 1, 254, 1, 244, 1, 2, 1, 7, 2, 5, 1, 0, 6, 0,
+    ];
+    #[inline(always)]
+    pub(crate) fn lookup(c: char) -> bool {
+        super::skip_search(
+            c as u32,
+            &SHORT_OFFSET_RUNS,
+            &OFFSETS,
+        )
+    }
+}
+
+#[rustfmt::skip]
+pub(crate) mod soft_dotted {
+    static SHORT_OFFSET_RUNS: [u32; 9] = [
+        585, 10486771, 27270498, 39854193, 65023100, 73520162, 77717274, 132243532, 137428992,
+    ];
+    static OFFSETS: [u8; 69] = [
+        105, 2, 196, 1, 0, 1, 30, 1, 52, 1, 20, 1, 0, 1, 98, 1, 1, 1, 0, 1, 51, 1, 13, 1, 3, 1,
+        132, 1, 157, 1, 0, 1, 214, 2, 0, 1, 0, 2, 50, 2, 50, 2, 50, 2, 50, 2, 50, 2, 50, 2, 50, 2,
+        50, 2, 50, 2, 50, 2, 50, 2, 50, 2, 0, 1, 0, 2, 26, 1, 0,
+    ];
+    #[inline(always)]
+    pub(crate) fn lookup(c: char) -> bool {
+        super::skip_search(
+            c as u32,
+            &SHORT_OFFSET_RUNS,
+            &OFFSETS,
+        )
+    }
+}
+
+#[rustfmt::skip]
+pub(crate) mod case_folding {
+    /// The Cherokee capital letters U+13A0 to U+13F5, which both cases fold to.
+    static CHEROKEE: &str = "\
+        \u{13a0}\u{13a1}\u{13a2}\u{13a3}\u{13a4}\u{13a5}\u{13a6}\u{13a7}\u{13a8}\u{13a9}\u{13aa}\
+        \u{13ab}\u{13ac}\u{13ad}\u{13ae}\u{13af}\u{13b0}\u{13b1}\u{13b2}\u{13b3}\u{13b4}\u{13b5}\
+        \u{13b6}\u{13b7}\u{13b8}\u{13b9}\u{13ba}\u{13bb}\u{13bc}\u{13bd}\u{13be}\u{13bf}\u{13c0}\
+        \u{13c1}\u{13c2}\u{13c3}\u{13c4}\u{13c5}\u{13c6}\u{13c7}\u{13c8}\u{13c9}\u{13ca}\u{13cb}\
+        \u{13cc}\u{13cd}\u{13ce}\u{13cf}\u{13d0}\u{13d1}\u{13d2}\u{13d3}\u{13d4}\u{13d5}\u{13d6}\
+        \u{13d7}\u{13d8}\u{13d9}\u{13da}\u{13db}\u{13dc}\u{13dd}\u{13de}\u{13df}\u{13e0}\u{13e1}\
+        \u{13e2}\u{13e3}\u{13e4}\u{13e5}\u{13e6}\u{13e7}\u{13e8}\u{13e9}\u{13ea}\u{13eb}\u{13ec}\
+        \u{13ed}\u{13ee}\u{13ef}\u{13f0}\u{13f1}\u{13f2}\u{13f3}\u{13f4}\u{13f5}";
+    /// The full (`C` and `F`) mappings from `CaseFolding.txt` that differ from
+    /// `char::to_lowercase`, sorted by character. Cherokee is handled separately.
+    static FOLDINGS: [(char, &str); 125] = [
+        ('\u{b5}', "\u{3bc}"),
+        ('\u{df}', "ss"),
+        ('\u{149}', "\u{2bc}n"),
+        ('\u{17f}', "s"),
+        ('\u{1f0}', "j\u{30c}"),
+        ('\u{345}', "\u{3b9}"),
+        ('\u{390}', "\u{3b9}\u{308}\u{301}"),
+        ('\u{3b0}', "\u{3c5}\u{308}\u{301}"),
+        ('\u{3c2}', "\u{3c3}"),
+        ('\u{3d0}', "\u{3b2}"),
+        ('\u{3d1}', "\u{3b8}"),
+        ('\u{3d5}', "\u{3c6}"),
+        ('\u{3d6}', "\u{3c0}"),
+        ('\u{3f0}', "\u{3ba}"),
+        ('\u{3f1}', "\u{3c1}"),
+        ('\u{3f5}', "\u{3b5}"),
+        ('\u{587}', "\u{565}\u{582}"),
+        ('\u{1c80}', "\u{432}"),
+        ('\u{1c81}', "\u{434}"),
+        ('\u{1c82}', "\u{43e}"),
+        ('\u{1c83}', "\u{441}"),
+        ('\u{1c84}', "\u{442}"),
+        ('\u{1c85}', "\u{442}"),
+        ('\u{1c86}', "\u{44a}"),
+        ('\u{1c87}', "\u{463}"),
+        ('\u{1c88}', "\u{a64b}"),
+        ('\u{1e96}', "h\u{331}"),
+        ('\u{1e97}', "t\u{308}"),
+        ('\u{1e98}', "w\u{30a}"),
+        ('\u{1e99}', "y\u{30a}"),
+        ('\u{1e9a}', "a\u{2be}"),
+        ('\u{1e9b}', "\u{1e61}"),
+        ('\u{1e9e}', "ss"),
+        ('\u{1f50}', "\u{3c5}\u{313}"),
+        ('\u{1f52}', "\u{3c5}\u{313}\u{300}"),
+        ('\u{1f54}', "\u{3c5}\u{313}\u{301}"),
+        ('\u{1f56}', "\u{3c5}\u{313}\u{342}"),
+        ('\u{1f80}', "\u{1f00}\u{3b9}"),
+        ('\u{1f81}', "\u{1f01}\u{3b9}"),
+        ('\u{1f82}', "\u{1f02}\u{3b9}"),
+        ('\u{1f83}', "\u{1f03}\u{3b9}"),
+        ('\u{1f84}', "\u{1f04}\u{3b9}"),
+        ('\u{1f85}', "\u{1f05}\u{3b9}"),
+        ('\u{1f86}', "\u{1f06}\u{3b9}"),
+        ('\u{1f87}', "\u{1f07}\u{3b9}"),
+        ('\u{1f88}', "\u{1f00}\u{3b9}"),
+        ('\u{1f89}', "\u{1f01}\u{3b9}"),
+        ('\u{1f8a}', "\u{1f02}\u{3b9}"),
+        ('\u{1f8b}', "\u{1f03}\u{3b9}"),
+        ('\u{1f8c}', "\u{1f04}\u{3b9}"),
+        ('\u{1f8d}', "\u{1f05}\u{3b9}"),
+        ('\u{1f8e}', "\u{1f06}\u{3b9}"),
+        ('\u{1f8f}', "\u{1f07}\u{3b9}"),
+        ('\u{1f90}', "\u{1f20}\u{3b9}"),
+        ('\u{1f91}', "\u{1f21}\u{3b9}"),
+        ('\u{1f92}', "\u{1f22}\u{3b9}"),
+        ('\u{1f93}', "\u{1f23}\u{3b9}"),
+        ('\u{1f94}', "\u{1f24}\u{3b9}"),
+        ('\u{1f95}', "\u{1f25}\u{3b9}"),
+        ('\u{1f96}', "\u{1f26}\u{3b9}"),
+        ('\u{1f97}', "\u{1f27}\u{3b9}"),
+        ('\u{1f98}', "\u{1f20}\u{3b9}"),
+        ('\u{1f99}', "\u{1f21}\u{3b9}"),
+        ('\u{1f9a}', "\u{1f22}\u{3b9}"),
+        ('\u{1f9b}', "\u{1f23}\u{3b9}"),
+        ('\u{1f9c}', "\u{1f24}\u{3b9}"),
+        ('\u{1f9d}', "\u{1f25}\u{3b9}"),
+        ('\u{1f9e}', "\u{1f26}\u{3b9}"),
+        ('\u{1f9f}', "\u{1f27}\u{3b9}"),
+        ('\u{1fa0}', "\u{1f60}\u{3b9}"),
+        ('\u{1fa1}', "\u{1f61}\u{3b9}"),
+        ('\u{1fa2}', "\u{1f62}\u{3b9}"),
+        ('\u{1fa3}', "\u{1f63}\u{3b9}"),
+        ('\u{1fa4}', "\u{1f64}\u{3b9}"),
+        ('\u{1fa5}', "\u{1f65}\u{3b9}"),
+        ('\u{1fa6}', "\u{1f66}\u{3b9}"),
+        ('\u{1fa7}', "\u{1f67}\u{3b9}"),
+        ('\u{1fa8}', "\u{1f60}\u{3b9}"),
+        ('\u{1fa9}', "\u{1f61}\u{3b9}"),
+        ('\u{1faa}', "\u{1f62}\u{3b9}"),
+        ('\u{1fab}', "\u{1f63}\u{3b9}"),
+        ('\u{1fac}', "\u{1f64}\u{3b9}"),
+        ('\u{1fad}', "\u{1f65}\u{3b9}"),
+        ('\u{1fae}', "\u{1f66}\u{3b9}"),
+        ('\u{1faf}', "\u{1f67}\u{3b9}"),
+        ('\u{1fb2}', "\u{1f70}\u{3b9}"),
+        ('\u{1fb3}', "\u{3b1}\u{3b9}"),
+        ('\u{1fb4}', "\u{3ac}\u{3b9}"),
+        ('\u{1fb6}', "\u{3b1}\u{342}"),
+        ('\u{1fb7}', "\u{3b1}\u{342}\u{3b9}"),
+        ('\u{1fbc}', "\u{3b1}\u{3b9}"),
+        ('\u{1fbe}', "\u{3b9}"),
+        ('\u{1fc2}', "\u{1f74}\u{3b9}"),
+        ('\u{1fc3}', "\u{3b7}\u{3b9}"),
+        ('\u{1fc4}', "\u{3ae}\u{3b9}"),
+        ('\u{1fc6}', "\u{3b7}\u{342}"),
+        ('\u{1fc7}', "\u{3b7}\u{342}\u{3b9}"),
+        ('\u{1fcc}', "\u{3b7}\u{3b9}"),
+        ('\u{1fd2}', "\u{3b9}\u{308}\u{300}"),
+        ('\u{1fd3}', "\u{3b9}\u{308}\u{301}"),
+        ('\u{1fd6}', "\u{3b9}\u{342}"),
+        ('\u{1fd7}', "\u{3b9}\u{308}\u{342}"),
+        ('\u{1fe2}', "\u{3c5}\u{308}\u{300}"),
+        ('\u{1fe3}', "\u{3c5}\u{308}\u{301}"),
+        ('\u{1fe4}', "\u{3c1}\u{313}"),
+        ('\u{1fe6}', "\u{3c5}\u{342}"),
+        ('\u{1fe7}', "\u{3c5}\u{308}\u{342}"),
+        ('\u{1ff2}', "\u{1f7c}\u{3b9}"),
+        ('\u{1ff3}', "\u{3c9}\u{3b9}"),
+        ('\u{1ff4}', "\u{3ce}\u{3b9}"),
+        ('\u{1ff6}', "\u{3c9}\u{342}"),
+        ('\u{1ff7}', "\u{3c9}\u{342}\u{3b9}"),
+        ('\u{1ffc}', "\u{3c9}\u{3b9}"),
+        ('\u{fb00}', "ff"),
+        ('\u{fb01}', "fi"),
+        ('\u{fb02}', "fl"),
+        ('\u{fb03}', "ffi"),
+        ('\u{fb04}', "ffl"),
+        ('\u{fb05}', "st"),
+        ('\u{fb06}', "st"),
+        ('\u{fb13}', "\u{574}\u{576}"),
+        ('\u{fb14}', "\u{574}\u{565}"),
+        ('\u{fb15}', "\u{574}\u{56b}"),
+        ('\u{fb16}', "\u{57e}\u{576}"),
+        ('\u{fb17}', "\u{574}\u{56d}"),
+    ];
+    /// Returns the full case folding of `c`, if it's different from `c.to_lowercase()`.
+    #[inline]
+    pub(crate) fn lookup(c: char) -> Option<&'static str> {
+        // every Cherokee capital letter is 3 bytes long in UTF-8
+        let cherokee = |i: u32| Some(&CHEROKEE[i as usize * 3..][..3]);
+        match c as u32 {
+            0..=0xb4 => None,
+            c @ 0x13a0..=0x13f5 => cherokee(c - 0x13a0),
+            c @ 0x13f8..=0x13fd => cherokee(c - 0x13f8 + 0x50),
+            c @ 0xab70..=0xabbf => cherokee(c - 0xab70),
+            _ => FOLDINGS
+                .binary_search_by_key(&c, |(from, _)| *from)
+                .ok()
+                .map(|i| FOLDINGS[i].1),
+        }
+    }
+}
@@ -50,6 +50,14 @@ pub enum Action<'a> {
     Repeat(usize),
     /// Zero out the data backing the string.
     Zeroize,
+    /// Replace all matches of a pattern with a string
+    Replace(&'a str, &'a str),
+    /// Replace the first n matches of a pattern with a string
+    ReplaceN(&'a str, &'a str, u8),
+    /// Replace all occurrences of a character with another character
+    ReplaceChar(char, char),
+    /// Replace all matches of a pattern with a string, in place
+    ReplaceInPlace(&'a str, &'a str),
 }
 
 impl Action<'_> {
@@ -412,10 +420,81 @@ impl Action<'_> {
                 control.zeroize();
                 compact.zeroize();
             }
+            Replace(from, to) => {
+                // If we'd grow larger than our limit, no-op.
+                if replaced_len(control, from, to) > super::TWENTY_FOUR_MIB_AS_BYTES {
+                    return;
+                }
+
+                let new_compact = compact.replace(from, to);
+                let new_control = control.replace(from, to);
+
+                assert_eq!(new_compact, new_control);
+                super::assert_properly_allocated(&new_compact, &new_control);
+
+                *compact = new_compact;
+                *control = new_control;
+            }
+            ReplaceN(from, to, count) => {
+                // If we'd grow larger than our limit, no-op.
+                if replaced_len(control, from, to) > super::TWENTY_FOUR_MIB_AS_BYTES {
+                    return;
+                }
+
+                let new_compact = compact.replacen(from, to, count as usize);
+                let new_control = control.replacen(from, to, count as usize);
+
+                assert_eq!(new_compact, new_control);
+                super::assert_properly_allocated(&new_compact, &new_control);
+
+                *compact = new_compact;
+                *control = new_control;
+            }
+            ReplaceChar(from, to) => {
+                let new_compact = compact.replace_char(from, to);
+                let new_control = control.replace(from, to.encode_utf8(&mut [0; 4]));
+
+                assert_eq!(new_compact, new_control);
+                super::assert_properly_allocated(&new_compact, &new_control);
+
+                *compact = new_compact;
+                *control = new_control;
+            }
+            ReplaceInPlace(from, to) => {
+                // If we'd grow larger than our limit, no-op.
+                if replaced_len(control, from, to) > super::TWENTY_FOUR_MIB_AS_BYTES {
+                    return;
+                }
+
+                let og_capacity = compact.capacity();
+                let is_static = compact.as_static_str().is_some();
+
+                *control = control.replace(from, to);
+                compact.replace_in_place(from, to);
+
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+
+                // shrinking, or same size, replacements never reallocate
+                if !from.is_empty() && to.len() <= from.len() && !is_static {
+                    assert_eq!(compact.capacity(), og_capacity);
+                }
+            }
         }
     }
 }
 
+/// An upper bound on the length of `s` after replacing every match of `from` with `to`.
+fn replaced_len(s: &str, from: &str, to: &str) -> usize {
+    // an empty pattern matches at every char boundary
+    let max_matches = if from.is_empty() {
+        s.chars().count() + 1
+    } else {
+        s.len() / from.len()
+    };
+    s.len().saturating_add(max_matches.saturating_mul(to.len()))
+}
+
 fn to_index(s: &str, idx: u8) -> usize {
     s.char_indices()
         .map(|(idx, _)| idx)